@@ -0,0 +1,41 @@
+//! Conversions for `#[func]` arguments that the backend thread cannot take as Godot types.
+
+use godot::classes::{Json, ProjectSettings};
+use godot::prelude::*;
+
+/// Resolves a Godot path to an OS path the backend thread can write to.
+///
+/// Mirrors `WryPwSession._normalize_output_path`: `res://` and `user://` are globalized,
+/// absolute paths are kept and relative paths land under `user://`.
+pub(crate) fn output_path(path: &GString) -> String {
+  let raw = path.to_string();
+  let trimmed = raw.trim();
+  if trimmed.is_empty() {
+    return String::new();
+  }
+
+  let settings = ProjectSettings::singleton();
+  if trimmed.starts_with("res://") || trimmed.starts_with("user://") {
+    return settings.globalize_path(trimmed).to_string();
+  }
+  if std::path::Path::new(trimmed).is_absolute() {
+    return trimmed.to_string();
+  }
+  settings.globalize_path(&format!("user://{trimmed}")).to_string()
+}
+
+/// Serializes an options dictionary for `serde` parsing on the Rust side.
+pub(crate) fn dictionary_json(options: &Dictionary) -> String {
+  if options.is_empty() {
+    return "{}".to_string();
+  }
+  Json::stringify(&options.to_variant()).to_string()
+}
+
+/// Serializes an array argument (e.g. a list of dictionaries) for `serde` parsing.
+pub(crate) fn array_json(values: &VariantArray) -> String {
+  if values.is_empty() {
+    return "[]".to_string();
+  }
+  Json::stringify(&values.to_variant()).to_string()
+}
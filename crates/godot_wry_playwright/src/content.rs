@@ -0,0 +1,38 @@
+use crate::storage_state::origin_of;
+
+/// WebView2 `NavigateToString` rejects documents larger than 2 MB.
+pub const MAX_CONTENT_BYTES: usize = 2 * 1024 * 1024;
+
+/// A `set_content` document and where it is loaded from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Content {
+  pub html: String,
+  /// URL the document is served at, so it gets that URL and origin; `None` loads it from a string
+  /// with an opaque origin.
+  pub url: Option<String>,
+}
+
+/// `url` as the backend sees it in a document request: lowercased origin without default port,
+/// at least a `/` path and no fragment. `None` for anything but http(s).
+pub fn document_url(url: &str) -> Option<String> {
+  let origin = origin_of(url)?;
+  let (_, rest) = url.trim().split_once("://")?;
+  let tail = &rest[rest.find(['/', '?', '#']).unwrap_or(rest.len())..];
+  let tail = tail.split('#').next().unwrap_or_default();
+  Some(if tail.starts_with('/') { format!("{origin}{tail}") } else { format!("{origin}/{tail}") })
+}
+
+/// Validates a `set_content` call. With a `base_url` the document is served at that http(s) URL,
+/// so relative links, storage and cookies behave as on the real page; without one it is loaded
+/// from a string and limited to [`MAX_CONTENT_BYTES`].
+pub fn prepare(html: &str, base_url: &str) -> Result<Content, String> {
+  let base_url = base_url.trim();
+  if !base_url.is_empty() {
+    let url = document_url(base_url).ok_or_else(|| format!("content_base_url_invalid: {base_url}"))?;
+    return Ok(Content { html: html.to_string(), url: Some(url) });
+  }
+  if html.len() > MAX_CONTENT_BYTES {
+    return Err(format!("content_too_large: {} bytes (max {MAX_CONTENT_BYTES})", html.len()));
+  }
+  Ok(Content { html: html.to_string(), url: None })
+}
@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SameSite {
+  #[default]
+  #[serde(alias = "lax")]
+  Lax,
+  #[serde(alias = "strict")]
+  Strict,
+  #[serde(alias = "none")]
+  None,
+}
+
+/// A cookie as reported by the native cookie manager, in Playwright's field layout.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Cookie {
+  pub name: String,
+  pub value: String,
+  pub domain: String,
+  pub path: String,
+  /// Unix time in seconds; `-1` for session cookies.
+  pub expires: f64,
+  pub http_only: bool,
+  pub secure: bool,
+  pub same_site: SameSite,
+}
+
+/// A cookie passed to `cookies_set`: either `url` or `domain` must be given.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct CookieParam {
+  pub name: String,
+  pub value: String,
+  pub url: Option<String>,
+  pub domain: Option<String>,
+  pub path: Option<String>,
+  pub expires: Option<f64>,
+  #[serde(alias = "http_only")]
+  pub http_only: bool,
+  pub secure: Option<bool>,
+  #[serde(alias = "same_site")]
+  pub same_site: Option<SameSite>,
+}
+
+/// Splits `scheme://host[:port]/path?query` into `(scheme, host, path)`.
+fn split_url(url: &str) -> Option<(&str, &str, &str)> {
+  let (scheme, rest) = url.split_once("://")?;
+  let end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+  let authority = &rest[..end];
+  let host_port = authority.rsplit_once('@').map_or(authority, |(_, h)| h);
+  let host = if host_port.starts_with('[') {
+    host_port.split_inclusive(']').next().unwrap_or(host_port)
+  } else {
+    host_port.split(':').next().unwrap_or(host_port)
+  };
+  let path = rest[end..].split(['?', '#']).next().unwrap_or("");
+  if host.is_empty() {
+    return None;
+  }
+  Some((scheme, host, path))
+}
+
+impl CookieParam {
+  pub fn list_from_json(cookies_json: &str) -> Result<Vec<Self>, String> {
+    serde_json::from_str(cookies_json).map_err(|e| format!("cookies_invalid: {e}"))
+  }
+
+  /// Fills `domain`, `path` and `secure` from `url` where they were not given explicitly.
+  pub fn resolve(self) -> Result<Cookie, String> {
+    if self.name.is_empty() {
+      return Err("cookie_missing_name".to_string());
+    }
+
+    let mut domain = self.domain.filter(|d| !d.is_empty());
+    let mut path = self.path.filter(|p| !p.is_empty());
+    let mut secure = self.secure;
+    if let Some(url) = self.url.as_deref().filter(|u| !u.is_empty()) {
+      let (scheme, host, url_path) = split_url(url).ok_or_else(|| format!("cookie_invalid_url: {url}"))?;
+      domain.get_or_insert_with(|| host.to_string());
+      path.get_or_insert_with(|| match url_path.rfind('/') {
+        Some(i) if i > 0 => url_path[..i].to_string(),
+        _ => "/".to_string(),
+      });
+      secure.get_or_insert(scheme.eq_ignore_ascii_case("https"));
+    }
+
+    let domain = domain.ok_or_else(|| format!("cookie_missing_domain: {}", self.name))?;
+    let same_site = self.same_site.unwrap_or_default();
+    Ok(Cookie {
+      name: self.name,
+      value: self.value,
+      domain,
+      path: path.unwrap_or_else(|| "/".to_string()),
+      expires: self.expires.filter(|e| *e > 0.0).unwrap_or(-1.0),
+      http_only: self.http_only,
+      // Browsers reject `SameSite=None` cookies that are not secure.
+      secure: secure.unwrap_or(false) || same_site == SameSite::None,
+      same_site,
+    })
+  }
+}
+
+/// Selects cookies for `cookies_clear`; unset fields match anything.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct CookieFilter {
+  pub name: Option<String>,
+  pub domain: Option<String>,
+  pub path: Option<String>,
+}
+
+impl CookieFilter {
+  pub fn from_json(filter_json: &str) -> Result<Self, String> {
+    serde_json::from_str(filter_json).map_err(|e| format!("cookies_invalid_filter: {e}"))
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.name.is_none() && self.domain.is_none() && self.path.is_none()
+  }
+
+  pub fn matches(&self, cookie: &Cookie) -> bool {
+    let bare = |d: &str| d.trim_start_matches('.').to_ascii_lowercase();
+    self.name.as_deref().is_none_or(|n| n == cookie.name)
+      && self.domain.as_deref().is_none_or(|d| bare(d) == bare(&cookie.domain))
+      && self.path.as_deref().is_none_or(|p| p == cookie.path)
+  }
+}
+
+/// Merges per-URL results, keeping the first cookie for each `(name, domain, path)`.
+pub fn dedupe(cookies: Vec<Cookie>) -> Vec<Cookie> {
+  let mut out: Vec<Cookie> = Vec::with_capacity(cookies.len());
+  for cookie in cookies {
+    let seen = out
+      .iter()
+      .any(|c| c.name == cookie.name && c.domain == cookie.domain && c.path == cookie.path);
+    if !seen {
+      out.push(cookie);
+    }
+  }
+  out
+}
+
+pub fn cookies_json(cookies: &[Cookie]) -> String {
+  serde_json::to_string(cookies).unwrap_or_else(|_| "[]".to_string())
+}
@@ -0,0 +1,75 @@
+/// JavaScript dialog kinds, named as in Playwright's `dialog.type()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DialogKind {
+  Alert,
+  Confirm,
+  Prompt,
+  BeforeUnload,
+}
+
+impl DialogKind {
+  pub fn as_str(self) -> &'static str {
+    match self {
+      Self::Alert => "alert",
+      Self::Confirm => "confirm",
+      Self::Prompt => "prompt",
+      Self::BeforeUnload => "beforeunload",
+    }
+  }
+}
+
+/// How dialogs are answered when they open. Every dialog is reported through the `dialog` signal.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum DialogPolicy {
+  /// Accept; prompts get `prompt_text`, or their default value when it is empty.
+  Accept { prompt_text: String },
+  /// Dismiss (Cancel), as Playwright does when nobody handles the dialog.
+  #[default]
+  Dismiss,
+  /// Keep the dialog open until `dialog_respond`.
+  Manual,
+}
+
+/// The answer given to an open dialog.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DialogAnswer {
+  pub accept: bool,
+  /// Result text for accepted prompts.
+  pub text: Option<String>,
+}
+
+impl DialogPolicy {
+  pub fn from_name(name: &str, prompt_text: &str) -> Result<Self, String> {
+    match name.trim().to_ascii_lowercase().as_str() {
+      "accept" => Ok(Self::Accept {
+        prompt_text: prompt_text.to_string(),
+      }),
+      "dismiss" | "" => Ok(Self::Dismiss),
+      "manual" => Ok(Self::Manual),
+      other => Err(format!("dialog_policy_invalid: {other}")),
+    }
+  }
+
+  /// The automatic answer, or `None` when the dialog waits for `dialog_respond`.
+  pub fn answer(&self, kind: DialogKind, default_text: &str) -> Option<DialogAnswer> {
+    match self {
+      Self::Accept { prompt_text } => Some(DialogAnswer::new(true, prompt_text, kind, default_text)),
+      Self::Dismiss => Some(DialogAnswer::new(false, "", kind, default_text)),
+      Self::Manual => None,
+    }
+  }
+}
+
+impl DialogAnswer {
+  /// Only accepted prompts carry text; an empty `text` keeps the prompt's default value.
+  pub fn new(accept: bool, text: &str, kind: DialogKind, default_text: &str) -> Self {
+    let text = (accept && kind == DialogKind::Prompt).then(|| {
+      if text.is_empty() {
+        default_text.to_string()
+      } else {
+        text.to_string()
+      }
+    });
+    Self { accept, text }
+  }
+}
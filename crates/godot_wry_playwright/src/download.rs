@@ -0,0 +1,69 @@
+use std::path::{Path, PathBuf};
+
+/// Default download directory; `set_download_dir` overrides it.
+pub const DOWNLOADS_DIR: &str = "user://downloads";
+
+/// Godot path of a download directory, which must stay under `user://`: relative paths land there,
+/// `..`, absolute and `res://` paths fail with `download_dir_outside_user`.
+pub fn download_dir(path: &str) -> Result<String, String> {
+  let path = path.trim();
+  if path.is_empty() {
+    return Err("download_dir_empty".to_string());
+  }
+  let relative = path.strip_prefix("user://").unwrap_or(path);
+  if relative.contains(':') || relative.contains('\\') || relative.starts_with('/') {
+    return Err(format!("download_dir_outside_user: {path}"));
+  }
+  let parts: Vec<&str> = relative.split('/').filter(|part| !part.is_empty() && *part != ".").collect();
+  if parts.contains(&"..") {
+    return Err(format!("download_dir_outside_user: {path}"));
+  }
+  Ok(format!("user://{}", parts.join("/")))
+}
+
+/// Lifecycle of one download, reported through the `download_*` signals.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DownloadEvent {
+  Started { url: String, suggested_filename: String, path: String },
+  /// `total_bytes` is -1 when the server did not send a length.
+  Progress { received_bytes: i64, total_bytes: i64 },
+  /// `error` is `download_canceled` or `download_interrupted: <reason>` when it did not complete.
+  Finished { path: String, received_bytes: i64, error: Option<String> },
+}
+
+/// Makes a server-suggested name safe to use as a single path component.
+pub fn sanitize_filename(name: &str) -> String {
+  let cleaned: String = name
+    .chars()
+    .map(|c| {
+      if c.is_control() || matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') {
+        '_'
+      } else {
+        c
+      }
+    })
+    .collect();
+  let cleaned = cleaned.trim().trim_matches('.');
+  if cleaned.is_empty() {
+    "download".to_string()
+  } else {
+    cleaned.to_string()
+  }
+}
+
+/// Target path in `dir` for `suggested`, adding ` (n)` before the extension if the name is taken.
+pub fn unique_path(dir: &Path, suggested: &str) -> PathBuf {
+  let name = sanitize_filename(suggested);
+  let candidate = dir.join(&name);
+  if !candidate.exists() {
+    return candidate;
+  }
+  let (stem, ext) = match name.rsplit_once('.') {
+    Some((stem, ext)) if !stem.is_empty() => (stem, format!(".{ext}")),
+    _ => (name.as_str(), String::new()),
+  };
+  (1..)
+    .map(|n| dir.join(format!("{stem} ({n}){ext}")))
+    .find(|p| !p.exists())
+    .unwrap_or(candidate)
+}
@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+
+/// Anchored JS regex source for an origin glob: `*` matches anything but `/`, `**` anything.
+/// A pattern without `://` matches that host under any scheme and port.
+pub fn origin_regex(pattern: &str) -> String {
+  let pattern = pattern.trim();
+  let host_only = !pattern.contains("://");
+  let pattern = if host_only { format!("*://{pattern}") } else { pattern.to_string() };
+  let mut re = String::from("^");
+  let mut chars = pattern.chars().peekable();
+  while let Some(c) = chars.next() {
+    match c {
+      '*' if chars.peek() == Some(&'*') => {
+        chars.next();
+        re.push_str(".*");
+      }
+      '*' => re.push_str("[^/]*"),
+      c if "\\^$.|?+()[]{}/".contains(c) => {
+        re.push('\\');
+        re.push(c);
+      }
+      c => re.push(c),
+    }
+  }
+  if host_only {
+    re.push_str("(:\\d+)?");
+  }
+  re.push('$');
+  re
+}
+
+/// Source registered with the engine: `js` as-is for every page, or guarded by `location.origin`.
+/// A guarded script runs as the body of a function called on `globalThis`, so a leading
+/// `"use strict"` still applies but its top-level bindings are function-scoped; assign to
+/// `window` to share them with the page.
+pub fn wrap(js: &str, origin_pattern: &str) -> String {
+  let origin_pattern = origin_pattern.trim();
+  if origin_pattern.is_empty() || origin_pattern == "*" {
+    return js.to_string();
+  }
+  let regex = serde_json::to_string(&origin_regex(origin_pattern)).unwrap_or_default();
+  format!("if (new RegExp({regex}).test(location.origin)) (function () {{\n{js}\n}}).call(globalThis);")
+}
+
+/// User init scripts and their engine-side registrations per tab.
+#[derive(Debug, Default)]
+pub struct InitScripts {
+  next_id: u64,
+  scripts: Vec<(u64, String)>,
+  native: HashMap<(u64, i64), String>,
+}
+
+impl InitScripts {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Stores `js` (wrapped for `origin_pattern`) and returns its id.
+  pub fn add(&mut self, js: &str, origin_pattern: &str) -> Result<u64, String> {
+    if js.trim().is_empty() {
+      return Err("init_script_empty".to_string());
+    }
+    self.next_id += 1;
+    self.scripts.push((self.next_id, wrap(js, origin_pattern)));
+    Ok(self.next_id)
+  }
+
+  pub fn get(&self, id: u64) -> Option<&str> {
+    self.scripts.iter().find(|(script, _)| *script == id).map(|(_, source)| source.as_str())
+  }
+
+  /// Scripts in registration order, for newly opened tabs.
+  pub fn iter(&self) -> impl Iterator<Item = (u64, &str)> {
+    self.scripts.iter().map(|(id, source)| (*id, source.as_str()))
+  }
+
+  /// Records the engine id of `id` on `tab`; `false` when the script was removed meanwhile and
+  /// the registration should be undone.
+  pub fn register(&mut self, id: u64, tab: i64, native_id: String) -> bool {
+    if self.get(id).is_none() {
+      return false;
+    }
+    self.native.insert((id, tab), native_id);
+    true
+  }
+
+  /// Forgets `id` and returns its `(tab, engine id)` registrations; `None` when unknown.
+  pub fn remove(&mut self, id: u64) -> Option<Vec<(i64, String)>> {
+    let pos = self.scripts.iter().position(|(script, _)| *script == id)?;
+    self.scripts.remove(pos);
+    let tabs: Vec<(u64, i64)> = self.native.keys().filter(|(script, _)| *script == id).copied().collect();
+    let mut removed: Vec<(i64, String)> =
+      tabs.into_iter().filter_map(|key| self.native.remove(&key).map(|native| (key.1, native))).collect();
+    removed.sort();
+    Some(removed)
+  }
+
+  /// Drops the registrations of a closed tab.
+  pub fn remove_tab(&mut self, tab: i64) {
+    self.native.retain(|(_, t), _| *t != tab);
+  }
+}
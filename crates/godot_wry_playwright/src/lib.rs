@@ -1,7 +1,31 @@
+
 use godot::prelude::*;
 
+mod args;
+pub mod content;
+pub mod cookies;
+pub mod dialog;
+pub mod download;
+pub mod init_script;
+pub mod navigation;
+pub mod network;
+pub mod pdf;
+pub mod permission;
 pub mod pending;
+pub mod profile;
+pub mod screenshot;
+pub mod storage_state;
+pub mod tabs;
+pub mod trace;
+pub mod upload;
+pub mod video;
+#[cfg(windows)]
+mod webview2;
+// `#[godot_api]` generates, next to the impl block, closures returning godot's large `CallError`;
+// an attribute on the impl does not reach them, so the lint is allowed for the two node modules.
+#[allow(clippy::result_large_err)]
 mod wry_browser;
+#[allow(clippy::result_large_err)]
 mod wry_texture_browser;
 
 pub use wry_browser::WryBrowser;
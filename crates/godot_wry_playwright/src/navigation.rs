@@ -0,0 +1,245 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use serde::Deserialize;
+
+/// Schemes used internally by pages and the shim; never blocked.
+const INTERNAL_SCHEMES: &[&str] = &["about", "data", "blob"];
+
+/// Restricts where pages may navigate and what they may load.
+///
+/// Host patterns: `example.com` matches that host only, `*.example.com` also matches every
+/// subdomain, `*` matches everything. Blocked patterns win over allowed ones. Private-IP blocking
+/// applies to IP literals and `localhost` only: there is no DNS resolution, so names such as
+/// `localtest.me` that resolve to loopback are not caught.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct NavigationPolicy {
+  /// When non-empty, only these hosts may be loaded.
+  pub allowed_hosts: Vec<String>,
+  pub blocked_hosts: Vec<String>,
+  /// When non-empty, only these schemes may be loaded (besides `about:`, `data:` and `blob:`).
+  pub allowed_schemes: Vec<String>,
+  pub block_private_ips: bool,
+}
+
+fn host_matches(pattern: &str, host: &str) -> bool {
+  let pattern = pattern.trim().trim_end_matches('.').to_ascii_lowercase();
+  if pattern == "*" {
+    return true;
+  }
+  match pattern.strip_prefix("*.") {
+    Some(domain) => host == domain || host.ends_with(&format!(".{domain}")),
+    None => host == pattern,
+  }
+}
+
+fn is_private_v4(ip: Ipv4Addr) -> bool {
+  let [a, b, ..] = ip.octets();
+  ip.is_private()
+    || ip.is_loopback()
+    || ip.is_link_local()
+    || ip.is_unspecified()
+    || ip.is_broadcast()
+    // Carrier-grade NAT, 100.64.0.0/10.
+    || (a == 100 && (64..128).contains(&b))
+}
+
+fn is_private_v6(ip: Ipv6Addr) -> bool {
+  let first = ip.segments()[0];
+  ip.is_loopback()
+    || ip.is_unspecified()
+    // Unique local fc00::/7 and link-local fe80::/10.
+    || (first & 0xfe00) == 0xfc00
+    || (first & 0xffc0) == 0xfe80
+    || ip.to_ipv4_mapped().is_some_and(is_private_v4)
+}
+
+fn is_private_host(host: &str) -> bool {
+  if host == "localhost" || host.ends_with(".localhost") {
+    return true;
+  }
+  match host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>() {
+    Ok(IpAddr::V4(ip)) => is_private_v4(ip),
+    Ok(IpAddr::V6(ip)) => is_private_v6(ip),
+    Err(_) => false,
+  }
+}
+
+/// Splits a URL into its lowercased scheme and host (`None` for schemes without an authority).
+fn scheme_and_host(url: &str) -> Option<(String, Option<String>)> {
+  let (scheme, rest) = url.trim().split_once(':')?;
+  let scheme = scheme.to_ascii_lowercase();
+  let Some(rest) = rest.strip_prefix("//") else {
+    return Some((scheme, None));
+  };
+  let authority = &rest[..rest.find(['/', '?', '#']).unwrap_or(rest.len())];
+  let host_port = authority.rsplit_once('@').map_or(authority, |(_, h)| h);
+  let host = if host_port.starts_with('[') {
+    host_port.split_inclusive(']').next().unwrap_or(host_port)
+  } else {
+    host_port.split(':').next().unwrap_or(host_port)
+  };
+  Some((scheme, Some(host.trim_end_matches('.').to_ascii_lowercase())))
+}
+
+impl NavigationPolicy {
+  pub fn from_json(policy_json: &str) -> Result<Self, String> {
+    serde_json::from_str(policy_json).map_err(|e| format!("navigation_policy_invalid: {e}"))
+  }
+
+  pub fn is_unrestricted(&self) -> bool {
+    self.allowed_hosts.is_empty() && self.blocked_hosts.is_empty() && self.allowed_schemes.is_empty() && !self.block_private_ips
+  }
+
+  /// `Err("navigation_blocked: <reason>")` when `url` may not be loaded.
+  pub fn check(&self, url: &str) -> Result<(), String> {
+    if self.is_unrestricted() {
+      return Ok(());
+    }
+    let blocked = |reason: String| Err(format!("navigation_blocked: {reason}"));
+    let Some((scheme, host)) = scheme_and_host(url) else {
+      return blocked(format!("invalid url {url}"));
+    };
+    if INTERNAL_SCHEMES.contains(&scheme.as_str()) {
+      return Ok(());
+    }
+    if !self.allowed_schemes.is_empty() && !self.allowed_schemes.iter().any(|s| s.trim().eq_ignore_ascii_case(&scheme)) {
+      return blocked(format!("scheme {scheme}"));
+    }
+    let Some(host) = host.filter(|h| !h.is_empty()) else {
+      return Ok(());
+    };
+    if self.blocked_hosts.iter().any(|p| host_matches(p, &host)) {
+      return blocked(format!("host {host}"));
+    }
+    if !self.allowed_hosts.is_empty() && !self.allowed_hosts.iter().any(|p| host_matches(p, &host)) {
+      return blocked(format!("host {host} not allowed"));
+    }
+    if self.block_private_ips && is_private_host(&host) {
+      return blocked(format!("private address {host}"));
+    }
+    Ok(())
+  }
+}
+
+/// Native history and load controls (`go_back`, `go_forward`, `reload`, `stop`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryAction {
+  Back,
+  Forward,
+  Reload,
+  Stop,
+}
+
+impl HistoryAction {
+  /// Request kind, also used for `<kind>_timeout` errors.
+  pub fn as_str(self) -> &'static str {
+    match self {
+      HistoryAction::Back => "go_back",
+      HistoryAction::Forward => "go_forward",
+      HistoryAction::Reload => "reload",
+      HistoryAction::Stop => "stop",
+    }
+  }
+}
+
+/// Glob match for `wait_for_url`: `**` matches anything, `*` anything but `/`, `?` one character.
+/// An empty pattern matches every URL.
+///
+/// Runs over the URL once while tracking every reachable pattern position, so the cost stays
+/// `pattern × url` however many wildcards the pattern has.
+pub fn url_matches(pattern: &str, url: &str) -> bool {
+  let p: Vec<char> = pattern.trim().chars().collect();
+  if p.is_empty() {
+    return true;
+  }
+  // Wildcards match the empty string, so a reachable position also reaches the one after them.
+  let close = |states: &mut Vec<bool>| {
+    let mut i = 0;
+    while i < p.len() {
+      if states[i] && p[i] == '*' {
+        states[i + 1] = true;
+      }
+      i += 1;
+    }
+  };
+  let mut states = vec![false; p.len() + 1];
+  states[0] = true;
+  close(&mut states);
+  for c in url.chars() {
+    let mut next = vec![false; p.len() + 1];
+    for i in (0..p.len()).filter(|&i| states[i]) {
+      match p[i] {
+        '*' if p.get(i + 1) == Some(&'*') => next[i] = true,
+        '*' if i > 0 && p[i - 1] == '*' => next[i - 1] = true,
+        '*' if c != '/' => next[i] = true,
+        '*' => {}
+        '?' => next[i + 1] = true,
+        literal if literal == c => next[i + 1] = true,
+        _ => {}
+      }
+    }
+    close(&mut next);
+    if !next.contains(&true) {
+      return false;
+    }
+    states = next;
+  }
+  states[p.len()]
+}
+
+#[derive(Debug, Clone)]
+struct UrlWaiter {
+  id: i64,
+  tab: i64,
+  /// `None` waits for the next navigation of any kind.
+  pattern: Option<String>,
+}
+
+/// Pending `wait_for_url` / `wait_for_navigation` requests, resolved by URL changes per tab.
+#[derive(Debug, Default)]
+pub struct UrlWaiters {
+  waiters: Vec<UrlWaiter>,
+}
+
+impl UrlWaiters {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn add(&mut self, id: i64, tab: i64, pattern: Option<String>) {
+    self.waiters.push(UrlWaiter { id, tab, pattern });
+  }
+
+  /// Removes and returns the requests satisfied by `tab` now showing `url`.
+  pub fn resolve(&mut self, tab: i64, url: &str) -> Vec<i64> {
+    let mut resolved = Vec::new();
+    self.waiters.retain(|w| {
+      let hit = w.tab == tab && w.pattern.as_deref().is_none_or(|p| url_matches(p, url));
+      if hit {
+        resolved.push(w.id);
+      }
+      !hit
+    });
+    resolved
+  }
+
+  /// Drops a request that timed out; `false` when it was not waiting.
+  pub fn remove(&mut self, id: i64) -> bool {
+    let before = self.waiters.len();
+    self.waiters.retain(|w| w.id != id);
+    self.waiters.len() != before
+  }
+
+  /// Removes and returns every request waiting on a closed tab.
+  pub fn remove_tab(&mut self, tab: i64) -> Vec<i64> {
+    let mut removed = Vec::new();
+    self.waiters.retain(|w| {
+      if w.tab == tab {
+        removed.push(w.id);
+      }
+      w.tab != tab
+    });
+    removed
+  }
+}
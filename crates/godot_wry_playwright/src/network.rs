@@ -0,0 +1,214 @@
+use std::collections::{HashMap, VecDeque};
+
+use serde::Serialize;
+use serde_json::Value;
+
+pub const DEFAULT_LOG_CAPACITY: usize = 500;
+pub const DEFAULT_MAX_BODY_BYTES: usize = 64 * 1024;
+
+/// DevTools protocol events the backends subscribe to for the network log.
+pub const CDP_EVENTS: [&str; 4] = [
+  "Network.requestWillBeSent",
+  "Network.responseReceived",
+  "Network.loadingFinished",
+  "Network.loadingFailed",
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct NetworkRequest {
+  pub id: String,
+  pub method: String,
+  pub url: String,
+  pub resource_type: String,
+  pub status: i64,
+  pub status_text: String,
+  pub mime_type: String,
+  /// Wall clock time the request was issued, in milliseconds since the unix epoch.
+  pub started_at_ms: f64,
+  /// Time from request start until response headers arrived.
+  pub response_ms: Option<f64>,
+  /// Time from request start until the request finished or failed.
+  pub duration_ms: f64,
+  pub request_body_size: u64,
+  pub encoded_data_length: u64,
+  pub failed: bool,
+  pub error_text: Option<String>,
+  pub body: Option<String>,
+  pub body_base64: bool,
+  pub body_truncated: bool,
+}
+
+#[derive(Debug)]
+struct Inflight {
+  start_ts: f64,
+  entry: NetworkRequest,
+}
+
+/// Folds DevTools `Network.*` events into finished [`NetworkRequest`] records.
+#[derive(Debug, Default)]
+pub struct NetworkTracker {
+  inflight: HashMap<String, Inflight>,
+}
+
+impl NetworkTracker {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Returns the finished record once `loadingFinished` / `loadingFailed` arrives for a request.
+  pub fn on_cdp_event(&mut self, method: &str, params_json: &str) -> Option<NetworkRequest> {
+    let params: Value = serde_json::from_str(params_json).ok()?;
+    let id = params.get("requestId")?.as_str()?.to_string();
+    let ts = params.get("timestamp").and_then(Value::as_f64).unwrap_or(0.0);
+
+    match method {
+      "Network.requestWillBeSent" => {
+        let request = params.get("request").cloned().unwrap_or(Value::Null);
+        let entry = NetworkRequest {
+          id: id.clone(),
+          method: str_field(&request, "method"),
+          url: str_field(&request, "url"),
+          resource_type: resource_type(&params),
+          started_at_ms: params.get("wallTime").and_then(Value::as_f64).unwrap_or(0.0) * 1000.0,
+          request_body_size: request
+            .get("postData")
+            .and_then(Value::as_str)
+            .map(|s| s.len() as u64)
+            .unwrap_or(0),
+          ..Default::default()
+        };
+        // A redirect reuses the request id: the previous hop finishes with the redirect response.
+        let previous = self.inflight.insert(id, Inflight { start_ts: ts, entry });
+        let redirect = params.get("redirectResponse")?;
+        let Inflight { start_ts, entry: mut hop } = previous?;
+        hop.status = redirect.get("status").and_then(Value::as_i64).unwrap_or(0);
+        hop.status_text = str_field(redirect, "statusText");
+        hop.mime_type = str_field(redirect, "mimeType");
+        hop.duration_ms = elapsed_ms(start_ts, ts);
+        hop.response_ms = Some(hop.duration_ms);
+        Some(hop)
+      }
+      "Network.responseReceived" => {
+        let Inflight { start_ts, entry } = self.inflight.get_mut(&id)?;
+        let response = params.get("response").cloned().unwrap_or(Value::Null);
+        entry.status = response.get("status").and_then(Value::as_i64).unwrap_or(0);
+        entry.status_text = str_field(&response, "statusText");
+        entry.mime_type = str_field(&response, "mimeType");
+        if params.get("type").is_some() {
+          entry.resource_type = resource_type(&params);
+        }
+        entry.response_ms = Some(elapsed_ms(*start_ts, ts));
+        None
+      }
+      "Network.loadingFinished" => {
+        let Inflight { start_ts, mut entry } = self.inflight.remove(&id)?;
+        entry.encoded_data_length = params.get("encodedDataLength").and_then(Value::as_f64).unwrap_or(0.0) as u64;
+        entry.duration_ms = elapsed_ms(start_ts, ts);
+        Some(entry)
+      }
+      "Network.loadingFailed" => {
+        let Inflight { start_ts, mut entry } = self.inflight.remove(&id)?;
+        entry.failed = true;
+        entry.error_text = params.get("errorText").and_then(Value::as_str).map(str::to_string);
+        entry.duration_ms = elapsed_ms(start_ts, ts);
+        Some(entry)
+      }
+      _ => None,
+    }
+  }
+
+  pub fn clear(&mut self) {
+    self.inflight.clear();
+  }
+}
+
+/// Attaches a `Network.getResponseBody` result to a record, truncating it to `max_bytes`.
+pub fn apply_response_body(entry: &mut NetworkRequest, result_json: &str, max_bytes: usize) {
+  let Ok(result) = serde_json::from_str::<Value>(result_json) else {
+    return;
+  };
+  let Some(body) = result.get("body").and_then(Value::as_str) else {
+    return;
+  };
+
+  entry.body_base64 = result.get("base64Encoded").and_then(Value::as_bool).unwrap_or(false);
+  if body.len() > max_bytes {
+    let mut cut = max_bytes;
+    while !body.is_char_boundary(cut) {
+      cut -= 1;
+    }
+    entry.body = Some(body[..cut].to_string());
+    entry.body_truncated = true;
+  } else {
+    entry.body = Some(body.to_string());
+    entry.body_truncated = false;
+  }
+}
+
+/// Bounded log of finished requests; the oldest entries are dropped first.
+#[derive(Debug)]
+pub struct NetworkLog {
+  capacity: usize,
+  entries: VecDeque<NetworkRequest>,
+}
+
+impl Default for NetworkLog {
+  fn default() -> Self {
+    Self::with_capacity(DEFAULT_LOG_CAPACITY)
+  }
+}
+
+impl NetworkLog {
+  pub fn with_capacity(capacity: usize) -> Self {
+    Self {
+      capacity: capacity.max(1),
+      entries: VecDeque::new(),
+    }
+  }
+
+  pub fn set_capacity(&mut self, capacity: usize) {
+    self.capacity = capacity.max(1);
+    while self.entries.len() > self.capacity {
+      self.entries.pop_front();
+    }
+  }
+
+  pub fn push(&mut self, entry: NetworkRequest) {
+    if self.entries.len() == self.capacity {
+      self.entries.pop_front();
+    }
+    self.entries.push_back(entry);
+  }
+
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
+  pub fn clear(&mut self) {
+    self.entries.clear();
+  }
+
+  pub fn to_json(&self) -> String {
+    serde_json::to_string(&self.entries).unwrap_or_else(|_| "[]".to_string())
+  }
+}
+
+fn str_field(v: &Value, key: &str) -> String {
+  v.get(key).and_then(Value::as_str).unwrap_or_default().to_string()
+}
+
+fn resource_type(params: &Value) -> String {
+  params
+    .get("type")
+    .and_then(Value::as_str)
+    .unwrap_or("Other")
+    .to_ascii_lowercase()
+}
+
+fn elapsed_ms(start_ts: f64, ts: f64) -> f64 {
+  ((ts - start_ts) * 1000.0).max(0.0)
+}
@@ -0,0 +1,153 @@
+use base64::Engine;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+/// Paper sizes in inches, portrait orientation.
+const PAPER_FORMATS: &[(&str, f64, f64)] = &[
+  ("letter", 8.5, 11.0),
+  ("legal", 8.5, 14.0),
+  ("tabloid", 11.0, 17.0),
+  ("ledger", 17.0, 11.0),
+  ("a0", 33.1, 46.8),
+  ("a1", 23.4, 33.1),
+  ("a2", 16.54, 23.4),
+  ("a3", 11.7, 16.54),
+  ("a4", 8.27, 11.7),
+  ("a5", 5.83, 8.27),
+  ("a6", 4.13, 5.83),
+];
+
+/// Options accepted by `pdf(path, options)`.
+///
+/// Lengths are inches when given as numbers, or strings with a `px`, `in`, `cm` or `mm` unit.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct PdfOptions {
+  /// Paper format name such as "letter" or "a4"; `width`/`height` take precedence.
+  pub format: Option<String>,
+  pub width: Option<Value>,
+  pub height: Option<Value>,
+  pub margin: PdfMargin,
+  pub landscape: bool,
+  pub scale: Option<f64>,
+  pub print_background: bool,
+  pub display_header_footer: bool,
+  pub timeout_ms: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct PdfMargin {
+  pub top: Option<Value>,
+  pub right: Option<Value>,
+  pub bottom: Option<Value>,
+  pub left: Option<Value>,
+}
+
+/// Resolved print settings, all lengths in inches.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PdfSettings {
+  pub page_width: f64,
+  pub page_height: f64,
+  pub margin_top: f64,
+  pub margin_right: f64,
+  pub margin_bottom: f64,
+  pub margin_left: f64,
+  pub landscape: bool,
+  pub scale: f64,
+  pub print_background: bool,
+  pub display_header_footer: bool,
+}
+
+/// Parses a CSS-like length into inches; bare numbers are already inches.
+pub fn parse_length(value: &Value) -> Result<f64, String> {
+  let inches = match value {
+    Value::Number(n) => n.as_f64().unwrap_or(0.0),
+    Value::String(s) => {
+      let s = s.trim().to_ascii_lowercase();
+      let split = s.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(s.len());
+      let (number, unit) = s.split_at(split);
+      let number: f64 = number
+        .trim()
+        .parse()
+        .map_err(|_| format!("pdf_invalid_length: {s}"))?;
+      match unit {
+        "" | "in" => number,
+        "px" => number / 96.0,
+        "cm" => number / 2.54,
+        "mm" => number / 25.4,
+        _ => return Err(format!("pdf_invalid_length: {s}")),
+      }
+    }
+    _ => return Err(format!("pdf_invalid_length: {value}")),
+  };
+  if !inches.is_finite() || inches < 0.0 {
+    return Err(format!("pdf_invalid_length: {value}"));
+  }
+  Ok(inches)
+}
+
+fn optional_length(value: &Option<Value>) -> Result<Option<f64>, String> {
+  value.as_ref().filter(|v| !v.is_null()).map(parse_length).transpose()
+}
+
+impl PdfOptions {
+  pub fn from_json(options_json: &str) -> Result<Self, String> {
+    serde_json::from_str(options_json).map_err(|e| format!("pdf_invalid_options: {e}"))
+  }
+
+  pub fn timeout_ms(&self, default_ms: u64) -> u64 {
+    self.timeout_ms.map(|t| t.max(0.0) as u64).unwrap_or(default_ms)
+  }
+
+  pub fn settings(&self) -> Result<PdfSettings, String> {
+    let format = self.format.as_deref().unwrap_or("letter").trim().to_ascii_lowercase();
+    let (_, paper_width, paper_height) = PAPER_FORMATS
+      .iter()
+      .find(|(name, _, _)| *name == format)
+      .ok_or_else(|| format!("pdf_unknown_format: {format}"))?;
+
+    let page_width = optional_length(&self.width)?.unwrap_or(*paper_width);
+    let page_height = optional_length(&self.height)?.unwrap_or(*paper_height);
+    if page_width <= 0.0 || page_height <= 0.0 {
+      return Err("pdf_invalid_page_size".to_string());
+    }
+
+    let scale = self.scale.unwrap_or(1.0);
+    if !(0.1..=2.0).contains(&scale) {
+      return Err(format!("pdf_invalid_scale: {scale}"));
+    }
+
+    Ok(PdfSettings {
+      page_width,
+      page_height,
+      margin_top: optional_length(&self.margin.top)?.unwrap_or(0.0),
+      margin_right: optional_length(&self.margin.right)?.unwrap_or(0.0),
+      margin_bottom: optional_length(&self.margin.bottom)?.unwrap_or(0.0),
+      margin_left: optional_length(&self.margin.left)?.unwrap_or(0.0),
+      landscape: self.landscape,
+      scale,
+      print_background: self.print_background,
+      display_header_footer: self.display_header_footer,
+    })
+  }
+}
+
+/// Completion payload: a written file reports its path, otherwise the document is returned as base64.
+pub fn result_json(path: Option<&str>, bytes: Option<&[u8]>) -> String {
+  match (path, bytes) {
+    (Some(path), _) => {
+      let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+      json!({ "format": "pdf", "path": path, "size": size })
+    }
+    (None, bytes) => {
+      let bytes = bytes.unwrap_or_default();
+      json!({
+        "format": "pdf",
+        "size": bytes.len(),
+        "base64": base64::engine::general_purpose::STANDARD.encode(bytes),
+      })
+    }
+  }
+  .to_string()
+}
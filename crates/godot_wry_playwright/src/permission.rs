@@ -0,0 +1,124 @@
+/// Permissions a page can request, named as in the `permission_requested` signal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PermissionKind {
+  Camera,
+  Microphone,
+  Geolocation,
+  Notifications,
+  Sensors,
+  ClipboardRead,
+  MultipleDownloads,
+  FileSystem,
+  Autoplay,
+  LocalFonts,
+  MidiSysex,
+  WindowManagement,
+  Unknown,
+}
+
+const KINDS: &[PermissionKind] = &[
+  PermissionKind::Camera,
+  PermissionKind::Microphone,
+  PermissionKind::Geolocation,
+  PermissionKind::Notifications,
+  PermissionKind::Sensors,
+  PermissionKind::ClipboardRead,
+  PermissionKind::MultipleDownloads,
+  PermissionKind::FileSystem,
+  PermissionKind::Autoplay,
+  PermissionKind::LocalFonts,
+  PermissionKind::MidiSysex,
+  PermissionKind::WindowManagement,
+  PermissionKind::Unknown,
+];
+
+impl PermissionKind {
+  pub fn as_str(self) -> &'static str {
+    match self {
+      Self::Camera => "camera",
+      Self::Microphone => "microphone",
+      Self::Geolocation => "geolocation",
+      Self::Notifications => "notifications",
+      Self::Sensors => "sensors",
+      Self::ClipboardRead => "clipboard_read",
+      Self::MultipleDownloads => "multiple_downloads",
+      Self::FileSystem => "file_system",
+      Self::Autoplay => "autoplay",
+      Self::LocalFonts => "local_fonts",
+      Self::MidiSysex => "midi_sysex",
+      Self::WindowManagement => "window_management",
+      Self::Unknown => "unknown",
+    }
+  }
+
+  pub fn from_name(name: &str) -> Option<Self> {
+    let name = name.trim().to_ascii_lowercase().replace('-', "_");
+    KINDS.iter().copied().find(|k| k.as_str() == name)
+  }
+}
+
+/// What happens to a permission request when it arrives.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PermissionDecision {
+  /// Leave it to the engine (its built-in prompt or saved choice).
+  #[default]
+  Default,
+  Allow,
+  Deny,
+  /// Hold the request until `permission_respond`.
+  Ask,
+}
+
+impl PermissionDecision {
+  pub fn from_name(name: &str) -> Result<Self, String> {
+    match name.trim().to_ascii_lowercase().as_str() {
+      "default" | "" => Ok(Self::Default),
+      "allow" | "grant" => Ok(Self::Allow),
+      "deny" => Ok(Self::Deny),
+      "ask" | "manual" => Ok(Self::Ask),
+      other => Err(format!("permission_policy_invalid: {other}")),
+    }
+  }
+}
+
+/// A decision for every request, with per-kind overrides.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PermissionPolicy {
+  pub default: PermissionDecision,
+  pub overrides: Vec<(PermissionKind, PermissionDecision)>,
+}
+
+impl PermissionPolicy {
+  /// Parses `{ "default": "deny", "camera": "allow", "geolocation": "ask" }`.
+  pub fn from_json(policy_json: &str) -> Result<Self, String> {
+    let map: serde_json::Map<String, serde_json::Value> =
+      serde_json::from_str(policy_json).map_err(|e| format!("permission_policy_invalid: {e}"))?;
+    let mut policy = Self::default();
+    for (key, value) in map {
+      let decision = PermissionDecision::from_name(value.as_str().unwrap_or_default())?;
+      if key == "default" {
+        policy.default = decision;
+        continue;
+      }
+      let kind = PermissionKind::from_name(&key).ok_or_else(|| format!("permission_kind_invalid: {key}"))?;
+      policy.overrides.retain(|(k, _)| *k != kind);
+      policy.overrides.push((kind, decision));
+    }
+    Ok(policy)
+  }
+
+  pub fn decide(&self, kind: PermissionKind) -> PermissionDecision {
+    self
+      .overrides
+      .iter()
+      .find(|(k, _)| *k == kind)
+      .map_or(self.default, |(_, decision)| *decision)
+  }
+}
+
+/// Whether a pending request fits a `permission_respond` filter; empty filters match anything.
+pub fn request_matches(origin: &str, kind: PermissionKind, want_origin: &str, want_kind: &str) -> bool {
+  let want_origin = want_origin.trim().trim_end_matches('/');
+  (want_origin.is_empty() || origin.trim_end_matches('/').eq_ignore_ascii_case(want_origin))
+    && (want_kind.trim().is_empty() || PermissionKind::from_name(want_kind) == Some(kind))
+}
@@ -0,0 +1,98 @@
+use serde::Deserialize;
+
+/// Every profile directory lives under `user://wry_profiles`.
+pub const PROFILES_DIR: &str = "user://wry_profiles";
+
+/// Profile selection for `set_profile` / `delete_profile_data`.
+///
+/// With neither `name` nor `data_dir` the WebView2 default data folder is used, shared by every
+/// node that does the same.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ProfileConfig {
+  pub name: Option<String>,
+  /// Explicit data directory relative to `user://wry_profiles` (the prefix itself is optional).
+  /// Absolute paths, other `scheme://` roots and `..` components are rejected.
+  pub data_dir: Option<String>,
+  /// Keeps cookies, storage and cache in memory; nothing is written to the data directory.
+  pub incognito: bool,
+}
+
+/// A resolved profile the backend thread can apply when it creates the webview.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProfileSettings {
+  pub data_dir: Option<String>,
+  pub incognito: bool,
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+  let valid = !name.is_empty()
+    && name.len() <= 64
+    && !name.starts_with('.')
+    && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+  if valid {
+    Ok(())
+  } else {
+    Err(format!("profile_invalid_name: {name}"))
+  }
+}
+
+fn validate_data_dir(dir: &str) -> Result<String, String> {
+  let invalid = || format!("profile_invalid_data_dir: {dir}");
+  let relative = dir.strip_prefix(PROFILES_DIR).map(|rest| rest.trim_start_matches('/')).unwrap_or(dir);
+  if relative.contains(':') || relative.contains('\\') || relative.starts_with('/') {
+    return Err(invalid());
+  }
+  let parts: Vec<&str> = relative.split('/').filter(|part| !part.is_empty()).collect();
+  if parts.is_empty() || parts.iter().any(|part| validate_name(part).is_err()) {
+    return Err(invalid());
+  }
+  Ok(format!("{PROFILES_DIR}/{}", parts.join("/")))
+}
+
+impl ProfileConfig {
+  pub fn from_json(config_json: &str) -> Result<Self, String> {
+    serde_json::from_str(config_json).map_err(|e| format!("profile_invalid_config: {e}"))
+  }
+
+  /// Godot path of the data directory, always under `PROFILES_DIR`: `data_dir` wins over the
+  /// directory derived from `name`.
+  pub fn data_dir_path(&self) -> Result<Option<String>, String> {
+    if let Some(dir) = self.data_dir.as_deref().map(str::trim).filter(|d| !d.is_empty()) {
+      return validate_data_dir(dir).map(Some);
+    }
+    match self.name.as_deref().map(str::trim).filter(|n| !n.is_empty()) {
+      Some(name) => {
+        validate_name(name)?;
+        Ok(Some(format!("{PROFILES_DIR}/{name}")))
+      }
+      None => Ok(None),
+    }
+  }
+
+  /// Resolves the data directory to an OS path with `globalize` (Godot path -> OS path).
+  pub fn resolve(&self, globalize: impl Fn(&str) -> String) -> Result<ProfileSettings, String> {
+    Ok(ProfileSettings {
+      data_dir: self.data_dir_path()?.map(|dir| globalize(&dir)),
+      incognito: self.incognito,
+    })
+  }
+}
+
+/// Removes a profile's data directory, which must resolve strictly inside `root` (the OS path of
+/// `PROFILES_DIR`) after following links. Returns whether there was anything to delete.
+pub fn delete_data_dir(dir: &str, root: &str) -> Result<bool, String> {
+  let path = std::path::Path::new(dir);
+  if !path.exists() {
+    return Ok(false);
+  }
+  let outside = || format!("profile_outside_root: {dir}");
+  let root = std::fs::canonicalize(root).map_err(|_| outside())?;
+  let path = std::fs::canonicalize(path).map_err(|e| format!("profile_delete_error: {e}"))?;
+  if path == root || !path.starts_with(&root) {
+    return Err(outside());
+  }
+  std::fs::remove_dir_all(&path)
+    .map(|()| true)
+    .map_err(|e| format!("profile_delete_error: {e}"))
+}
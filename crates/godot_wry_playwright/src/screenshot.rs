@@ -0,0 +1,174 @@
+use base64::Engine;
+use godot_wry_playwright_core::protocol::Target;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFormat {
+  #[default]
+  Png,
+  #[serde(alias = "jpg")]
+  Jpeg,
+}
+
+impl ImageFormat {
+  pub fn as_str(self) -> &'static str {
+    match self {
+      ImageFormat::Png => "png",
+      ImageFormat::Jpeg => "jpeg",
+    }
+  }
+}
+
+/// Chromium cannot capture a larger surface; bigger pages fail rather than come back cut off.
+pub const MAX_FULL_PAGE_PIXELS: f64 = 16_384.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct Clip {
+  pub x: f64,
+  pub y: f64,
+  pub width: f64,
+  pub height: f64,
+}
+
+/// Options accepted by `screenshot(options)`; numbers are `f64` because Godot's JSON emits floats.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ScreenshotOptions {
+  pub format: ImageFormat,
+  /// JPEG quality in `0..=100`; ignored for PNG.
+  pub quality: Option<f64>,
+  pub full_page: bool,
+  /// Clips the capture to the border box of this element: a selector, `aria-ref=<ref>` or
+  /// `handle=<id>`, resolved like any command target.
+  pub selector: Option<String>,
+  /// Explicit clip rectangle in CSS pixels, relative to the page origin.
+  pub clip: Option<Clip>,
+  /// When set the image is written here and only its metadata is returned.
+  pub path: Option<String>,
+  pub timeout_ms: Option<f64>,
+}
+
+impl ScreenshotOptions {
+  pub fn from_json(options_json: &str) -> Result<Self, String> {
+    serde_json::from_str(options_json).map_err(|e| format!("screenshot_invalid_options: {e}"))
+  }
+
+  pub fn timeout_ms(&self, default_ms: u64) -> u64 {
+    self.timeout_ms.map(|t| t.max(0.0) as u64).unwrap_or(default_ms)
+  }
+
+  /// `Runtime.evaluate` expression resolving the target through the automation shim to a
+  /// page-relative rectangle.
+  pub fn element_rect_expression(&self) -> Option<String> {
+    let selector = self.selector.as_deref().filter(|s| !s.is_empty())?;
+    let target_json = serde_json::to_string(&Target::from_selector(selector)).unwrap_or_else(|_| "\"\"".to_string());
+    Some(format!(
+      "(() => {{ const g = window.__gwry; if (!g || !g.element) throw new Error(\"shim_not_installed\"); \
+       const el = g.element({target_json}); const r = el.getBoundingClientRect(); \
+       return {{ x: r.left + window.scrollX, y: r.top + window.scrollY, width: r.width, height: r.height }}; }})()"
+    ))
+  }
+
+  /// `Page.captureScreenshot` parameters; `element` wins over `clip`, which wins over full page.
+  pub fn capture_params(&self, element: Option<Clip>, content_size: Option<Clip>) -> Value {
+    let mut params = json!({ "format": self.format.as_str(), "fromSurface": true });
+    if self.format == ImageFormat::Jpeg {
+      params["quality"] = json!(self.quality.unwrap_or(80.0).clamp(0.0, 100.0) as u8);
+    }
+
+    let clip = element.or(self.clip).or(if self.full_page { content_size } else { None });
+    if let Some(clip) = clip {
+      params["clip"] = json!({
+        "x": clip.x,
+        "y": clip.y,
+        "width": clip.width.max(1.0),
+        "height": clip.height.max(1.0),
+        "scale": 1,
+      });
+    }
+    if self.full_page || clip.is_some() {
+      params["captureBeyondViewport"] = json!(true);
+    }
+    params
+  }
+}
+
+/// Reads the `Runtime.evaluate` result of [`ScreenshotOptions::element_rect_expression`]. Shim
+/// errors (`not_found`, `handle_not_found`, `stale_element`) come back as they are.
+pub fn parse_element_rect(result_json: &str) -> Result<Clip, String> {
+  let result: Value = serde_json::from_str(result_json).map_err(|e| format!("screenshot_invalid_result: {e}"))?;
+  if let Some(details) = result.get("exceptionDetails") {
+    let message = details
+      .pointer("/exception/description")
+      .and_then(Value::as_str)
+      .and_then(|d| d.lines().next())
+      .map(|line| line.strip_prefix("Error: ").unwrap_or(line))
+      .or_else(|| details.get("text").and_then(Value::as_str))
+      .unwrap_or("exception");
+    return Err(match message {
+      "not_found" | "handle_not_found" | "stale_element" | "shim_not_installed" => message.to_string(),
+      other => format!("screenshot_selector_error: {other}"),
+    });
+  }
+  let value = result.pointer("/result/value").cloned().unwrap_or(Value::Null);
+  if value.is_null() {
+    return Err("not_found".to_string());
+  }
+  serde_json::from_value(value).map_err(|e| format!("screenshot_invalid_result: {e}"))
+}
+
+/// Full document size from a `Page.getLayoutMetrics` result.
+pub fn parse_content_size(result_json: &str) -> Option<Clip> {
+  let result: Value = serde_json::from_str(result_json).ok()?;
+  let size = result.get("cssContentSize").or_else(|| result.get("contentSize"))?;
+  Some(Clip {
+    x: 0.0,
+    y: 0.0,
+    width: size.get("width")?.as_f64()?,
+    height: size.get("height")?.as_f64()?,
+  })
+}
+
+/// `Emulation.setDeviceMetricsOverride` parameters that lay the page out at its full `content`
+/// size for a full-page capture, so fixed and viewport-sized elements render as on a tall screen.
+pub fn full_page_metrics(content: Clip) -> Result<Value, String> {
+  let (width, height) = (content.width.ceil().max(1.0), content.height.ceil().max(1.0));
+  if width > MAX_FULL_PAGE_PIXELS || height > MAX_FULL_PAGE_PIXELS {
+    return Err(format!("screenshot_full_page_too_large: {width}x{height} (max {MAX_FULL_PAGE_PIXELS})"));
+  }
+  Ok(json!({ "width": width as u32, "height": height as u32, "deviceScaleFactor": 0, "mobile": false }))
+}
+
+/// Decodes the image bytes from a `Page.captureScreenshot` result.
+pub fn decode_capture(result_json: &str) -> Result<Vec<u8>, String> {
+  let result: Value = serde_json::from_str(result_json).map_err(|e| format!("screenshot_invalid_result: {e}"))?;
+  let data = result
+    .get("data")
+    .and_then(Value::as_str)
+    .ok_or_else(|| "screenshot_missing_data".to_string())?;
+  base64::engine::general_purpose::STANDARD
+    .decode(data)
+    .map_err(|e| format!("screenshot_decode_error: {e}"))
+}
+
+/// Completion payload: written files report their path, in-memory captures carry base64 bytes.
+pub fn result_json(options: &ScreenshotOptions, bytes: &[u8]) -> Result<String, String> {
+  let result = match options.path.as_deref().filter(|p| !p.is_empty()) {
+    Some(path) => {
+      let path = std::path::Path::new(path);
+      if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).map_err(|e| format!("screenshot_mkdir_error: {e}"))?;
+      }
+      std::fs::write(path, bytes).map_err(|e| format!("screenshot_write_error: {e}"))?;
+      json!({ "format": options.format.as_str(), "path": path.to_string_lossy(), "size": bytes.len() })
+    }
+    None => json!({
+      "format": options.format.as_str(),
+      "size": bytes.len(),
+      "base64": base64::engine::general_purpose::STANDARD.encode(bytes),
+    }),
+  };
+  Ok(result.to_string())
+}
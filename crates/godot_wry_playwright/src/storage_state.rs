@@ -0,0 +1,161 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::cookies::Cookie;
+
+pub const STORAGE_STATE_VERSION: u32 = 1;
+
+/// Path visited on each origin while collecting its `localStorage`; the request is answered with an
+/// empty document so no site code runs.
+pub const SENTINEL_PATH: &str = "/__gwry_storage_state__";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StorageEntry {
+  pub name: String,
+  pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OriginState {
+  pub origin: String,
+  pub local_storage: Vec<StorageEntry>,
+}
+
+/// Playwright `storageState` layout plus a format version.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StorageState {
+  pub version: u32,
+  pub cookies: Vec<Cookie>,
+  pub origins: Vec<OriginState>,
+}
+
+impl StorageState {
+  pub fn new(cookies: Vec<Cookie>, origins: Vec<OriginState>) -> Self {
+    Self {
+      version: STORAGE_STATE_VERSION,
+      cookies,
+      origins,
+    }
+  }
+
+  pub fn from_json(state_json: &str) -> Result<Self, String> {
+    let value: Value = serde_json::from_str(state_json).map_err(|e| format!("storage_state_invalid: {e}"))?;
+    match value.get("version").and_then(Value::as_u64) {
+      Some(v) if (1..=u64::from(STORAGE_STATE_VERSION)).contains(&v) => {}
+      Some(v) => return Err(format!("storage_state_unsupported_version: {v}")),
+      None => return Err("storage_state_missing_version".to_string()),
+    }
+    serde_json::from_value(value).map_err(|e| format!("storage_state_invalid: {e}"))
+  }
+
+  pub fn load(path: &Path) -> Result<Self, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("storage_state_read_error: {e}"))?;
+    Self::from_json(&text)
+  }
+
+  pub fn save(&self, path: &Path) -> Result<(), String> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+      std::fs::create_dir_all(dir).map_err(|e| format!("storage_state_mkdir_error: {e}"))?;
+    }
+    let text = serde_json::to_string_pretty(self).map_err(|e| format!("storage_state_encode_error: {e}"))?;
+    std::fs::write(path, text).map_err(|e| format!("storage_state_write_error: {e}"))
+  }
+
+  /// Init script that seeds each origin's `localStorage` and reports it through the shim as
+  /// `storage_seeded`, so the backend can stop seeding that origin.
+  pub fn seed_script(&self) -> Option<String> {
+    let origins: serde_json::Map<String, Value> = self
+      .origins
+      .iter()
+      .filter(|o| !o.local_storage.is_empty())
+      .map(|o| {
+        let entries = o.local_storage.iter().map(|e| serde_json::json!([e.name, e.value])).collect();
+        (o.origin.clone(), Value::Array(entries))
+      })
+      .collect();
+    if origins.is_empty() {
+      return None;
+    }
+    Some(format!(
+      "(() => {{ const entries = {}[location.origin]; if (!entries) return; \
+       try {{ for (const [name, value] of entries) localStorage.setItem(name, value); }} catch (_) {{}} \
+       if (window.__gwry && window.__gwry.sendEvent) window.__gwry.sendEvent(\"storage_seeded\", {{ origin: location.origin }}); }})();",
+      Value::Object(origins)
+    ))
+  }
+}
+
+/// `scheme://host[:port]` of an http(s) URL, lowercased; `None` for other schemes.
+pub fn origin_of(url: &str) -> Option<String> {
+  let (scheme, rest) = url.trim().split_once("://")?;
+  let scheme = scheme.to_ascii_lowercase();
+  if scheme != "http" && scheme != "https" {
+    return None;
+  }
+  let authority = &rest[..rest.find(['/', '?', '#']).unwrap_or(rest.len())];
+  let host = authority.rsplit_once('@').map_or(authority, |(_, h)| h).to_ascii_lowercase();
+  let host = match (scheme.as_str(), host.rsplit_once(':')) {
+    ("http", Some((h, "80"))) | ("https", Some((h, "443"))) => h.to_string(),
+    _ => host,
+  };
+  if host.is_empty() {
+    return None;
+  }
+  Some(format!("{scheme}://{host}"))
+}
+
+/// Origins `storage_state_save` reads: normalized, deduplicated, the current page's origin first,
+/// and just the current origin when `origins` is empty.
+pub fn storage_origins(origins: &[String], current_url: &str) -> Vec<String> {
+  let current = origin_of(current_url);
+  let mut queue: Vec<String> = Vec::new();
+  for origin in origins.iter().map(|o| origin_of(o).unwrap_or_else(|| o.trim_end_matches('/').to_string())) {
+    if !queue.contains(&origin) {
+      queue.push(origin);
+    }
+  }
+  if queue.is_empty() {
+    queue.extend(current.clone());
+  }
+  if let Some(pos) = current.as_ref().and_then(|c| queue.iter().position(|o| o == c)) {
+    let current = queue.remove(pos);
+    queue.insert(0, current);
+  }
+  queue
+}
+
+/// `Runtime.evaluate` expression (awaited) reading `localStorage` as `[[name, value], ...]`.
+///
+/// With `at_sentinel` it resolves to `null` until the sentinel document has replaced the previous
+/// page, so the caller can poll across the navigation.
+pub fn read_local_storage_expression(at_sentinel: bool) -> String {
+  let guard = if at_sentinel {
+    format!("if (location.pathname !== \"{SENTINEL_PATH}\") return null; ")
+  } else {
+    String::new()
+  };
+  format!(
+    "new Promise((r) => setTimeout(r, {delay})).then(() => {{ {guard}\
+     try {{ return Object.entries(localStorage); }} catch (_) {{ return []; }} }})",
+    delay = if at_sentinel { 25 } else { 0 }
+  )
+}
+
+/// Parses the result of [`read_local_storage_expression`]; `None` while still off the sentinel page.
+pub fn parse_local_storage(result_json: &str) -> Result<Option<Vec<StorageEntry>>, String> {
+  let result: Value = serde_json::from_str(result_json).map_err(|e| format!("storage_state_invalid_result: {e}"))?;
+  if let Some(details) = result.get("exceptionDetails") {
+    let text = details.get("text").and_then(Value::as_str).unwrap_or("exception");
+    return Err(format!("storage_state_read_error: {text}"));
+  }
+  let value = result.pointer("/result/value").cloned().unwrap_or(Value::Null);
+  if value.is_null() {
+    return Ok(None);
+  }
+  let pairs: Vec<(String, String)> =
+    serde_json::from_value(value).map_err(|e| format!("storage_state_invalid_result: {e}"))?;
+  Ok(Some(pairs.into_iter().map(|(name, value)| StorageEntry { name, value }).collect()))
+}
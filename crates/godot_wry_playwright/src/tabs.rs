@@ -0,0 +1,141 @@
+use serde::Serialize;
+
+/// Tabs owned by a backend, keyed by ids that are never reused within a session.
+///
+/// Commands go to the active tab; closing it activates the tab to its right, or the last one.
+#[derive(Debug)]
+pub struct TabSet<T> {
+  next_id: i64,
+  tabs: Vec<(i64, T)>,
+  active: Option<i64>,
+}
+
+impl<T> Default for TabSet<T> {
+  fn default() -> Self {
+    Self {
+      next_id: 1,
+      tabs: Vec::new(),
+      active: None,
+    }
+  }
+}
+
+impl<T> TabSet<T> {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Adds a tab; the first tab becomes active.
+  pub fn open(&mut self, tab: T) -> i64 {
+    let id = self.next_id;
+    self.next_id += 1;
+    self.tabs.push((id, tab));
+    self.active.get_or_insert(id);
+    id
+  }
+
+  /// Like [`TabSet::open`] for tabs that need their id while being built (e.g. in event handlers).
+  pub fn try_open<E>(&mut self, build: impl FnOnce(i64) -> Result<T, E>) -> Result<i64, E> {
+    let id = self.next_id;
+    self.next_id += 1;
+    self.tabs.push((id, build(id)?));
+    self.active.get_or_insert(id);
+    Ok(id)
+  }
+
+  pub fn close(&mut self, id: i64) -> Option<T> {
+    let pos = self.tabs.iter().position(|(tab_id, _)| *tab_id == id)?;
+    let (_, tab) = self.tabs.remove(pos);
+    if self.active == Some(id) {
+      self.active = self
+        .tabs
+        .get(pos)
+        .or_else(|| self.tabs.last())
+        .map(|(tab_id, _)| *tab_id);
+    }
+    Some(tab)
+  }
+
+  pub fn select(&mut self, id: i64) -> bool {
+    if self.get(id).is_none() {
+      return false;
+    }
+    self.active = Some(id);
+    true
+  }
+
+  pub fn active_id(&self) -> Option<i64> {
+    self.active
+  }
+
+  pub fn active(&self) -> Option<&T> {
+    self.active.and_then(|id| self.get(id))
+  }
+
+  pub fn active_entry(&self) -> Option<(i64, &T)> {
+    self.active.and_then(|id| self.get(id).map(|tab| (id, tab)))
+  }
+
+  /// Resolves a command's target: the given tab, or the active one when `tab` is `None`.
+  pub fn target(&self, tab: Option<i64>) -> Result<(i64, &T), String> {
+    match tab {
+      Some(id) => self.get(id).map(|t| (id, t)).ok_or_else(|| format!("tab_not_found: {id}")),
+      None => self.active_entry().ok_or_else(|| "webview_not_started".to_string()),
+    }
+  }
+
+  pub fn get(&self, id: i64) -> Option<&T> {
+    self.tabs.iter().find(|(tab_id, _)| *tab_id == id).map(|(_, tab)| tab)
+  }
+
+  pub fn get_mut(&mut self, id: i64) -> Option<&mut T> {
+    self.tabs.iter_mut().find(|(tab_id, _)| *tab_id == id).map(|(_, tab)| tab)
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = (i64, &T)> {
+    self.tabs.iter().map(|(id, tab)| (*id, tab))
+  }
+
+  pub fn len(&self) -> usize {
+    self.tabs.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.tabs.is_empty()
+  }
+}
+
+/// Entry of the `tab_list` result.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TabInfo {
+  pub id: i64,
+  pub url: String,
+  pub title: String,
+  pub active: bool,
+}
+
+/// What happens to popups, `window.open` and `target=_blank` links.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NewWindowPolicy {
+  /// Navigate the opener tab to the popup URL.
+  SameView,
+  /// Open the URL in a new background tab and emit `tab_opened`.
+  #[default]
+  NewTab,
+  /// Drop the request.
+  Deny,
+  /// Drop the request and emit `popup_requested(url)` so the game decides.
+  Signal,
+}
+
+impl NewWindowPolicy {
+  pub fn from_name(name: &str) -> Result<Self, String> {
+    match name.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+      "same_view" | "same" => Ok(Self::SameView),
+      "new_tab" | "tab" | "" => Ok(Self::NewTab),
+      "deny" => Ok(Self::Deny),
+      "signal" => Ok(Self::Signal),
+      other => Err(format!("new_window_policy_invalid: {other}")),
+    }
+  }
+}
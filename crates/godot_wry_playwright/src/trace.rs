@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Seek, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use godot_wry_playwright_core::protocol::Command;
+use serde_json::{json, Value};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+pub const TRACE_EVENTS_FILE: &str = "trace.jsonl";
+
+/// Results above this size are recorded by length only, so large payloads (e.g. screenshots) do not
+/// pile up in memory.
+pub const MAX_TRACED_RESULT_BYTES: usize = 16 * 1024;
+
+#[derive(Debug, Clone, Copy)]
+struct Inflight {
+  started_ms: u64,
+}
+
+/// Archive file that is removed unless [`TraceRecorder::save`] moved it away first.
+#[derive(Debug)]
+struct TempArchive(PathBuf);
+
+impl Drop for TempArchive {
+  fn drop(&mut self) {
+    let _ = std::fs::remove_file(&self.0);
+  }
+}
+
+static NEXT_TEMP_ARCHIVE: AtomicU64 = AtomicU64::new(1);
+
+/// Records an automation session as JSON lines plus one PNG frame per action.
+///
+/// Frames are written to the archive as they arrive; only the (small) event lines are kept until
+/// the trace is finished. Times are relative to `tracing_start`; request ids are the ones handed
+/// out by the browser node.
+#[derive(Debug)]
+pub struct TraceRecorder<W: Write + Seek = File> {
+  started_ms: u64,
+  events: Vec<Value>,
+  frame_count: usize,
+  inflight: HashMap<i64, Inflight>,
+  // Declared before `temp` so the file is closed before it is removed.
+  archive: ZipWriter<W>,
+  temp: Option<TempArchive>,
+}
+
+fn options() -> SimpleFileOptions {
+  SimpleFileOptions::default().compression_method(CompressionMethod::Stored)
+}
+
+impl<W: Write + Seek> TraceRecorder<W> {
+  pub fn new(now_ms: u64, writer: W) -> Self {
+    let mut recorder = Self {
+      started_ms: now_ms,
+      events: Vec::new(),
+      frame_count: 0,
+      inflight: HashMap::new(),
+      archive: ZipWriter::new(writer),
+      temp: None,
+    };
+    recorder.push(now_ms, json!({ "type": "trace_start" }));
+    recorder
+  }
+
+  fn push(&mut self, now_ms: u64, mut event: Value) {
+    event["t_ms"] = json!(now_ms.saturating_sub(self.started_ms));
+    self.events.push(event);
+  }
+
+  pub fn command(&mut self, now_ms: u64, id: i64, cmd: &Command) {
+    self.inflight.insert(id, Inflight { started_ms: now_ms });
+    self.push(now_ms, json!({ "type": "command", "id": id, "command": cmd }));
+  }
+
+  pub fn navigation_started(&mut self, now_ms: u64, id: i64, url: &str) {
+    self.inflight.insert(id, Inflight { started_ms: now_ms });
+    self.push(now_ms, json!({ "type": "navigation", "id": id, "url": url }));
+  }
+
+  /// Records a request handled by the backend itself (screenshots, cookies, tabs, ...).
+  pub fn action(&mut self, now_ms: u64, id: i64, action: &str, params: Value) {
+    self.inflight.insert(id, Inflight { started_ms: now_ms });
+    self.push(now_ms, json!({ "type": "action", "id": id, "action": action, "params": params }));
+  }
+
+  /// Records how a request completed, with its round-trip time when it was traced.
+  pub fn response(&mut self, now_ms: u64, id: i64, ok: bool, result_json: &str, error: &str) {
+    let duration_ms = self
+      .inflight
+      .remove(&id)
+      .map(|p| now_ms.saturating_sub(p.started_ms));
+    let mut event = json!({ "type": "response", "id": id, "ok": ok, "duration_ms": duration_ms });
+    if !ok {
+      event["error"] = json!(error);
+    } else if result_json.len() > MAX_TRACED_RESULT_BYTES {
+      event["result_bytes"] = json!(result_json.len());
+    } else {
+      event["result"] = serde_json::from_str(result_json).unwrap_or_else(|_| json!(result_json));
+    }
+    self.push(now_ms, event);
+  }
+
+  pub fn console(&mut self, now_ms: u64, data: &Value) {
+    self.push(now_ms, json!({ "type": "console", "data": data }));
+  }
+
+  /// Writes a frame for the action `id` to the archive; returns the path it was stored under.
+  pub fn frame(&mut self, now_ms: u64, id: i64, png: &[u8]) -> Result<String, String> {
+    let file = format!("frames/{:06}_{id}.png", self.frame_count + 1);
+    self
+      .archive
+      .start_file(file.as_str(), options())
+      .and_then(|()| Ok(self.archive.write_all(png)?))
+      .map_err(|e| format!("trace_write_error: {e}"))?;
+    self.frame_count += 1;
+    self.push(now_ms, json!({ "type": "frame", "id": id, "file": file }));
+    Ok(file)
+  }
+
+  pub fn event_count(&self) -> usize {
+    self.events.len()
+  }
+
+  /// Appends the event lines and finishes the archive.
+  pub fn finish(mut self) -> zip::result::ZipResult<W> {
+    self.archive.start_file(TRACE_EVENTS_FILE, options())?;
+    for event in &self.events {
+      self.archive.write_all(event.to_string().as_bytes())?;
+      self.archive.write_all(b"\n")?;
+    }
+    self.archive.finish()
+  }
+}
+
+impl TraceRecorder<File> {
+  /// Starts a trace archived in a temporary file until [`TraceRecorder::save`].
+  pub fn start(now_ms: u64) -> Result<Self, String> {
+    let n = NEXT_TEMP_ARCHIVE.fetch_add(1, Ordering::Relaxed);
+    let path = std::env::temp_dir().join(format!("gwry-trace-{}-{n}.zip", std::process::id()));
+    let file = File::create(&path).map_err(|e| format!("trace_open_error: {e}"))?;
+    let mut recorder = Self::new(now_ms, file);
+    recorder.temp = Some(TempArchive(path));
+    Ok(recorder)
+  }
+
+  /// Finishes the archive and moves it to `path`.
+  pub fn save(mut self, path: &Path) -> Result<(), String> {
+    let temp = self.temp.take().ok_or("trace_not_started")?;
+    drop(self.finish().map_err(|e| format!("trace_write_error: {e}"))?);
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+      std::fs::create_dir_all(dir).map_err(|e| format!("trace_mkdir_error: {e}"))?;
+    }
+    // `rename` fails across volumes; the temp file is removed when `temp` drops either way.
+    if std::fs::rename(&temp.0, path).is_err() {
+      std::fs::copy(&temp.0, path).map_err(|e| format!("trace_write_error: {e}"))?;
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,62 @@
+use std::path::Path;
+
+use base64::Engine;
+use godot_wry_playwright_core::protocol::InputFile;
+
+/// Upper bound for the files of one `set_input_files` call; they travel inline through the shim.
+pub const MAX_INPUT_FILES_BYTES: u64 = 64 * 1024 * 1024;
+
+/// MIME type from the file extension, `application/octet-stream` when unknown.
+pub fn mime_type(path: &Path) -> &'static str {
+  let ext = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_ascii_lowercase();
+  match ext.as_str() {
+    "txt" | "log" => "text/plain",
+    "md" => "text/markdown",
+    "csv" => "text/csv",
+    "html" | "htm" => "text/html",
+    "css" => "text/css",
+    "js" | "mjs" => "text/javascript",
+    "json" => "application/json",
+    "xml" => "application/xml",
+    "pdf" => "application/pdf",
+    "zip" => "application/zip",
+    "gz" => "application/gzip",
+    "png" => "image/png",
+    "jpg" | "jpeg" => "image/jpeg",
+    "gif" => "image/gif",
+    "webp" => "image/webp",
+    "bmp" => "image/bmp",
+    "svg" => "image/svg+xml",
+    "ico" => "image/x-icon",
+    "mp3" => "audio/mpeg",
+    "ogg" => "audio/ogg",
+    "wav" => "audio/wav",
+    "mp4" => "video/mp4",
+    "webm" => "video/webm",
+    _ => "application/octet-stream",
+  }
+}
+
+/// Reads files (OS paths) for `set_input_files`.
+pub fn read_input_files(paths: &[String]) -> Result<Vec<InputFile>, String> {
+  let mut total = 0u64;
+  paths
+    .iter()
+    .map(|path| {
+      let path = Path::new(path);
+      if !path.is_file() {
+        return Err(format!("input_file_not_found: {}", path.display()));
+      }
+      let bytes = std::fs::read(path).map_err(|e| format!("input_file_read_error: {e}"))?;
+      total += bytes.len() as u64;
+      if total > MAX_INPUT_FILES_BYTES {
+        return Err(format!("input_files_too_large: limit {MAX_INPUT_FILES_BYTES} bytes"));
+      }
+      Ok(InputFile {
+        name: path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default(),
+        mime_type: mime_type(path).to_string(),
+        base64: base64::engine::general_purpose::STANDARD.encode(bytes),
+      })
+    })
+    .collect()
+}
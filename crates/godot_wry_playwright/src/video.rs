@@ -0,0 +1,237 @@
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use thiserror::Error;
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+#[derive(Error, Debug)]
+pub enum VideoError {
+  #[error("video_frame_not_png")]
+  NotPng,
+  #[error("video_frame_truncated")]
+  Truncated,
+  #[error("video_frame_size_mismatch")]
+  SizeMismatch,
+  #[error("video_io_error: {0}")]
+  Io(#[from] std::io::Error),
+}
+
+struct Chunk<'a> {
+  kind: [u8; 4],
+  data: &'a [u8],
+}
+
+fn parse_chunks(png: &[u8]) -> Result<Vec<Chunk<'_>>, VideoError> {
+  if png.len() < PNG_SIGNATURE.len() || png[..8] != PNG_SIGNATURE {
+    return Err(VideoError::NotPng);
+  }
+
+  let mut chunks = Vec::new();
+  let mut pos = 8;
+  while pos < png.len() {
+    if pos + 8 > png.len() {
+      return Err(VideoError::Truncated);
+    }
+    let len = u32::from_be_bytes([png[pos], png[pos + 1], png[pos + 2], png[pos + 3]]) as usize;
+    let kind = [png[pos + 4], png[pos + 5], png[pos + 6], png[pos + 7]];
+    let data_start = pos + 8;
+    let data_end = data_start.checked_add(len).ok_or(VideoError::Truncated)?;
+    if data_end + 4 > png.len() {
+      return Err(VideoError::Truncated);
+    }
+    chunks.push(Chunk {
+      kind,
+      data: &png[data_start..data_end],
+    });
+    pos = data_end + 4;
+    if &kind == b"IEND" {
+      break;
+    }
+  }
+  Ok(chunks)
+}
+
+fn write_chunk<W: Write>(w: &mut W, kind: &[u8; 4], data: &[u8]) -> std::io::Result<()> {
+  let mut crc = crc32fast::Hasher::new();
+  crc.update(kind);
+  crc.update(data);
+  w.write_all(&(data.len() as u32).to_be_bytes())?;
+  w.write_all(kind)?;
+  w.write_all(data)?;
+  w.write_all(&crc.finalize().to_be_bytes())
+}
+
+struct PendingFrame {
+  timestamp_ms: u64,
+  data: Vec<Vec<u8>>,
+}
+
+/// Streams PNG frames into an animated PNG without re-encoding them.
+///
+/// Frames are remuxed chunk by chunk; each frame's delay is the gap to the next frame's timestamp,
+/// so the file plays back at capture timing rather than a fixed rate.
+pub struct ApngWriter<W: Write + Seek> {
+  out: W,
+  ihdr: Option<Vec<u8>>,
+  width: u32,
+  height: u32,
+  actl_offset: u64,
+  sequence: u32,
+  frames: u32,
+  fallback_delay_ms: u64,
+  first_timestamp_ms: Option<u64>,
+  last_timestamp_ms: u64,
+  pending: Option<PendingFrame>,
+}
+
+impl ApngWriter<BufWriter<File>> {
+  pub fn create(path: &Path, fps: i32) -> Result<Self, VideoError> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+      std::fs::create_dir_all(dir)?;
+    }
+    Ok(Self::new(BufWriter::new(File::create(path)?), fps))
+  }
+}
+
+impl<W: Write + Seek> ApngWriter<W> {
+  pub fn new(out: W, fps: i32) -> Self {
+    Self {
+      out,
+      ihdr: None,
+      width: 0,
+      height: 0,
+      actl_offset: 0,
+      sequence: 0,
+      frames: 0,
+      fallback_delay_ms: 1000 / fps.clamp(1, 30) as u64,
+      first_timestamp_ms: None,
+      last_timestamp_ms: 0,
+      pending: None,
+    }
+  }
+
+  pub fn frame_count(&self) -> u32 {
+    self.frames + u32::from(self.pending.is_some())
+  }
+
+  pub fn duration_ms(&self) -> u64 {
+    let Some(first) = self.first_timestamp_ms else {
+      return 0;
+    };
+    self.last_timestamp_ms.saturating_sub(first) + self.fallback_delay_ms
+  }
+
+  pub fn push_frame(&mut self, png: &[u8], timestamp_ms: u64) -> Result<(), VideoError> {
+    let chunks = parse_chunks(png)?;
+    let ihdr = chunks
+      .iter()
+      .find(|c| &c.kind == b"IHDR" && c.data.len() >= 8)
+      .ok_or(VideoError::Truncated)?;
+
+    match &self.ihdr {
+      Some(first) if first.as_slice() != ihdr.data => return Err(VideoError::SizeMismatch),
+      Some(_) => {}
+      None => self.write_header(&chunks, ihdr.data)?,
+    }
+
+    let data: Vec<Vec<u8>> = chunks
+      .iter()
+      .filter(|c| &c.kind == b"IDAT")
+      .map(|c| c.data.to_vec())
+      .collect();
+    if data.is_empty() {
+      return Err(VideoError::Truncated);
+    }
+
+    if let Some(prev) = self.pending.take() {
+      let delay = timestamp_ms.saturating_sub(prev.timestamp_ms).max(1);
+      self.write_frame(prev, delay)?;
+    }
+    self.first_timestamp_ms.get_or_insert(timestamp_ms);
+    self.last_timestamp_ms = timestamp_ms;
+    self.pending = Some(PendingFrame { timestamp_ms, data });
+    Ok(())
+  }
+
+  /// Flushes the last frame, writes `IEND` and patches the frame count into `acTL`.
+  pub fn finish(mut self) -> Result<u32, VideoError> {
+    if let Some(last) = self.pending.take() {
+      let delay = self.fallback_delay_ms;
+      self.write_frame(last, delay)?;
+    }
+    if self.ihdr.is_none() {
+      self.out.flush()?;
+      return Ok(0);
+    }
+
+    write_chunk(&mut self.out, b"IEND", &[])?;
+    let end = self.out.stream_position()?;
+    self.out.seek(SeekFrom::Start(self.actl_offset))?;
+    write_chunk(&mut self.out, b"acTL", &actl(self.frames))?;
+    self.out.seek(SeekFrom::Start(end))?;
+    self.out.flush()?;
+    Ok(self.frames)
+  }
+
+  fn write_header(&mut self, chunks: &[Chunk<'_>], ihdr: &[u8]) -> Result<(), VideoError> {
+    self.width = u32::from_be_bytes([ihdr[0], ihdr[1], ihdr[2], ihdr[3]]);
+    self.height = u32::from_be_bytes([ihdr[4], ihdr[5], ihdr[6], ihdr[7]]);
+    self.ihdr = Some(ihdr.to_vec());
+
+    self.out.write_all(&PNG_SIGNATURE)?;
+    write_chunk(&mut self.out, b"IHDR", ihdr)?;
+    self.actl_offset = self.out.stream_position()?;
+    write_chunk(&mut self.out, b"acTL", &actl(0))?;
+
+    // Keep colour information (palette, gamma, sRGB, ...) that precedes the image data.
+    for chunk in chunks {
+      match &chunk.kind {
+        b"IHDR" | b"IDAT" | b"IEND" | b"acTL" | b"fcTL" | b"fdAT" => {}
+        kind => write_chunk(&mut self.out, kind, chunk.data)?,
+      }
+    }
+    Ok(())
+  }
+
+  fn write_frame(&mut self, frame: PendingFrame, delay_ms: u64) -> Result<(), VideoError> {
+    let mut fctl = Vec::with_capacity(26);
+    fctl.extend_from_slice(&self.next_sequence().to_be_bytes());
+    fctl.extend_from_slice(&self.width.to_be_bytes());
+    fctl.extend_from_slice(&self.height.to_be_bytes());
+    fctl.extend_from_slice(&0u32.to_be_bytes());
+    fctl.extend_from_slice(&0u32.to_be_bytes());
+    fctl.extend_from_slice(&(delay_ms.min(u16::MAX as u64) as u16).to_be_bytes());
+    fctl.extend_from_slice(&1000u16.to_be_bytes());
+    fctl.push(0); // dispose_op: none
+    fctl.push(0); // blend_op: source
+    write_chunk(&mut self.out, b"fcTL", &fctl)?;
+
+    for data in frame.data {
+      if self.frames == 0 {
+        write_chunk(&mut self.out, b"IDAT", &data)?;
+      } else {
+        let mut fdat = Vec::with_capacity(data.len() + 4);
+        fdat.extend_from_slice(&self.next_sequence().to_be_bytes());
+        fdat.extend_from_slice(&data);
+        write_chunk(&mut self.out, b"fdAT", &fdat)?;
+      }
+    }
+    self.frames += 1;
+    Ok(())
+  }
+
+  fn next_sequence(&mut self) -> u32 {
+    let seq = self.sequence;
+    self.sequence += 1;
+    seq
+  }
+}
+
+fn actl(frames: u32) -> [u8; 8] {
+  let mut data = [0u8; 8];
+  data[..4].copy_from_slice(&frames.to_be_bytes());
+  // num_plays = 0: loop forever.
+  data
+}
@@ -0,0 +1,1131 @@
+//! WebView2 helpers shared by the `wry` backed and the raw WebView2 texture backends.
+
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use webview2_com::Microsoft::Web::WebView2::Win32::*;
+use webview2_com::{
+  take_pwstr, AddScriptToExecuteOnDocumentCreatedCompletedHandler, BytesReceivedChangedEventHandler,
+  CallDevToolsProtocolMethodCompletedHandler, CapturePreviewCompletedHandler, DevToolsProtocolEventReceivedEventHandler,
+  DownloadStartingEventHandler, NavigationStartingEventHandler, PermissionRequestedEventHandler, StateChangedEventHandler, WebResourceRequestedEventHandler, GetCookiesCompletedHandler, PrintToPdfCompletedHandler, PrintToPdfStreamCompletedHandler,
+  ScriptDialogOpeningEventHandler,
+};
+use windows::core::{w, Error as WinError, Interface, HSTRING};
+use windows::Win32::System::Com::StructuredStorage::CreateStreamOnHGlobal;
+use windows::Win32::System::Com::{IStream, STATFLAG_NONAME, STREAM_SEEK_SET};
+
+use crate::cookies::{Cookie, CookieFilter, SameSite};
+use crate::dialog::{DialogAnswer, DialogKind};
+use crate::download::{unique_path, DownloadEvent};
+use crate::navigation::{HistoryAction, NavigationPolicy};
+use crate::pdf::PdfSettings;
+use crate::permission::{PermissionDecision, PermissionKind};
+use crate::screenshot::{decode_capture, full_page_metrics, parse_content_size, parse_element_rect, Clip, ScreenshotOptions};
+use crate::storage_state::{origin_of, parse_local_storage, read_local_storage_expression, OriginState, SENTINEL_PATH};
+
+pub(crate) fn read_stream_to_vec(stream: &IStream) -> Result<Vec<u8>, WinError> {
+  unsafe {
+    let mut stat = windows::Win32::System::Com::STATSTG::default();
+    stream.Stat(&mut stat, STATFLAG_NONAME)?;
+    let size = stat.cbSize as usize;
+
+    // reset to beginning
+    let mut _new_pos: u64 = 0;
+    stream.Seek(0, STREAM_SEEK_SET, Some(&mut _new_pos))?;
+
+    let mut buf = vec![0u8; size];
+    let mut read: u32 = 0;
+    stream.Read(buf.as_mut_ptr() as *mut _, size as u32, Some(&mut read)).ok()?;
+    buf.truncate(read as usize);
+    Ok(buf)
+  }
+}
+
+/// Captures the visible viewport as PNG; `done` runs on the webview thread.
+pub(crate) fn capture_preview_png(
+  webview: &ICoreWebView2,
+  done: impl FnOnce(Result<Vec<u8>, String>) + 'static,
+) -> Result<(), String> {
+  let stream = unsafe { CreateStreamOnHGlobal(Default::default(), true) }
+    .map_err(|e| format!("capture_stream_error: {e:?}"))?;
+
+  let stream2 = stream.clone();
+  let handler = CapturePreviewCompletedHandler::create(Box::new(move |err| {
+    if let Err(e) = err {
+      done(Err(format!("capture_error: {e:?}")));
+      return Err(e);
+    }
+    done(read_stream_to_vec(&stream2).map_err(|e| format!("capture_read_error: {e:?}")));
+    Ok(())
+  }));
+
+  unsafe { webview.CapturePreview(COREWEBVIEW2_CAPTURE_PREVIEW_IMAGE_FORMAT_PNG, &stream, &handler) }
+    .map_err(|e| format!("capture_start_error: {e:?}"))
+}
+
+/// Calls a DevTools protocol method; `done` receives the result object as JSON.
+///
+/// `done` is always invoked exactly once, also when the call cannot be started.
+pub(crate) fn call_devtools(
+  webview: &ICoreWebView2,
+  method: &str,
+  params_json: &str,
+  done: impl FnOnce(Result<String, String>) + 'static,
+) {
+  let slot = Rc::new(Cell::new(Some(done)));
+  let handler_slot = slot.clone();
+  let handler = CallDevToolsProtocolMethodCompletedHandler::create(Box::new(move |err, result| {
+    if let Some(done) = handler_slot.take() {
+      match err {
+        Ok(()) => done(Ok(result)),
+        Err(e) => done(Err(format!("devtools_error: {e:?}"))),
+      }
+    }
+    Ok(())
+  }));
+
+  let started = unsafe {
+    webview.CallDevToolsProtocolMethod(&HSTRING::from(method), &HSTRING::from(params_json), &handler)
+  };
+  if let Err(e) = started {
+    if let Some(done) = slot.take() {
+      done(Err(format!("devtools_call_error: {e:?}")));
+    }
+  }
+}
+
+/// Subscribes to a DevTools protocol event; `on_event` receives the event parameters as JSON.
+pub(crate) fn subscribe_devtools_event(
+  webview: &ICoreWebView2,
+  event: &str,
+  mut on_event: impl FnMut(String) + 'static,
+) -> Result<(), WinError> {
+  unsafe {
+    let receiver = webview.GetDevToolsProtocolEventReceiver(&HSTRING::from(event))?;
+    let mut token = 0i64;
+    receiver.add_DevToolsProtocolEventReceived(
+      &DevToolsProtocolEventReceivedEventHandler::create(Box::new(move |_, args| {
+        let Some(args) = args else { return Ok(()) };
+        let mut json = windows::core::PWSTR::null();
+        args.ParameterObjectAsJson(&mut json)?;
+        on_event(take_pwstr(json));
+        Ok(())
+      })),
+      &mut token,
+    )?;
+  }
+  Ok(())
+}
+
+/// Enables the DevTools `Network` domain and forwards its request lifecycle events.
+pub(crate) fn observe_network(
+  webview: &ICoreWebView2,
+  on_event: impl Fn(&'static str, String) + Clone + 'static,
+) -> Result<(), WinError> {
+  for event in crate::network::CDP_EVENTS {
+    let on_event = on_event.clone();
+    subscribe_devtools_event(webview, event, move |json| on_event(event, json))?;
+  }
+  call_devtools(webview, "Network.enable", "{}", |_| {});
+  Ok(())
+}
+
+/// Keeps file inputs from opening the native picker (it would block a hidden window) and reports
+/// each attempt to `opened` with whether several files are accepted.
+pub(crate) fn intercept_file_chooser(webview: &ICoreWebView2, opened: impl Fn(bool) + 'static) -> Result<(), WinError> {
+  subscribe_devtools_event(webview, "Page.fileChooserOpened", move |json| {
+    let params: serde_json::Value = serde_json::from_str(&json).unwrap_or_default();
+    opened(params.get("mode").and_then(|m| m.as_str()) == Some("selectMultiple"));
+  })?;
+  call_devtools(webview, "Page.enable", "{}", |_| {});
+  call_devtools(webview, "Page.setInterceptFileChooserDialog", r#"{"enabled":true}"#, |_| {});
+  Ok(())
+}
+
+type DoneSlot<T> = Rc<Cell<Option<Box<dyn FnOnce(Result<T, String>)>>>>;
+
+fn finish<T>(slot: &DoneSlot<T>, result: Result<T, String>) {
+  if let Some(done) = slot.take() {
+    done(result);
+  }
+}
+
+/// Captures the page through `Page.captureScreenshot`, resolving element and full-page clips first.
+///
+/// A full-page capture lays the page out at its content size for the capture and restores the
+/// viewport afterwards.
+pub(crate) fn capture_screenshot(
+  webview: &ICoreWebView2,
+  options: ScreenshotOptions,
+  done: impl FnOnce(Result<Vec<u8>, String>) + 'static,
+) {
+  let slot: DoneSlot<Vec<u8>> = Rc::new(Cell::new(Some(Box::new(done))));
+
+  let wv = webview.clone();
+  let capture_slot = slot.clone();
+  let layout_slot = slot.clone();
+  let expression = options.element_rect_expression();
+  let whole_page = options.full_page && options.clip.is_none();
+  let with_element = move |element: Option<Clip>| {
+    let wv2 = wv.clone();
+    let capture = move |content: Option<Clip>| {
+      let params = options.capture_params(element, content).to_string();
+      let wv3 = wv2.clone();
+      call_devtools(&wv2, "Page.captureScreenshot", &params, move |result| {
+        if content.is_some() {
+          call_devtools(&wv3, "Emulation.clearDeviceMetricsOverride", "{}", |_| {});
+        }
+        finish(&capture_slot, result.and_then(|json| decode_capture(&json)));
+      });
+    };
+
+    if !whole_page || element.is_some() {
+      return capture(None);
+    }
+    let wv3 = wv.clone();
+    call_devtools(&wv, "Page.getLayoutMetrics", "{}", move |result| {
+      let metrics = result
+        .ok()
+        .and_then(|json| parse_content_size(&json))
+        .ok_or_else(|| "screenshot_layout_error".to_string())
+        .and_then(|content| Ok((content, full_page_metrics(content)?)));
+      let (content, metrics) = match metrics {
+        Ok(found) => found,
+        Err(e) => return finish(&layout_slot, Err(e)),
+      };
+      call_devtools(&wv3, "Emulation.setDeviceMetricsOverride", &metrics.to_string(), move |result| {
+        match result {
+          Ok(_) => capture(Some(content)),
+          Err(e) => finish(&layout_slot, Err(e)),
+        }
+      });
+    });
+  };
+
+  let Some(expression) = expression else {
+    with_element(None);
+    return;
+  };
+  let params = serde_json::json!({ "expression": expression, "returnByValue": true }).to_string();
+  call_devtools(webview, "Runtime.evaluate", &params, move |result| {
+    match result.and_then(|json| parse_element_rect(&json)) {
+      Ok(rect) => with_element(Some(rect)),
+      Err(e) => finish(&slot, Err(e)),
+    }
+  });
+}
+
+fn print_settings(webview: &ICoreWebView2, settings: &PdfSettings) -> Result<ICoreWebView2PrintSettings, WinError> {
+  unsafe {
+    let environment = webview.cast::<ICoreWebView2_2>()?.Environment()?.cast::<ICoreWebView2Environment6>()?;
+    let print = environment.CreatePrintSettings()?;
+    print.SetOrientation(if settings.landscape {
+      COREWEBVIEW2_PRINT_ORIENTATION_LANDSCAPE
+    } else {
+      COREWEBVIEW2_PRINT_ORIENTATION_PORTRAIT
+    })?;
+    print.SetPageWidth(settings.page_width)?;
+    print.SetPageHeight(settings.page_height)?;
+    print.SetMarginTop(settings.margin_top)?;
+    print.SetMarginRight(settings.margin_right)?;
+    print.SetMarginBottom(settings.margin_bottom)?;
+    print.SetMarginLeft(settings.margin_left)?;
+    print.SetScaleFactor(settings.scale)?;
+    print.SetShouldPrintBackgrounds(settings.print_background)?;
+    print.SetShouldPrintHeaderAndFooter(settings.display_header_footer)?;
+    Ok(print)
+  }
+}
+
+/// Prints the page to PDF. With a `path` the file is written by WebView2 and `done` gets `None`;
+/// otherwise the document bytes are streamed back.
+pub(crate) fn print_to_pdf(
+  webview: &ICoreWebView2,
+  settings: &PdfSettings,
+  path: Option<&str>,
+  done: impl FnOnce(Result<Option<Vec<u8>>, String>) + 'static,
+) {
+  let slot: DoneSlot<Option<Vec<u8>>> = Rc::new(Cell::new(Some(Box::new(done))));
+  let print = match print_settings(webview, settings) {
+    Ok(print) => print,
+    Err(e) => return finish(&slot, Err(format!("pdf_settings_error: {e:?}"))),
+  };
+
+  let handler_slot = slot.clone();
+  let started = match path {
+    Some(path) => {
+      let handler = PrintToPdfCompletedHandler::create(Box::new(move |err, success| {
+        let result = match err {
+          Ok(()) if success => Ok(None),
+          Ok(()) => Err("pdf_print_failed".to_string()),
+          Err(e) => Err(format!("pdf_print_error: {e:?}")),
+        };
+        finish(&handler_slot, result);
+        Ok(())
+      }));
+      webview
+        .cast::<ICoreWebView2_7>()
+        .and_then(|wv| unsafe { wv.PrintToPdf(&HSTRING::from(path), &print, &handler) })
+    }
+    None => {
+      let handler = PrintToPdfStreamCompletedHandler::create(Box::new(move |err, stream| {
+        let result = match (err, stream) {
+          (Ok(()), Some(stream)) => read_stream_to_vec(&stream)
+            .map(Some)
+            .map_err(|e| format!("pdf_read_error: {e:?}")),
+          (Ok(()), None) => Err("pdf_print_failed".to_string()),
+          (Err(e), _) => Err(format!("pdf_print_error: {e:?}")),
+        };
+        finish(&handler_slot, result);
+        Ok(())
+      }));
+      webview
+        .cast::<ICoreWebView2_16>()
+        .and_then(|wv| unsafe { wv.PrintToPdfStream(&print, &handler) })
+    }
+  };
+  if let Err(e) = started {
+    finish(&slot, Err(format!("pdf_start_error: {e:?}")));
+  }
+}
+
+pub(crate) fn cookie_manager(webview: &ICoreWebView2) -> Result<ICoreWebView2CookieManager, String> {
+  unsafe { webview.cast::<ICoreWebView2_2>().and_then(|wv| wv.CookieManager()) }
+    .map_err(|e| format!("cookie_manager_error: {e:?}"))
+}
+
+fn cookie_record(cookie: &ICoreWebView2Cookie) -> Result<Cookie, WinError> {
+  unsafe {
+    let text = |get: &dyn Fn(*mut windows::core::PWSTR) -> windows::core::Result<()>| {
+      let mut value = windows::core::PWSTR::null();
+      get(&mut value).map(|()| take_pwstr(value))
+    };
+    let mut expires = -1.0;
+    let mut is_session = windows::core::BOOL::default();
+    let mut http_only = windows::core::BOOL::default();
+    let mut secure = windows::core::BOOL::default();
+    let mut same_site = COREWEBVIEW2_COOKIE_SAME_SITE_KIND_LAX;
+    cookie.Expires(&mut expires)?;
+    cookie.IsSession(&mut is_session)?;
+    cookie.IsHttpOnly(&mut http_only)?;
+    cookie.IsSecure(&mut secure)?;
+    cookie.SameSite(&mut same_site)?;
+    Ok(Cookie {
+      name: text(&|p| cookie.Name(p))?,
+      value: text(&|p| cookie.Value(p))?,
+      domain: text(&|p| cookie.Domain(p))?,
+      path: text(&|p| cookie.Path(p))?,
+      expires: if is_session.as_bool() { -1.0 } else { expires },
+      http_only: http_only.as_bool(),
+      secure: secure.as_bool(),
+      same_site: match same_site {
+        COREWEBVIEW2_COOKIE_SAME_SITE_KIND_STRICT => SameSite::Strict,
+        COREWEBVIEW2_COOKIE_SAME_SITE_KIND_NONE => SameSite::None,
+        _ => SameSite::Lax,
+      },
+    })
+  }
+}
+
+/// Lists the cookies sent to `uri` (all cookies when empty) with their native handles.
+fn list_cookies(
+  manager: &ICoreWebView2CookieManager,
+  uri: &str,
+  done: impl FnOnce(Result<Vec<(ICoreWebView2Cookie, Cookie)>, String>) + 'static,
+) {
+  let slot: DoneSlot<Vec<(ICoreWebView2Cookie, Cookie)>> = Rc::new(Cell::new(Some(Box::new(done))));
+  let handler_slot = slot.clone();
+  let handler = GetCookiesCompletedHandler::create(Box::new(move |err, list| {
+    let result = err
+      .and_then(|()| {
+        let Some(list) = list else { return Ok(Vec::new()) };
+        let mut count = 0u32;
+        unsafe { list.Count(&mut count)? };
+        (0..count)
+          .map(|i| {
+            let cookie = unsafe { list.GetValueAtIndex(i)? };
+            let record = cookie_record(&cookie)?;
+            Ok((cookie, record))
+          })
+          .collect()
+      })
+      .map_err(|e| format!("cookies_get_error: {e:?}"));
+    finish(&handler_slot, result);
+    Ok(())
+  }));
+  if let Err(e) = unsafe { manager.GetCookies(&HSTRING::from(uri), &handler) } {
+    finish(&slot, Err(format!("cookies_get_error: {e:?}")));
+  }
+}
+
+/// Collects the cookies for every URL in turn; an empty list returns all cookies of the profile.
+pub(crate) fn get_cookies(
+  manager: &ICoreWebView2CookieManager,
+  mut urls: Vec<String>,
+  done: impl FnOnce(Result<Vec<Cookie>, String>) + 'static,
+) {
+  fn next(
+    manager: ICoreWebView2CookieManager,
+    mut urls: std::vec::IntoIter<String>,
+    mut acc: Vec<Cookie>,
+    done: Box<dyn FnOnce(Result<Vec<Cookie>, String>)>,
+  ) {
+    let Some(url) = urls.next() else {
+      return done(Ok(crate::cookies::dedupe(acc)));
+    };
+    let manager2 = manager.clone();
+    list_cookies(&manager, &url, move |result| match result {
+      Ok(cookies) => {
+        acc.extend(cookies.into_iter().map(|(_, record)| record));
+        next(manager2, urls, acc, done);
+      }
+      Err(e) => done(Err(e)),
+    });
+  }
+
+  if urls.is_empty() {
+    urls.push(String::new());
+  }
+  next(manager.clone(), urls.into_iter(), Vec::new(), Box::new(done));
+}
+
+pub(crate) fn set_cookie(manager: &ICoreWebView2CookieManager, cookie: &Cookie) -> Result<(), String> {
+  let add = || -> Result<(), WinError> {
+    unsafe {
+      let native = manager.CreateCookie(
+        &HSTRING::from(cookie.name.as_str()),
+        &HSTRING::from(cookie.value.as_str()),
+        &HSTRING::from(cookie.domain.as_str()),
+        &HSTRING::from(cookie.path.as_str()),
+      )?;
+      if cookie.expires > 0.0 {
+        native.SetExpires(cookie.expires)?;
+      }
+      native.SetIsHttpOnly(cookie.http_only)?;
+      native.SetIsSecure(cookie.secure)?;
+      native.SetSameSite(match cookie.same_site {
+        SameSite::Lax => COREWEBVIEW2_COOKIE_SAME_SITE_KIND_LAX,
+        SameSite::Strict => COREWEBVIEW2_COOKIE_SAME_SITE_KIND_STRICT,
+        SameSite::None => COREWEBVIEW2_COOKIE_SAME_SITE_KIND_NONE,
+      })?;
+      manager.AddOrUpdateCookie(&native)
+    }
+  };
+  add().map_err(|e| format!("cookie_set_error: {}: {e:?}", cookie.name))
+}
+
+/// Deletes the cookies matching `filter`; `done` receives how many were removed.
+pub(crate) fn clear_cookies(
+  manager: &ICoreWebView2CookieManager,
+  filter: CookieFilter,
+  done: impl FnOnce(Result<usize, String>) + 'static,
+) {
+  let manager2 = manager.clone();
+  list_cookies(manager, "", move |result| {
+    let result = result.and_then(|cookies| {
+      let matching: Vec<_> = cookies.iter().filter(|(_, record)| filter.matches(record)).collect();
+      for (native, _) in &matching {
+        unsafe { manager2.DeleteCookie(native) }.map_err(|e| format!("cookies_clear_error: {e:?}"))?;
+      }
+      Ok(matching.len())
+    });
+    done(result);
+  });
+}
+
+/// Current `(url, title)` of a webview, for `tab_list`.
+pub(crate) fn page_info(webview: &ICoreWebView2) -> (String, String) {
+  let mut url_ptr = windows::core::PWSTR::null();
+  let mut title_ptr = windows::core::PWSTR::null();
+  unsafe {
+    let url = webview.Source(&mut url_ptr).map(|()| take_pwstr(url_ptr)).unwrap_or_default();
+    let title = webview.DocumentTitle(&mut title_ptr).map(|()| take_pwstr(title_ptr)).unwrap_or_default();
+    (url, title)
+  }
+}
+
+/// `(can_go_back, can_go_forward)` of a webview.
+pub(crate) fn history_state(webview: &ICoreWebView2) -> (bool, bool) {
+  let mut back = windows::core::BOOL::default();
+  let mut forward = windows::core::BOOL::default();
+  unsafe {
+    let _ = webview.CanGoBack(&mut back);
+    let _ = webview.CanGoForward(&mut forward);
+  }
+  (back.as_bool(), forward.as_bool())
+}
+
+/// Runs `action`; `Ok(false)` when there is no history entry to move to.
+pub(crate) fn apply_history(webview: &ICoreWebView2, action: HistoryAction) -> Result<bool, String> {
+  let (can_go_back, can_go_forward) = history_state(webview);
+  let result = unsafe {
+    match action {
+      HistoryAction::Back if !can_go_back => return Ok(false),
+      HistoryAction::Forward if !can_go_forward => return Ok(false),
+      HistoryAction::Back => webview.GoBack(),
+      HistoryAction::Forward => webview.GoForward(),
+      HistoryAction::Reload => webview.Reload(),
+      HistoryAction::Stop => webview.Stop(),
+    }
+  };
+  result.map(|()| true).map_err(|e| format!("{}_error: {e:?}", action.as_str()))
+}
+
+/// Registers a script that runs at document creation; `done` receives its id for removal.
+pub(crate) fn add_document_script(
+  webview: &ICoreWebView2,
+  js: &str,
+  done: impl FnOnce(Result<String, String>) + 'static,
+) {
+  let slot: DoneSlot<String> = Rc::new(Cell::new(Some(Box::new(done))));
+  let handler_slot = slot.clone();
+  let handler = AddScriptToExecuteOnDocumentCreatedCompletedHandler::create(Box::new(move |err, id| {
+    finish(&handler_slot, err.map(|()| id).map_err(|e| format!("init_script_error: {e:?}")));
+    Ok(())
+  }));
+  if let Err(e) = unsafe { webview.AddScriptToExecuteOnDocumentCreated(&HSTRING::from(js), &handler) } {
+    finish(&slot, Err(format!("init_script_error: {e:?}")));
+  }
+}
+
+pub(crate) fn remove_document_script(webview: &ICoreWebView2, id: &str) {
+  let _ = unsafe { webview.RemoveScriptToExecuteOnDocumentCreated(&HSTRING::from(id)) };
+}
+
+/// Answers requests for the storage-state sentinel page with an empty document.
+///
+/// `Fetch` is only enabled (for the sentinel URLs) on the scratch webview of [`collect_local_storage`].
+pub(crate) fn serve_storage_sentinel(webview: &ICoreWebView2) -> Result<(), WinError> {
+  let wv = webview.clone();
+  subscribe_devtools_event(webview, "Fetch.requestPaused", move |params| {
+    let Some(request_id) = serde_json::from_str::<serde_json::Value>(&params)
+      .ok()
+      .and_then(|p| p.get("requestId").and_then(|v| v.as_str()).map(str::to_string))
+    else {
+      return;
+    };
+    let fulfill = serde_json::json!({
+      "requestId": request_id,
+      "responseCode": 200,
+      "responseHeaders": [{ "name": "Content-Type", "value": "text/html" }],
+      "body": "PCFkb2N0eXBlIGh0bWw+PHRpdGxlPjwvdGl0bGU+",
+    });
+    call_devtools(&wv, "Fetch.fulfillRequest", &fulfill.to_string(), |_| {});
+  })
+}
+
+struct StorageCollect {
+  active: ICoreWebView2,
+  scratch: Option<ICoreWebView2>,
+  current_origin: Option<String>,
+  canceled: Rc<Cell<bool>>,
+}
+
+/// Reads `localStorage` for each of `origins` (from `storage_state::storage_origins`). The active page's origin
+/// is read in place; other origins are visited at [`SENTINEL_PATH`] in `scratch`, a hidden webview
+/// of the same profile, so the user's tab never navigates. Setting `canceled` (see
+/// [`cancel_storage_collect`]) stops the chain; `done` is then never called.
+pub(crate) fn collect_local_storage(
+  active: &ICoreWebView2,
+  scratch: Option<&ICoreWebView2>,
+  current_origin: Option<String>,
+  origins: Vec<String>,
+  canceled: Rc<Cell<bool>>,
+  done: impl FnOnce(Result<Vec<OriginState>, String>) + 'static,
+) {
+  let patterns: Vec<serde_json::Value> = origins
+    .iter()
+    .filter(|o| Some(*o) != current_origin.as_ref())
+    .map(|o| serde_json::json!({ "urlPattern": format!("{o}{SENTINEL_PATH}*") }))
+    .collect();
+  let ctx = Rc::new(StorageCollect {
+    active: active.clone(),
+    scratch: scratch.cloned(),
+    current_origin,
+    canceled,
+  });
+  let queue = VecDeque::from(origins);
+  let done: Box<dyn FnOnce(Result<Vec<OriginState>, String>)> = Box::new(done);
+  if patterns.is_empty() {
+    return collect_next(ctx, queue, Vec::new(), done);
+  }
+  let Some(scratch) = ctx.scratch.clone() else {
+    return done(Err("storage_state_scratch_missing".to_string()));
+  };
+  if let Err(e) = serve_storage_sentinel(&scratch) {
+    return done(Err(format!("storage_state_sentinel_error: {e:?}")));
+  }
+  let fetch_params = serde_json::json!({ "patterns": patterns }).to_string();
+  call_devtools(&scratch, "Fetch.enable", &fetch_params, move |result| match result {
+    Ok(_) => collect_next(ctx, queue, Vec::new(), done),
+    Err(e) => done(Err(e)),
+  });
+}
+
+fn collect_next(
+  ctx: Rc<StorageCollect>,
+  mut queue: VecDeque<String>,
+  acc: Vec<OriginState>,
+  done: Box<dyn FnOnce(Result<Vec<OriginState>, String>)>,
+) {
+  if ctx.canceled.get() {
+    return;
+  }
+  let Some(origin) = queue.pop_front() else {
+    return collect_finish(&ctx, Ok(acc), done);
+  };
+  if Some(&origin) == ctx.current_origin.as_ref() {
+    return read_origin_storage(ctx, origin, false, 0, queue, acc, done);
+  }
+  let Some(scratch) = ctx.scratch.clone() else {
+    return collect_finish(&ctx, Err("storage_state_scratch_missing".to_string()), done);
+  };
+
+  let params = serde_json::json!({ "url": format!("{origin}{SENTINEL_PATH}") }).to_string();
+  call_devtools(&scratch, "Page.navigate", &params, move |result| {
+    let error = match &result {
+      Ok(json) => serde_json::from_str::<serde_json::Value>(json)
+        .ok()
+        .and_then(|v| v.get("errorText").and_then(|e| e.as_str()).map(str::to_string)),
+      Err(e) => Some(e.clone()),
+    };
+    match error {
+      Some(e) => collect_finish(&ctx, Err(format!("storage_state_navigate_error: {e}")), done),
+      None => read_origin_storage(ctx, origin, true, 0, queue, acc, done),
+    }
+  });
+}
+
+fn read_origin_storage(
+  ctx: Rc<StorageCollect>,
+  origin: String,
+  at_sentinel: bool,
+  attempt: u32,
+  queue: VecDeque<String>,
+  mut acc: Vec<OriginState>,
+  done: Box<dyn FnOnce(Result<Vec<OriginState>, String>)>,
+) {
+  if ctx.canceled.get() {
+    return;
+  }
+  let params = serde_json::json!({
+    "expression": read_local_storage_expression(at_sentinel),
+    "awaitPromise": true,
+    "returnByValue": true,
+  })
+  .to_string();
+  let target = if at_sentinel { ctx.scratch.clone() } else { Some(ctx.active.clone()) };
+  let Some(target) = target else {
+    return collect_finish(&ctx, Err("storage_state_scratch_missing".to_string()), done);
+  };
+  call_devtools(&target, "Runtime.evaluate", &params, move |result| {
+    // The previous document may still be current, or torn down mid-call, right after `Page.navigate`.
+    match result.and_then(|json| parse_local_storage(&json)) {
+      Ok(Some(local_storage)) => {
+        acc.push(OriginState { origin, local_storage });
+        collect_next(ctx, queue, acc, done);
+      }
+      Ok(None) | Err(_) if at_sentinel && attempt < 40 => {
+        read_origin_storage(ctx, origin, at_sentinel, attempt + 1, queue, acc, done);
+      }
+      Ok(None) => collect_finish(&ctx, Err("storage_state_read_timeout".to_string()), done),
+      Err(e) => collect_finish(&ctx, Err(e), done),
+    }
+  });
+}
+
+fn collect_finish(
+  ctx: &StorageCollect,
+  result: Result<Vec<OriginState>, String>,
+  done: Box<dyn FnOnce(Result<Vec<OriginState>, String>)>,
+) {
+  if let Some(scratch) = &ctx.scratch {
+    call_devtools(scratch, "Fetch.disable", "{}", |_| {});
+  }
+  if !ctx.canceled.get() {
+    done(result);
+  }
+}
+
+/// Stops a [`collect_local_storage`] chain after a timeout and turns off its interception.
+pub(crate) fn cancel_storage_collect(canceled: &Cell<bool>, scratch: Option<&ICoreWebView2>) {
+  canceled.set(true);
+  if let Some(scratch) = scratch {
+    call_devtools(scratch, "Fetch.disable", "{}", |_| {});
+  }
+}
+
+/// A JavaScript dialog held open by its deferral until it is answered.
+pub(crate) struct ScriptDialog {
+  pub id: u64,
+  /// Tab whose page opened the dialog.
+  pub tab: i64,
+  args: ICoreWebView2ScriptDialogOpeningEventArgs,
+  deferral: ICoreWebView2Deferral,
+  pub kind: DialogKind,
+  pub default_text: String,
+}
+
+impl ScriptDialog {
+  pub fn respond(self, answer: &DialogAnswer) -> Result<(), String> {
+    unsafe {
+      if answer.accept {
+        if let Some(text) = &answer.text {
+          self.args.SetResultText(&HSTRING::from(text.as_str())).map_err(|e| format!("dialog_error: {e:?}"))?;
+        }
+        self.args.Accept().map_err(|e| format!("dialog_error: {e:?}"))?;
+      }
+      self.deferral.Complete().map_err(|e| format!("dialog_error: {e:?}"))
+    }
+  }
+}
+
+/// Dialogs waiting for an answer, oldest first; shared by every webview of a backend thread.
+pub(crate) type DialogQueue = Rc<RefCell<VecDeque<ScriptDialog>>>;
+
+/// State shared by the event handlers of every webview on a backend thread.
+#[derive(Clone, Default)]
+pub(crate) struct PageHooks {
+  pub dialogs: DialogQueue,
+  pub documents: ServedDocuments,
+  pub downloads: Rc<Downloads>,
+  pub navigation: Rc<RefCell<NavigationPolicy>>,
+  pub permissions: PermissionQueue,
+}
+
+static NEXT_DIALOG_ID: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(1);
+
+pub(crate) fn take_dialog(queue: &DialogQueue, id: u64) -> Option<ScriptDialog> {
+  let mut queue = queue.borrow_mut();
+  let pos = queue.iter().position(|d| d.id == id)?;
+  queue.remove(pos)
+}
+
+/// Forgets the dialogs of a closed tab, so `dialog_respond` does not answer a page that is gone.
+pub(crate) fn drop_tab_dialogs(queue: &DialogQueue, tab: i64) {
+  queue.borrow_mut().retain(|d| d.tab != tab);
+}
+
+/// Replaces the built-in dialog UI: each dialog is deferred, queued and reported to `opened`
+/// as `(id, kind, message, default_text)`.
+pub(crate) fn observe_script_dialogs(
+  webview: &ICoreWebView2,
+  tab: i64,
+  queue: &DialogQueue,
+  opened: impl Fn(u64, DialogKind, String, String) + 'static,
+) -> Result<(), WinError> {
+  unsafe {
+    webview.Settings()?.SetAreDefaultScriptDialogsEnabled(false)?;
+  }
+  let queue = queue.clone();
+  let handler = ScriptDialogOpeningEventHandler::create(Box::new(move |_, args| {
+    let Some(args) = args else { return Ok(()) };
+    unsafe {
+      let mut kind = COREWEBVIEW2_SCRIPT_DIALOG_KIND::default();
+      args.Kind(&mut kind)?;
+      let kind = match kind {
+        COREWEBVIEW2_SCRIPT_DIALOG_KIND_CONFIRM => DialogKind::Confirm,
+        COREWEBVIEW2_SCRIPT_DIALOG_KIND_PROMPT => DialogKind::Prompt,
+        COREWEBVIEW2_SCRIPT_DIALOG_KIND_BEFOREUNLOAD => DialogKind::BeforeUnload,
+        _ => DialogKind::Alert,
+      };
+      let mut message = windows::core::PWSTR::null();
+      args.Message(&mut message)?;
+      let message = take_pwstr(message);
+      let mut default_text = windows::core::PWSTR::null();
+      args.DefaultText(&mut default_text)?;
+      let default_text = take_pwstr(default_text);
+      let deferral = args.GetDeferral()?;
+      let id = NEXT_DIALOG_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+      queue.borrow_mut().push_back(ScriptDialog {
+        id,
+        tab,
+        args,
+        deferral,
+        kind,
+        default_text: default_text.clone(),
+      });
+      opened(id, kind, message, default_text);
+    }
+    Ok(())
+  }));
+  let mut token = 0i64;
+  unsafe { webview.add_ScriptDialogOpening(&handler, &mut token) }
+}
+
+/// Download directory and the downloads still running, by download id.
+#[derive(Default)]
+pub(crate) struct Downloads {
+  dir: RefCell<PathBuf>,
+  active: RefCell<HashMap<i64, ICoreWebView2DownloadOperation>>,
+}
+
+static NEXT_DOWNLOAD_ID: std::sync::atomic::AtomicI64 = std::sync::atomic::AtomicI64::new(1);
+
+impl Downloads {
+  pub fn set_dir(&self, dir: PathBuf) {
+    *self.dir.borrow_mut() = dir;
+  }
+
+  pub fn cancel(&self, id: i64) -> Result<(), String> {
+    let operation = self.active.borrow().get(&id).cloned();
+    let operation = operation.ok_or_else(|| format!("download_not_found: {id}"))?;
+    unsafe { operation.Cancel() }.map_err(|e| format!("download_cancel_error: {e:?}"))
+  }
+}
+
+fn download_bytes(operation: &ICoreWebView2DownloadOperation) -> Result<(i64, i64), WinError> {
+  let mut received = 0i64;
+  let mut total = 0i64;
+  unsafe {
+    operation.BytesReceived(&mut received)?;
+    operation.TotalBytesToReceive(&mut total)?;
+  }
+  Ok((received, if total > 0 { total } else { -1 }))
+}
+
+/// Saves downloads into the configured directory without the built-in download UI and reports
+/// their lifecycle to `on_event` by download id.
+pub(crate) fn observe_downloads(
+  webview: &ICoreWebView2,
+  downloads: &Rc<Downloads>,
+  on_event: impl Fn(i64, DownloadEvent) + Clone + 'static,
+) -> Result<(), WinError> {
+  let webview4 = webview.cast::<ICoreWebView2_4>()?;
+  let downloads = downloads.clone();
+  let handler = DownloadStartingEventHandler::create(Box::new(move |_, args| {
+    let Some(args) = args else { return Ok(()) };
+    let dir = downloads.dir.borrow().clone();
+    if dir.as_os_str().is_empty() {
+      return Ok(());
+    }
+    unsafe {
+      let operation = args.DownloadOperation()?;
+      let mut uri = windows::core::PWSTR::null();
+      operation.Uri(&mut uri)?;
+      let url = take_pwstr(uri);
+      let mut default_path = windows::core::PWSTR::null();
+      args.ResultFilePath(&mut default_path)?;
+      let default_path = take_pwstr(default_path);
+      let suggested_filename = Path::new(&default_path)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+      let _ = std::fs::create_dir_all(&dir);
+      let path = unique_path(&dir, &suggested_filename).to_string_lossy().into_owned();
+      args.SetResultFilePath(&HSTRING::from(path.as_str()))?;
+      args.SetHandled(true)?;
+
+      let id = NEXT_DOWNLOAD_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+      downloads.active.borrow_mut().insert(id, operation.clone());
+      on_event(id, DownloadEvent::Started { url, suggested_filename, path: path.clone() });
+
+      let on_progress = on_event.clone();
+      let mut token = 0i64;
+      operation.add_BytesReceivedChanged(
+        &BytesReceivedChangedEventHandler::create(Box::new(move |operation, _| {
+          let Some(operation) = operation else { return Ok(()) };
+          let (received_bytes, total_bytes) = download_bytes(&operation)?;
+          on_progress(id, DownloadEvent::Progress { received_bytes, total_bytes });
+          Ok(())
+        })),
+        &mut token,
+      )?;
+
+      let on_finished = on_event.clone();
+      let finished = Rc::downgrade(&downloads);
+      operation.add_StateChanged(
+        &StateChangedEventHandler::create(Box::new(move |operation, _| {
+          let Some(operation) = operation else { return Ok(()) };
+          let mut state = COREWEBVIEW2_DOWNLOAD_STATE::default();
+          operation.State(&mut state)?;
+          let error = match state {
+            COREWEBVIEW2_DOWNLOAD_STATE_COMPLETED => None,
+            COREWEBVIEW2_DOWNLOAD_STATE_INTERRUPTED => {
+              let mut reason = COREWEBVIEW2_DOWNLOAD_INTERRUPT_REASON::default();
+              operation.InterruptReason(&mut reason)?;
+              Some(if reason == COREWEBVIEW2_DOWNLOAD_INTERRUPT_REASON_USER_CANCELED {
+                "download_canceled".to_string()
+              } else {
+                format!("download_interrupted: {}", reason.0)
+              })
+            }
+            _ => return Ok(()),
+          };
+          if let Some(downloads) = finished.upgrade() {
+            downloads.active.borrow_mut().remove(&id);
+          }
+          let (received_bytes, _) = download_bytes(&operation)?;
+          on_finished(id, DownloadEvent::Finished { path: path.clone(), received_bytes, error });
+          Ok(())
+        })),
+        &mut token,
+      )?;
+    }
+    Ok(())
+  }));
+  let mut token = 0i64;
+  unsafe { webview4.add_DownloadStarting(&handler, &mut token) }
+}
+
+/// Enforces the navigation policy: blocked top-level and frame navigations are canceled and
+/// blocked subresources answered with 403. Top-level blocks are reported to `blocked` as
+/// `(url, error)`.
+pub(crate) fn enforce_navigation_policy(
+  webview: &ICoreWebView2,
+  policy: &Rc<RefCell<NavigationPolicy>>,
+  blocked: impl Fn(String, String) + 'static,
+) -> Result<(), WinError> {
+  let mut token = 0i64;
+  let top_policy = policy.clone();
+  let handler = NavigationStartingEventHandler::create(Box::new(move |_, args| {
+    let Some(args) = args else { return Ok(()) };
+    unsafe {
+      let mut uri = windows::core::PWSTR::null();
+      args.Uri(&mut uri)?;
+      let url = take_pwstr(uri);
+      if let Err(e) = top_policy.borrow().check(&url) {
+        args.SetCancel(true)?;
+        blocked(url, e);
+      }
+    }
+    Ok(())
+  }));
+  unsafe { webview.add_NavigationStarting(&handler, &mut token)? };
+
+  let frame_policy = policy.clone();
+  let handler = NavigationStartingEventHandler::create(Box::new(move |_, args| {
+    let Some(args) = args else { return Ok(()) };
+    unsafe {
+      let mut uri = windows::core::PWSTR::null();
+      args.Uri(&mut uri)?;
+      if frame_policy.borrow().check(&take_pwstr(uri)).is_err() {
+        args.SetCancel(true)?;
+      }
+    }
+    Ok(())
+  }));
+  unsafe { webview.add_FrameNavigationStarting(&handler, &mut token)? };
+
+  let environment = unsafe { webview.cast::<ICoreWebView2_2>()?.Environment()? };
+  let resource_policy = policy.clone();
+  let handler = WebResourceRequestedEventHandler::create(Box::new(move |_, args| {
+    let Some(args) = args else { return Ok(()) };
+    let policy = resource_policy.borrow();
+    if policy.is_unrestricted() {
+      return Ok(());
+    }
+    unsafe {
+      let mut uri = windows::core::PWSTR::null();
+      args.Request()?.Uri(&mut uri)?;
+      if policy.check(&take_pwstr(uri)).is_err() {
+        let response = environment.CreateWebResourceResponse(None, 403, w!("Forbidden"), w!(""))?;
+        args.SetResponse(&response)?;
+      }
+    }
+    Ok(())
+  }));
+  unsafe { webview.add_WebResourceRequested(&handler, &mut token)? };
+  if !policy.borrow().is_unrestricted() {
+    filter_all_resources(webview, true)?;
+  }
+  Ok(())
+}
+
+/// Adds or removes the catch-all resource filter behind [`enforce_navigation_policy`]; it is only
+/// installed while the policy has rules, so unrestricted pages skip the per-request round-trip.
+pub(crate) fn filter_all_resources(webview: &ICoreWebView2, enabled: bool) -> Result<(), WinError> {
+  unsafe {
+    // Without source kinds the filter only sees requests made by the main document.
+    match webview.cast::<ICoreWebView2_22>() {
+      Ok(webview22) if enabled => webview22.AddWebResourceRequestedFilterWithRequestSourceKinds(
+        w!("*"),
+        COREWEBVIEW2_WEB_RESOURCE_CONTEXT_ALL,
+        COREWEBVIEW2_WEB_RESOURCE_REQUEST_SOURCE_KINDS_ALL,
+      ),
+      Ok(webview22) => webview22.RemoveWebResourceRequestedFilterWithRequestSourceKinds(
+        w!("*"),
+        COREWEBVIEW2_WEB_RESOURCE_CONTEXT_ALL,
+        COREWEBVIEW2_WEB_RESOURCE_REQUEST_SOURCE_KINDS_ALL,
+      ),
+      Err(_) if enabled => webview.AddWebResourceRequestedFilter(w!("*"), COREWEBVIEW2_WEB_RESOURCE_CONTEXT_ALL),
+      Err(_) => webview.RemoveWebResourceRequestedFilter(w!("*"), COREWEBVIEW2_WEB_RESOURCE_CONTEXT_ALL),
+    }
+  }
+}
+
+/// Documents `set_content` serves at their base URL, by tab; each is served once.
+pub(crate) type ServedDocuments = Rc<RefCell<HashMap<i64, ServedDocument>>>;
+
+pub(crate) struct ServedDocument {
+  /// As normalized by `content::document_url`.
+  pub url: String,
+  pub html: String,
+}
+
+/// Answers the tab's next document request for a queued [`ServedDocument`] with its HTML.
+pub(crate) fn serve_documents(webview: &ICoreWebView2, tab: i64, documents: &ServedDocuments) -> Result<(), WinError> {
+  let environment = unsafe { webview.cast::<ICoreWebView2_2>()?.Environment()? };
+  let documents = documents.clone();
+  let wv = webview.clone();
+  let handler = WebResourceRequestedEventHandler::create(Box::new(move |_, args| {
+    let Some(args) = args else { return Ok(()) };
+    unsafe {
+      let mut context = COREWEBVIEW2_WEB_RESOURCE_CONTEXT_ALL;
+      args.ResourceContext(&mut context)?;
+      let mut uri = windows::core::PWSTR::null();
+      args.Request()?.Uri(&mut uri)?;
+      let uri = take_pwstr(uri);
+      let uri = uri.split('#').next().unwrap_or_default();
+      let requested = documents.borrow().get(&tab).is_some_and(|d| d.url == uri);
+      if context != COREWEBVIEW2_WEB_RESOURCE_CONTEXT_DOCUMENT || !requested {
+        return Ok(());
+      }
+      let Some(document) = documents.borrow_mut().remove(&tab) else { return Ok(()) };
+      drop_document_filter(&wv, &document);
+
+      let stream = CreateStreamOnHGlobal(Default::default(), true)?;
+      stream.Write(document.html.as_ptr() as *const _, document.html.len() as u32, None).ok()?;
+      stream.Seek(0, STREAM_SEEK_SET, None)?;
+      let response =
+        environment.CreateWebResourceResponse(&stream, 200, w!("OK"), w!("Content-Type: text/html; charset=utf-8"))?;
+      args.SetResponse(&response)?;
+    }
+    Ok(())
+  }));
+  let mut token = 0i64;
+  unsafe { webview.add_WebResourceRequested(&handler, &mut token) }
+}
+
+/// Queues `document` for [`serve_documents`]; the caller then navigates the tab to its URL.
+pub(crate) fn queue_document(
+  webview: &ICoreWebView2,
+  tab: i64,
+  documents: &ServedDocuments,
+  document: ServedDocument,
+) -> Result<(), WinError> {
+  drop_document(webview, tab, documents);
+  unsafe {
+    webview.AddWebResourceRequestedFilter(&HSTRING::from(&document.url), COREWEBVIEW2_WEB_RESOURCE_CONTEXT_DOCUMENT)?;
+  }
+  documents.borrow_mut().insert(tab, document);
+  Ok(())
+}
+
+/// Forgets a document that was never requested, e.g. after a failed navigation.
+pub(crate) fn drop_document(webview: &ICoreWebView2, tab: i64, documents: &ServedDocuments) {
+  if let Some(stale) = documents.borrow_mut().remove(&tab) {
+    drop_document_filter(webview, &stale);
+  }
+}
+
+fn drop_document_filter(webview: &ICoreWebView2, document: &ServedDocument) {
+  unsafe {
+    let _ = webview.RemoveWebResourceRequestedFilter(&HSTRING::from(&document.url), COREWEBVIEW2_WEB_RESOURCE_CONTEXT_DOCUMENT);
+  }
+}
+
+/// A permission request held by its deferral until it is decided.
+pub(crate) struct PermissionRequest {
+  pub id: u64,
+  /// Tab whose page asked for the permission.
+  pub tab: i64,
+  args: ICoreWebView2PermissionRequestedEventArgs,
+  deferral: ICoreWebView2Deferral,
+  pub origin: String,
+  pub kind: PermissionKind,
+}
+
+impl PermissionRequest {
+  pub fn respond(self, decision: PermissionDecision) -> Result<(), String> {
+    let state = match decision {
+      PermissionDecision::Allow => COREWEBVIEW2_PERMISSION_STATE_ALLOW,
+      PermissionDecision::Deny => COREWEBVIEW2_PERMISSION_STATE_DENY,
+      PermissionDecision::Default | PermissionDecision::Ask => COREWEBVIEW2_PERMISSION_STATE_DEFAULT,
+    };
+    unsafe {
+      // Decisions made here are per request; keep them out of the profile.
+      if state != COREWEBVIEW2_PERMISSION_STATE_DEFAULT {
+        if let Ok(args3) = self.args.cast::<ICoreWebView2PermissionRequestedEventArgs3>() {
+          let _ = args3.SetSavesInProfile(false);
+        }
+      }
+      self.args.SetState(state).map_err(|e| format!("permission_error: {e:?}"))?;
+      self.deferral.Complete().map_err(|e| format!("permission_error: {e:?}"))
+    }
+  }
+}
+
+/// Permission requests waiting for a decision, oldest first.
+pub(crate) type PermissionQueue = Rc<RefCell<VecDeque<PermissionRequest>>>;
+
+static NEXT_PERMISSION_ID: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(1);
+
+pub(crate) fn take_permission(queue: &PermissionQueue, id: u64) -> Option<PermissionRequest> {
+  let mut queue = queue.borrow_mut();
+  let pos = queue.iter().position(|r| r.id == id)?;
+  queue.remove(pos)
+}
+
+/// Forgets the requests of a closed tab, so `permission_respond` does not answer a page that is gone.
+pub(crate) fn drop_tab_permissions(queue: &PermissionQueue, tab: i64) {
+  queue.borrow_mut().retain(|r| r.tab != tab);
+}
+
+fn permission_kind(kind: COREWEBVIEW2_PERMISSION_KIND) -> PermissionKind {
+  match kind {
+    COREWEBVIEW2_PERMISSION_KIND_CAMERA => PermissionKind::Camera,
+    COREWEBVIEW2_PERMISSION_KIND_MICROPHONE => PermissionKind::Microphone,
+    COREWEBVIEW2_PERMISSION_KIND_GEOLOCATION => PermissionKind::Geolocation,
+    COREWEBVIEW2_PERMISSION_KIND_NOTIFICATIONS => PermissionKind::Notifications,
+    COREWEBVIEW2_PERMISSION_KIND_OTHER_SENSORS => PermissionKind::Sensors,
+    COREWEBVIEW2_PERMISSION_KIND_CLIPBOARD_READ => PermissionKind::ClipboardRead,
+    COREWEBVIEW2_PERMISSION_KIND_MULTIPLE_AUTOMATIC_DOWNLOADS => PermissionKind::MultipleDownloads,
+    COREWEBVIEW2_PERMISSION_KIND_FILE_READ_WRITE => PermissionKind::FileSystem,
+    COREWEBVIEW2_PERMISSION_KIND_AUTOPLAY => PermissionKind::Autoplay,
+    COREWEBVIEW2_PERMISSION_KIND_LOCAL_FONTS => PermissionKind::LocalFonts,
+    COREWEBVIEW2_PERMISSION_KIND_MIDI_SYSTEM_EXCLUSIVE_MESSAGES => PermissionKind::MidiSysex,
+    COREWEBVIEW2_PERMISSION_KIND_WINDOW_MANAGEMENT => PermissionKind::WindowManagement,
+    _ => PermissionKind::Unknown,
+  }
+}
+
+/// Defers every permission request, queues it and reports it to `requested` as
+/// `(id, origin, kind)`; the backend decides it through [`take_permission`].
+pub(crate) fn observe_permission_requests(
+  webview: &ICoreWebView2,
+  tab: i64,
+  queue: &PermissionQueue,
+  requested: impl Fn(u64, String, PermissionKind) + 'static,
+) -> Result<(), WinError> {
+  let queue = queue.clone();
+  let handler = PermissionRequestedEventHandler::create(Box::new(move |_, args| {
+    let Some(args) = args else { return Ok(()) };
+    unsafe {
+      let mut uri = windows::core::PWSTR::null();
+      args.Uri(&mut uri)?;
+      let uri = take_pwstr(uri);
+      let origin = origin_of(&uri).unwrap_or(uri);
+      let mut kind = COREWEBVIEW2_PERMISSION_KIND::default();
+      args.PermissionKind(&mut kind)?;
+      let kind = permission_kind(kind);
+      let deferral = args.GetDeferral()?;
+      let id = NEXT_PERMISSION_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+      queue.borrow_mut().push_back(PermissionRequest {
+        id,
+        tab,
+        args,
+        deferral,
+        origin: origin.clone(),
+        kind,
+      });
+      requested(id, origin, kind);
+    }
+    Ok(())
+  }));
+  let mut token = 0i64;
+  unsafe { webview.add_PermissionRequested(&handler, &mut token) }
+}
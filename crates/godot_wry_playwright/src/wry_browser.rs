@@ -3,8 +3,20 @@ use std::sync::mpsc;
 use godot::classes::{INode, Node};
 use godot::prelude::*;
 
-#[cfg(windows)]
-use godot_wry_playwright_core::protocol::Command;
+use godot_wry_playwright_core::protocol::{Command, OptionMatch, Polling, SelectorState, SnapshotFormat, Target};
+
+use crate::content;
+use crate::cookies::{CookieFilter, CookieParam};
+use crate::dialog::DialogPolicy;
+use crate::download::DownloadEvent;
+use crate::navigation::{HistoryAction, NavigationPolicy};
+use crate::permission::PermissionPolicy;
+use crate::network::{NetworkLog, NetworkRequest, DEFAULT_MAX_BODY_BYTES};
+use crate::pdf::PdfOptions;
+use crate::profile::{ProfileConfig, ProfileSettings};
+use crate::screenshot::ScreenshotOptions;
+use crate::storage_state::StorageState;
+use crate::tabs::NewWindowPolicy;
 
 #[derive(Debug, Clone)]
 struct BrowserResponse {
@@ -14,26 +26,58 @@ struct BrowserResponse {
   error: String,
 }
 
+#[cfg_attr(not(windows), allow(dead_code))]
+#[derive(Debug)]
+enum BackendMessage {
+  Response(BrowserResponse),
+  Network(Box<NetworkRequest>),
+  TabOpened { tab_id: i64, url: String, opener_id: i64 },
+  PopupRequested { url: String },
+  Dialog { kind: &'static str, message: String, default_text: String },
+  Permission { origin: String, kind: &'static str },
+  FileChooser { multiple: bool },
+  Download { download_id: i64, event: DownloadEvent },
+}
+
 #[cfg(windows)]
 mod backend {
   use super::*;
+  use std::cell::{Cell, RefCell};
   use std::collections::HashMap;
+  use std::rc::Rc;
   use std::thread;
   use std::time::{Duration, Instant};
 
   use tao::event::{Event, StartCause};
-  use tao::event_loop::{ControlFlow, EventLoopBuilder, EventLoopProxy};
+  use tao::event_loop::{ControlFlow, EventLoopBuilder, EventLoopProxy, EventLoopWindowTarget};
   use tao::platform::windows::EventLoopBuilderExtWindows;
   use tao::platform::windows::WindowBuilderExtWindows;
   use tao::platform::windows::WindowExtWindows;
   use tao::window::WindowBuilder;
   use windows::Win32::Foundation::HWND;
   use windows::Win32::UI::WindowsAndMessaging::{SetWindowPos, SWP_ASYNCWINDOWPOS, SWP_NOACTIVATE, SWP_NOZORDER};
-  use wry::{http::Request, PageLoadEvent, WebView, WebViewBuilder};
+  use wry::{
+    http::Request, NewWindowFeatures, NewWindowResponse, PageLoadEvent, WebContext, WebView, WebViewBuilder,
+    WebViewExtWindows,
+  };
 
+  use crate::cookies::{self, Cookie, CookieFilter};
+  use crate::dialog::{DialogAnswer, DialogKind, DialogPolicy};
+  use crate::permission::{request_matches, PermissionDecision, PermissionKind};
+  use crate::network::{apply_response_body, NetworkTracker};
+  use crate::init_script::InitScripts;
+  use crate::navigation::{url_matches, UrlWaiters};
   use crate::pending::PendingRequests;
+  use crate::pdf::{self, PdfSettings};
+  use crate::profile::ProfileSettings;
+  use crate::screenshot::{self, ScreenshotOptions};
+  use crate::storage_state::{origin_of, storage_origins, StorageState};
+  use crate::tabs::{NewWindowPolicy, TabInfo, TabSet};
+  use crate::trace::TraceRecorder;
+  use crate::upload;
+  use crate::webview2;
   use godot_wry_playwright_core::protocol::{
-    automation_shim_js, build_dispatch_script, parse_ipc_envelope, Command,
+    automation_shim_js, build_dispatch_script, parse_ipc_message, Command, IpcMessage,
   };
 
   fn set_child_hwnd_rect(hwnd: isize, x: i32, y: i32, w: i32, h: i32) {
@@ -57,23 +101,300 @@ mod backend {
     InitHidden,
     InitChild { parent_hwnd: isize, x: i32, y: i32, w: i32, h: i32 },
     SetViewRect { x: i32, y: i32, w: i32, h: i32 },
-    JsCommand { id: i64, cmd: Command, timeout_ms: u64 },
-    Goto { id: i64, url: String, timeout_ms: u64 },
-    Ipc(String),
-    PageLoadFinished(String),
+    JsCommand { id: i64, tab: Option<i64>, cmd: Command, timeout_ms: u64 },
+    Goto { id: i64, tab: Option<i64>, url: String, timeout_ms: u64 },
+    SetContent { id: i64, content: content::Content, timeout_ms: u64 },
+    WaitForUrl { id: i64, pattern: Option<String>, timeout_ms: u64 },
+    History { id: i64, action: HistoryAction, timeout_ms: u64 },
+    HistoryState { id: i64, forward: bool },
+    Ipc { tab: i64, body: String },
+    PageLoadFinished { tab: i64, url: String },
+    DevToolsEvent { tab: i64, method: &'static str, params: String },
+    NetworkBody { entry: Box<NetworkRequest>, result: Result<String, String> },
+    SetNetworkCapture { capture_bodies: bool, max_body_bytes: usize },
+    SetNewWindowPolicy(NewWindowPolicy),
+    SetDialogPolicy(DialogPolicy),
+    DialogOpened { dialog: u64, kind: DialogKind, message: String, default_text: String },
+    DialogRespond { id: i64, accept: bool, text: String },
+    FileChooserOpened { multiple: bool },
+    SetDownloadDir(String),
+    SetNavigationPolicy(NavigationPolicy),
+    SetPermissionPolicy(PermissionPolicy),
+    PermissionRequested { request: u64, origin: String, kind: PermissionKind },
+    PermissionRespond { id: i64, origin: String, kind: String, allow: bool },
+    NavigationBlocked { tab: i64, error: String },
+    Download { download: i64, event: DownloadEvent },
+    CancelDownload { id: i64, download: i64 },
+    SetInputFiles { id: i64, selector: String, paths: Vec<String>, timeout_ms: u64 },
+    TracingStart,
+    TracingStop { id: i64, path: String },
+    TraceFrame { id: i64, png: Vec<u8> },
+    Screenshot { id: i64, options: ScreenshotOptions, timeout_ms: u64 },
+    ScreenshotDone { id: i64, options: Box<ScreenshotOptions>, result: Result<Vec<u8>, String> },
+    Pdf { id: i64, path: Option<String>, settings: PdfSettings, timeout_ms: u64 },
+    PdfDone { id: i64, path: Option<String>, result: Result<Option<Vec<u8>>, String> },
+    CookiesGet { id: i64, urls: Vec<String>, timeout_ms: u64 },
+    CookiesSet { id: i64, cookies: Vec<Cookie> },
+    CookiesClear { id: i64, filter: CookieFilter, timeout_ms: u64 },
+    CookiesDone { id: i64, result: Result<String, String> },
+    StorageStateSave { id: i64, path: String, origins: Vec<String>, timeout_ms: u64 },
+    StorageStateSaved { id: i64, path: String, result: Result<Box<StorageState>, String> },
+    StorageStateLoad { id: i64, state: Box<StorageState> },
+    StorageSeedScript { tab: i64, request: Option<(i64, String)>, result: Result<String, String> },
+    AddInitScript { id: i64, js: String, origin_pattern: String, run_now: bool },
+    InitScriptRegistered { tab: i64, script: u64, request: Option<i64>, result: Result<String, String> },
+    RemoveInitScript { id: i64, script: u64 },
+    TabNew { id: i64, url: String },
+    TabClose { id: i64, tab: i64 },
+    TabSelect { id: i64, tab: i64 },
+    TabList { id: i64 },
+    PopupRequested { opener: i64, url: String },
     Tick,
     Stop,
   }
 
+  impl UserEvent {
+    /// Requests the backend handles natively, as recorded in a trace; shim commands and
+    /// navigations are recorded by their handlers.
+    fn traced_action(&self) -> Option<(i64, &'static str, serde_json::Value)> {
+      use serde_json::json;
+      let (id, action, params) = match self {
+        Self::WaitForUrl { id, pattern, .. } => (*id, "wait_for_url", json!({ "pattern": pattern })),
+        Self::History { id, action, .. } => (*id, action.as_str(), json!({})),
+        Self::DialogRespond { id, accept, .. } => (*id, "dialog_respond", json!({ "accept": accept })),
+        Self::PermissionRespond { id, origin, kind, allow } => {
+          (*id, "permission_respond", json!({ "origin": origin, "kind": kind, "allow": allow }))
+        }
+        Self::CancelDownload { id, download } => (*id, "cancel_download", json!({ "download": download })),
+        Self::SetInputFiles { id, selector, paths, .. } => {
+          (*id, "set_input_files", json!({ "selector": selector, "paths": paths }))
+        }
+        Self::Screenshot { id, options, .. } => {
+          (*id, "screenshot", json!({ "full_page": options.full_page, "path": options.path }))
+        }
+        Self::Pdf { id, path, .. } => (*id, "pdf", json!({ "path": path })),
+        Self::CookiesGet { id, urls, .. } => (*id, "cookies_get", json!({ "urls": urls })),
+        // Names only: cookie values are credentials.
+        Self::CookiesSet { id, cookies } => {
+          let names: Vec<&str> = cookies.iter().map(|c| c.name.as_str()).collect();
+          (*id, "cookies_set", json!({ "names": names }))
+        }
+        Self::CookiesClear { id, .. } => (*id, "cookies_clear", json!({})),
+        Self::StorageStateSave { id, path, origins, .. } => {
+          (*id, "storage_state_save", json!({ "path": path, "origins": origins }))
+        }
+        Self::StorageStateLoad { id, .. } => (*id, "storage_state_load", json!({})),
+        Self::AddInitScript { id, origin_pattern, run_now, .. } => {
+          (*id, "add_init_script", json!({ "origin_pattern": origin_pattern, "run_now": run_now }))
+        }
+        Self::RemoveInitScript { id, script } => (*id, "remove_init_script", json!({ "script": script })),
+        Self::TabNew { id, url } => (*id, "tab_new", json!({ "url": url })),
+        Self::TabClose { id, tab } => (*id, "tab_close", json!({ "tab": tab })),
+        Self::TabSelect { id, tab } => (*id, "tab_select", json!({ "tab": tab })),
+        Self::TabList { id } => (*id, "tab_list", json!({})),
+        _ => return None,
+      };
+      Some((id, action, params))
+    }
+  }
+
+  /// Sends backend messages and records each request's completion in the running trace.
+  #[derive(Clone)]
+  struct Responder {
+    tx: mpsc::Sender<BackendMessage>,
+    trace: Rc<RefCell<Option<TraceRecorder>>>,
+    start: Instant,
+  }
+
+  impl Responder {
+    fn send(&self, message: BackendMessage) -> Result<(), mpsc::SendError<BackendMessage>> {
+      if let (BackendMessage::Response(r), Some(t)) = (&message, self.trace.borrow_mut().as_mut()) {
+        t.response(self.start.elapsed().as_millis() as u64, r.request_id, r.ok, &r.result_json, &r.error);
+      }
+      self.tx.send(message)
+    }
+  }
+
   #[derive(Debug)]
   pub(super) struct Handle {
     pub proxy: EventLoopProxy<UserEvent>,
-    pub rx: mpsc::Receiver<BrowserResponse>,
+    pub rx: mpsc::Receiver<BackendMessage>,
     pub join: thread::JoinHandle<()>,
   }
 
-  pub(super) fn spawn() -> Result<Handle, String> {
-    let (resp_tx, resp_rx) = mpsc::channel::<BrowserResponse>();
+  fn install_observers(wv: &WebView, proxy: &EventLoopProxy<UserEvent>, hooks: &webview2::PageHooks, tab: i64) {
+    let proxy_net = proxy.clone();
+    let _ = webview2::observe_network(&wv.webview(), move |method, params| {
+      let _ = proxy_net.send_event(UserEvent::DevToolsEvent { tab, method, params });
+    });
+    let proxy_dialog = proxy.clone();
+    let _ = webview2::observe_script_dialogs(&wv.webview(), tab, &hooks.dialogs, move |dialog, kind, message, default_text| {
+      let _ = proxy_dialog.send_event(UserEvent::DialogOpened { dialog, kind, message, default_text });
+    });
+    let proxy_files = proxy.clone();
+    let _ = webview2::intercept_file_chooser(&wv.webview(), move |multiple| {
+      let _ = proxy_files.send_event(UserEvent::FileChooserOpened { multiple });
+    });
+    let proxy_download = proxy.clone();
+    let _ = webview2::observe_downloads(&wv.webview(), &hooks.downloads, move |download, event| {
+      let _ = proxy_download.send_event(UserEvent::Download { download, event });
+    });
+    let proxy_permission = proxy.clone();
+    let _ = webview2::observe_permission_requests(&wv.webview(), tab, &hooks.permissions, move |request, origin, kind| {
+      let _ = proxy_permission.send_event(UserEvent::PermissionRequested { request, origin, kind });
+    });
+    let proxy_nav = proxy.clone();
+    let _ = webview2::serve_documents(&wv.webview(), tab, &hooks.documents);
+    let _ = webview2::enforce_navigation_policy(&wv.webview(), &hooks.navigation, move |_, error| {
+      let _ = proxy_nav.send_event(UserEvent::NavigationBlocked { tab, error });
+    });
+  }
+
+  /// Child-window placement inside the Godot window (`start_view`); tabs are hidden windows otherwise.
+  #[derive(Debug, Clone, Copy)]
+  struct ViewRect {
+    parent_hwnd: isize,
+    x: i32,
+    y: i32,
+    w: i32,
+    h: i32,
+  }
+
+  struct WryTab {
+    // Declared first so the webview is dropped before its window.
+    webview: WebView,
+    window: tao::window::Window,
+  }
+
+  fn build_tab(
+    target: &EventLoopWindowTarget<UserEvent>,
+    web_context: &mut WebContext,
+    incognito: bool,
+    view: Option<ViewRect>,
+    proxy: &EventLoopProxy<UserEvent>,
+    hooks: &webview2::PageHooks,
+    tab: i64,
+  ) -> Result<WryTab, String> {
+    let window = match view {
+      None => WindowBuilder::new()
+        .with_title("godot-wry-playwright (hidden)")
+        .with_visible(false)
+        .build(target),
+      Some(view) => WindowBuilder::new()
+        .with_title("godot-wry-playwright (view)")
+        .with_visible(true)
+        .with_decorations(false)
+        .with_parent_window(view.parent_hwnd)
+        .build(target),
+    }
+    .map_err(|e| format!("create_window_error: {e}"))?;
+
+    if let Some(view) = view {
+      // NOTE: Tao's `set_inner_size` uses AdjustWindowRect, which can behave unexpectedly
+      // for `WS_CHILD` windows. Use Win32 directly for consistent sizing/positioning.
+      set_child_hwnd_rect(window.hwnd(), view.x, view.y, view.w, view.h);
+    }
+
+    let proxy_ipc = proxy.clone();
+    let ipc_handler = move |req: Request<String>| {
+      let body = req.body().to_string();
+      let _ = proxy_ipc.send_event(UserEvent::Ipc { tab, body });
+    };
+
+    let proxy_load = proxy.clone();
+    let page_load_handler = move |event: PageLoadEvent, url: String| {
+      if matches!(event, PageLoadEvent::Finished) {
+        let _ = proxy_load.send_event(UserEvent::PageLoadFinished { tab, url });
+      }
+    };
+
+    // Popups and `window.open` never get native windows; `PopupRequested` applies the new-window policy.
+    let proxy_popup = proxy.clone();
+    let new_window_handler = move |url: String, _features: NewWindowFeatures| {
+      let _ = proxy_popup.send_event(UserEvent::PopupRequested { opener: tab, url });
+      NewWindowResponse::Deny
+    };
+
+    let webview = WebViewBuilder::new_with_web_context(web_context)
+      .with_incognito(incognito)
+      .with_initialization_script(automation_shim_js())
+      .with_ipc_handler(ipc_handler)
+      .with_on_page_load_handler(page_load_handler)
+      .with_new_window_req_handler(new_window_handler)
+      .build(&window)
+      .map_err(|e| format!("build_webview_error: {e}"))?;
+    install_observers(&webview, proxy, hooks, tab);
+
+    Ok(WryTab { webview, window })
+  }
+
+  /// Hidden webview of the same profile for reading other origins' storage; it has no observers, so
+  /// its loads never reach pending navigations.
+  fn build_scratch(
+    target: &EventLoopWindowTarget<UserEvent>,
+    web_context: &mut WebContext,
+    incognito: bool,
+  ) -> Result<WryTab, String> {
+    let window = WindowBuilder::new()
+      .with_title("godot-wry-playwright (storage)")
+      .with_visible(false)
+      .build(target)
+      .map_err(|e| format!("create_window_error: {e}"))?;
+    let webview = WebViewBuilder::new_with_web_context(web_context)
+      .with_incognito(incognito)
+      .build(&window)
+      .map_err(|e| format!("build_webview_error: {e}"))?;
+    Ok(WryTab { webview, window })
+  }
+
+  /// In view mode only the active tab's window is shown.
+  fn show_active_tab(tabs: &TabSet<WryTab>, view: Option<ViewRect>) {
+    if view.is_none() {
+      return;
+    }
+    for (tab_id, tab) in tabs.iter() {
+      tab.window.set_visible(tabs.active_id() == Some(tab_id));
+    }
+  }
+
+  fn add_seed_script(
+    wv: &WebView,
+    js: &str,
+    proxy: &EventLoopProxy<UserEvent>,
+    tab: i64,
+    request: Option<(i64, String)>,
+  ) {
+    let proxy_seed = proxy.clone();
+    webview2::add_document_script(&wv.webview(), js, move |result| {
+      let _ = proxy_seed.send_event(UserEvent::StorageSeedScript { tab, request, result });
+    });
+  }
+
+  fn add_init_script(
+    wv: &WebView,
+    js: &str,
+    proxy: &EventLoopProxy<UserEvent>,
+    tab: i64,
+    script: u64,
+    request: Option<i64>,
+  ) {
+    let proxy_init = proxy.clone();
+    webview2::add_document_script(&wv.webview(), js, move |result| {
+      let _ = proxy_init.send_event(UserEvent::InitScriptRegistered { tab, script, request, result });
+    });
+  }
+
+  fn capture_trace_frame(wv: &WebView, proxy: &EventLoopProxy<UserEvent>, id: i64) {
+    let proxy = proxy.clone();
+    let _ = webview2::capture_preview_png(&wv.webview(), move |result| {
+      if let Ok(png) = result {
+        let _ = proxy.send_event(UserEvent::TraceFrame { id, png });
+      }
+    });
+  }
+
+  pub(super) fn spawn(profile: ProfileSettings) -> Result<Handle, String> {
+    let (resp_tx, resp_rx) = mpsc::channel::<BackendMessage>();
     let (proxy_tx, proxy_rx) = mpsc::channel::<EventLoopProxy<UserEvent>>();
 
     let join = thread::spawn(move || {
@@ -86,17 +407,48 @@ mod backend {
       let start = Instant::now();
       let mut pending = PendingRequests::new();
       let mut pending_kind: HashMap<i64, &'static str> = Default::default();
-      let mut goto_pending: Option<i64> = None;
-      let mut window: Option<tao::window::Window> = None;
-      let mut webview: Option<WebView> = None;
-
-      fn send_error(resp_tx: &mpsc::Sender<BrowserResponse>, request_id: i64, error: impl ToString) {
-        let _ = resp_tx.send(BrowserResponse {
+      // Pending `goto` request per tab.
+      let mut goto_pending: HashMap<i64, i64> = HashMap::new();
+      let mut url_waiters = UrlWaiters::new();
+      let mut network = NetworkTracker::new();
+      let mut capture_bodies = false;
+      let mut max_body_bytes = DEFAULT_MAX_BODY_BYTES;
+      let mut new_window_policy = NewWindowPolicy::default();
+      let mut dialog_policy = DialogPolicy::default();
+      let mut permission_policy = PermissionPolicy::default();
+      let trace: Rc<RefCell<Option<TraceRecorder>>> = Rc::default();
+      let resp_tx = Responder { tx: resp_tx, trace: trace.clone(), start };
+      let mut seed_state: Option<StorageState> = None;
+      let mut seed_script_ids: HashMap<i64, String> = HashMap::new();
+      let mut init_scripts = InitScripts::new();
+      // Scratch webview and cancel flag of each running `storage_state_save`.
+      let mut storage_collects: HashMap<i64, (Option<WryTab>, Rc<Cell<bool>>)> = HashMap::new();
+      let hooks = webview2::PageHooks::default();
+      let mut tabs: TabSet<WryTab> = TabSet::new();
+      let mut view: Option<ViewRect> = None;
+      let mut web_context = WebContext::new(profile.data_dir.map(std::path::PathBuf::from));
+
+      fn send_error(resp_tx: &Responder, request_id: i64, error: impl ToString) {
+        let _ = resp_tx.send(BackendMessage::Response(BrowserResponse {
           request_id,
           ok: false,
           result_json: "null".to_string(),
           error: error.to_string(),
-        });
+        }));
+      }
+
+      fn send_result(resp_tx: &Responder, request_id: i64, result: Result<String, String>) {
+        match result {
+          Ok(result_json) => {
+            let _ = resp_tx.send(BackendMessage::Response(BrowserResponse {
+              request_id,
+              ok: true,
+              result_json,
+              error: String::new(),
+            }));
+          }
+          Err(e) => send_error(resp_tx, request_id, e),
+        }
       }
 
       // A small ticker to drive timeouts even when the window is hidden.
@@ -110,6 +462,11 @@ mod backend {
 
       event_loop.run(move |event, _target, control_flow| {
         *control_flow = ControlFlow::Wait;
+        if let (Event::UserEvent(user_event), Some(t)) = (&event, trace.borrow_mut().as_mut()) {
+          if let Some((id, action, params)) = user_event.traced_action() {
+            t.action(start.elapsed().as_millis() as u64, id, action, params);
+          }
+        }
 
         match event {
           Event::NewEvents(StartCause::Init) => {}
@@ -117,41 +474,18 @@ mod backend {
             *control_flow = ControlFlow::Exit;
           }
           Event::UserEvent(UserEvent::InitHidden) => {
-            if webview.is_some() {
+            if !tabs.is_empty() {
               return;
             }
-
-            let w = WindowBuilder::new()
-              .with_title("godot-wry-playwright (hidden)")
-              .with_visible(false)
-              .build(&_target)
-              .expect("create hidden window");
-
-            let proxy_ipc = proxy.clone();
-            let ipc_handler = move |req: Request<String>| {
-              let body = req.body().to_string();
-              let _ = proxy_ipc.send_event(UserEvent::Ipc(body));
-            };
-
-            let proxy_load = proxy.clone();
-            let page_load_handler = move |event: PageLoadEvent, url: String| {
-              if matches!(event, PageLoadEvent::Finished) {
-                let _ = proxy_load.send_event(UserEvent::PageLoadFinished(url));
-              }
-            };
-
-            let wv = WebViewBuilder::new()
-              .with_initialization_script(automation_shim_js())
-              .with_ipc_handler(ipc_handler)
-              .with_on_page_load_handler(page_load_handler)
-              .build(&w)
-              .expect("build webview");
-
-            window = Some(w);
-            webview = Some(wv);
+            let opened = tabs.try_open(|tab| {
+              build_tab(_target, &mut web_context, profile.incognito, None, &proxy, &hooks, tab)
+            });
+            if let Err(e) = opened {
+              send_error(&resp_tx, -1, e);
+            }
           }
           Event::UserEvent(UserEvent::InitChild { parent_hwnd, x, y, w, h }) => {
-            if webview.is_some() {
+            if !tabs.is_empty() {
               return;
             }
 
@@ -160,77 +494,344 @@ mod backend {
               return;
             }
 
-            let child = WindowBuilder::new()
-              .with_title("godot-wry-playwright (view)")
-              .with_visible(true)
-              .with_decorations(false)
-              .with_parent_window(parent_hwnd)
-              .build(&_target)
-              .expect("create child window");
-
-            // NOTE: Tao's `set_inner_size` uses AdjustWindowRect, which can behave unexpectedly
-            // for `WS_CHILD` windows. Use Win32 directly for consistent sizing/positioning.
-            set_child_hwnd_rect(child.hwnd(), x, y, w, h);
-
-            let proxy_ipc = proxy.clone();
-            let ipc_handler = move |req: Request<String>| {
-              let body = req.body().to_string();
-              let _ = proxy_ipc.send_event(UserEvent::Ipc(body));
+            view = Some(ViewRect { parent_hwnd, x, y, w, h });
+            let opened = tabs.try_open(|tab| {
+              build_tab(_target, &mut web_context, profile.incognito, view, &proxy, &hooks, tab)
+            });
+            if let Err(e) = opened {
+              send_error(&resp_tx, -1, e);
+            }
+          }
+          Event::UserEvent(UserEvent::SetViewRect { x, y, w, h }) => {
+            if let Some(rect) = view.as_mut() {
+              (rect.x, rect.y, rect.w, rect.h) = (x, y, w, h);
+            }
+            for (_, tab) in tabs.iter() {
+              set_child_hwnd_rect(tab.window.hwnd(), x, y, w, h);
+            }
+          }
+          Event::UserEvent(UserEvent::TabNew { id, url }) => {
+            let opened = tabs.try_open(|tab| {
+              build_tab(_target, &mut web_context, profile.incognito, view, &proxy, &hooks, tab)
+            });
+            let tab = match opened {
+              Ok(tab) => tab,
+              Err(e) => return send_error(&resp_tx, id, e),
             };
-
-            let proxy_load = proxy.clone();
-            let page_load_handler = move |event: PageLoadEvent, url: String| {
-              if matches!(event, PageLoadEvent::Finished) {
-                let _ = proxy_load.send_event(UserEvent::PageLoadFinished(url));
+            show_active_tab(&tabs, view);
+            let Some(wv) = tabs.get(tab).map(|t| &t.webview) else {
+              return;
+            };
+            if let Some(js) = seed_state.as_ref().and_then(StorageState::seed_script) {
+              add_seed_script(wv, &js, &proxy, tab, None);
+            }
+            for (script, js) in init_scripts.iter() {
+              add_init_script(wv, js, &proxy, tab, script, None);
+            }
+            if !url.is_empty() {
+              if let Err(e) = wv.load_url(&url) {
+                return send_error(&resp_tx, id, e);
+              }
+            }
+            let _ = resp_tx.send(BackendMessage::TabOpened { tab_id: tab, url: url.clone(), opener_id: -1 });
+            send_result(&resp_tx, id, Ok(serde_json::json!({ "id": tab, "url": url }).to_string()));
+          }
+          Event::UserEvent(UserEvent::SetNewWindowPolicy(policy)) => {
+            new_window_policy = policy;
+          }
+          Event::UserEvent(UserEvent::SetDialogPolicy(policy)) => {
+            dialog_policy = policy;
+          }
+          Event::UserEvent(UserEvent::DialogOpened { dialog, kind, message, default_text }) => {
+            let answer = dialog_policy.answer(kind, &default_text);
+            let _ = resp_tx.send(BackendMessage::Dialog { kind: kind.as_str(), message, default_text });
+            if let Some(answer) = answer {
+              if let Some(open) = webview2::take_dialog(&hooks.dialogs, dialog) {
+                let _ = open.respond(&answer);
+              }
+            }
+          }
+          Event::UserEvent(UserEvent::SetDownloadDir(dir)) => {
+            hooks.downloads.set_dir(dir.into());
+          }
+          Event::UserEvent(UserEvent::SetNavigationPolicy(policy)) => {
+            let restricted = !policy.is_unrestricted();
+            let was_restricted = !hooks.navigation.replace(policy).is_unrestricted();
+            if restricted != was_restricted {
+              for (_, tab) in tabs.iter() {
+                let _ = webview2::filter_all_resources(&tab.webview.webview(), restricted);
+              }
+            }
+          }
+          Event::UserEvent(UserEvent::SetPermissionPolicy(policy)) => {
+            permission_policy = policy;
+          }
+          Event::UserEvent(UserEvent::PermissionRequested { request, origin, kind }) => {
+            let _ = resp_tx.send(BackendMessage::Permission { origin, kind: kind.as_str() });
+            let decision = permission_policy.decide(kind);
+            if decision != PermissionDecision::Ask {
+              if let Some(open) = webview2::take_permission(&hooks.permissions, request) {
+                let _ = open.respond(decision);
               }
+            }
+          }
+          Event::UserEvent(UserEvent::PermissionRespond { id, origin, kind, allow }) => {
+            let position = hooks
+              .permissions
+              .borrow()
+              .iter()
+              .position(|r| request_matches(&r.origin, r.kind, &origin, &kind));
+            let Some(open) = position.and_then(|pos| hooks.permissions.borrow_mut().remove(pos)) else {
+              return send_error(&resp_tx, id, "permission_not_pending");
             };
-
-            let wv = WebViewBuilder::new()
-              .with_initialization_script(automation_shim_js())
-              .with_ipc_handler(ipc_handler)
-              .with_on_page_load_handler(page_load_handler)
-              .build(&child)
-              .expect("build webview");
-
-            window = Some(child);
-            webview = Some(wv);
+            let answered = serde_json::json!({ "origin": open.origin, "kind": open.kind.as_str(), "allowed": allow });
+            let decision = if allow { PermissionDecision::Allow } else { PermissionDecision::Deny };
+            send_result(&resp_tx, id, open.respond(decision).map(|()| answered.to_string()));
           }
-          Event::UserEvent(UserEvent::SetViewRect { x, y, w, h }) => {
-            if let Some(wnd) = &window {
-              set_child_hwnd_rect(wnd.hwnd(), x, y, w, h);
+          Event::UserEvent(UserEvent::NavigationBlocked { tab, error }) => {
+            if let Some(id) = goto_pending.remove(&tab) {
+              if pending.complete(id) {
+                pending_kind.remove(&id);
+                send_error(&resp_tx, id, error);
+              }
             }
           }
-          Event::UserEvent(UserEvent::Goto { id, url, timeout_ms }) => {
-            let Some(wv) = &webview else {
-              pending.complete(id);
-              pending_kind.remove(&id);
-              send_error(&resp_tx, id, "webview_not_started");
+          Event::UserEvent(UserEvent::Download { download, event }) => {
+            let _ = resp_tx.send(BackendMessage::Download { download_id: download, event });
+          }
+          Event::UserEvent(UserEvent::CancelDownload { id, download }) => {
+            let result = hooks.downloads.cancel(download);
+            send_result(&resp_tx, id, result.map(|()| serde_json::json!({ "canceled": download }).to_string()));
+          }
+          Event::UserEvent(UserEvent::FileChooserOpened { multiple }) => {
+            let _ = resp_tx.send(BackendMessage::FileChooser { multiple });
+          }
+          Event::UserEvent(UserEvent::SetInputFiles { id, selector, paths, timeout_ms }) => {
+            // Files are read here so large uploads do not stall the game thread.
+            match upload::read_input_files(&paths) {
+              Ok(files) => {
+                let cmd = Command::SetInputFiles { selector, files };
+                let _ = proxy.send_event(UserEvent::JsCommand { id, tab: None, cmd, timeout_ms });
+              }
+              Err(e) => send_error(&resp_tx, id, e),
+            }
+          }
+          Event::UserEvent(UserEvent::DialogRespond { id, accept, text }) => {
+            let oldest = hooks.dialogs.borrow_mut().pop_front();
+            let Some(open) = oldest else {
+              return send_error(&resp_tx, id, "dialog_not_open");
+            };
+            let kind = open.kind;
+            let answer = DialogAnswer::new(accept, &text, kind, &open.default_text);
+            let result = open
+              .respond(&answer)
+              .map(|()| serde_json::json!({ "type": kind.as_str(), "accepted": accept }).to_string());
+            send_result(&resp_tx, id, result);
+          }
+          Event::UserEvent(UserEvent::PopupRequested { opener, url }) => {
+            match new_window_policy {
+              NewWindowPolicy::NewTab => {}
+              NewWindowPolicy::Deny => return,
+              NewWindowPolicy::Signal => {
+                let _ = resp_tx.send(BackendMessage::PopupRequested { url });
+                return;
+              }
+              NewWindowPolicy::SameView => {
+                if let Some(wv) = tabs.get(opener).map(|t| &t.webview) {
+                  let _ = wv.load_url(&url);
+                }
+                return;
+              }
+            }
+            let opened = tabs.try_open(|tab| {
+              build_tab(_target, &mut web_context, profile.incognito, view, &proxy, &hooks, tab)
+            });
+            let tab = match opened {
+              Ok(tab) => tab,
+              Err(e) => return send_error(&resp_tx, -1, e),
+            };
+            show_active_tab(&tabs, view);
+            let Some(wv) = tabs.get(tab).map(|t| &t.webview) else {
               return;
             };
+            if let Some(js) = seed_state.as_ref().and_then(StorageState::seed_script) {
+              add_seed_script(wv, &js, &proxy, tab, None);
+            }
+            for (script, js) in init_scripts.iter() {
+              add_init_script(wv, js, &proxy, tab, script, None);
+            }
+            let _ = wv.load_url(&url);
+            let _ = resp_tx.send(BackendMessage::TabOpened { tab_id: tab, url, opener_id: opener });
+          }
+          Event::UserEvent(UserEvent::TabClose { id, tab }) => {
+            let tab = if tab < 0 { tabs.active_id().unwrap_or(tab) } else { tab };
+            if tabs.close(tab).is_none() {
+              return send_error(&resp_tx, id, format!("tab_not_found: {tab}"));
+            }
+            seed_script_ids.remove(&tab);
+            init_scripts.remove_tab(tab);
+            webview2::drop_tab_dialogs(&hooks.dialogs, tab);
+            webview2::drop_tab_permissions(&hooks.permissions, tab);
+            hooks.documents.borrow_mut().remove(&tab);
+            for wait_id in url_waiters.remove_tab(tab) {
+              if pending.complete(wait_id) {
+                pending_kind.remove(&wait_id);
+                send_error(&resp_tx, wait_id, "tab_closed");
+              }
+            }
+            if let Some(goto_id) = goto_pending.remove(&tab) {
+              if pending.complete(goto_id) {
+                pending_kind.remove(&goto_id);
+                send_error(&resp_tx, goto_id, "tab_closed");
+              }
+            }
+            show_active_tab(&tabs, view);
+            send_result(
+              &resp_tx,
+              id,
+              Ok(serde_json::json!({ "closed": tab, "active": tabs.active_id() }).to_string()),
+            );
+          }
+          Event::UserEvent(UserEvent::TabSelect { id, tab }) => {
+            if !tabs.select(tab) {
+              return send_error(&resp_tx, id, format!("tab_not_found: {tab}"));
+            }
+            show_active_tab(&tabs, view);
+            send_result(&resp_tx, id, Ok(serde_json::json!({ "active": tab }).to_string()));
+          }
+          Event::UserEvent(UserEvent::TabList { id }) => {
+            let list: Vec<TabInfo> = tabs
+              .iter()
+              .map(|(tab_id, tab)| {
+                let (url, title) = webview2::page_info(&tab.webview.webview());
+                TabInfo {
+                  id: tab_id,
+                  url,
+                  title,
+                  active: tabs.active_id() == Some(tab_id),
+                }
+              })
+              .collect();
+            send_result(&resp_tx, id, serde_json::to_string(&list).map_err(|e| e.to_string()));
+          }
+          Event::UserEvent(UserEvent::Goto { id, tab, url, timeout_ms }) => {
+            let (tab, wv) = match tabs.target(tab) {
+              Ok((tab, t)) => (tab, &t.webview),
+              Err(e) => return send_error(&resp_tx, id, e),
+            };
+            if let Err(e) = hooks.navigation.borrow().check(&url) {
+              return send_error(&resp_tx, id, e);
+            }
 
             let now_ms = start.elapsed().as_millis() as u64;
             pending.insert(id, now_ms, timeout_ms);
             pending_kind.insert(id, "goto");
-            goto_pending = Some(id);
+            goto_pending.insert(tab, id);
+            if let Some(t) = trace.borrow_mut().as_mut() {
+              t.navigation_started(now_ms, id, &url);
+            }
 
             if let Err(e) = wv.load_url(&url) {
               pending.complete(id);
               pending_kind.remove(&id);
-              goto_pending = None;
+              goto_pending.remove(&tab);
               send_error(&resp_tx, id, e);
             }
           }
-          Event::UserEvent(UserEvent::JsCommand { id, cmd, timeout_ms }) => {
-            let Some(wv) = &webview else {
+          Event::UserEvent(UserEvent::SetContent { id, content, timeout_ms }) => {
+            let Some((tab, wv)) = tabs.active_entry().map(|(tab, t)| (tab, &t.webview)) else {
+              send_error(&resp_tx, id, "webview_not_started");
+              return;
+            };
+            if let Some(url) = content.url.as_deref() {
+              if let Err(e) = hooks.navigation.borrow().check(url) {
+                return send_error(&resp_tx, id, e);
+              }
+            }
+
+            // Completes like `goto`, when the document finishes loading.
+            let now_ms = start.elapsed().as_millis() as u64;
+            pending.insert(id, now_ms, timeout_ms);
+            pending_kind.insert(id, "set_content");
+            goto_pending.insert(tab, id);
+            if let Some(t) = trace.borrow_mut().as_mut() {
+              t.navigation_started(now_ms, id, content.url.as_deref().unwrap_or("about:blank"));
+            }
+
+            let loaded = match content.url {
+              Some(url) => {
+                let document = webview2::ServedDocument { url: url.clone(), html: content.html };
+                webview2::queue_document(&wv.webview(), tab, &hooks.documents, document)
+                  .map_err(|e| format!("set_content_error: {e:?}"))
+                  .and_then(|()| wv.load_url(&url).map_err(|e| e.to_string()))
+              }
+              None => wv.load_html(&content.html).map_err(|e| e.to_string()),
+            };
+            if let Err(e) = loaded {
+              webview2::drop_document(&wv.webview(), tab, &hooks.documents);
               pending.complete(id);
               pending_kind.remove(&id);
+              goto_pending.remove(&tab);
+              send_error(&resp_tx, id, e);
+            }
+          }
+          Event::UserEvent(UserEvent::WaitForUrl { id, pattern, timeout_ms }) => {
+            let Some((tab, wv)) = tabs.active_entry().map(|(tab, t)| (tab, &t.webview)) else {
+              send_error(&resp_tx, id, "webview_not_started");
+              return;
+            };
+            // `wait_for_url` completes at once when the page is already there.
+            if let Some(pattern) = pattern.as_deref() {
+              let (url, _) = webview2::page_info(&wv.webview());
+              if url_matches(pattern, &url) {
+                return send_result(&resp_tx, id, serde_json::to_string(&url).map_err(|e| e.to_string()));
+              }
+            }
+
+            let now_ms = start.elapsed().as_millis() as u64;
+            pending.insert(id, now_ms, timeout_ms);
+            pending_kind.insert(id, if pattern.is_some() { "wait_for_url" } else { "wait_for_navigation" });
+            url_waiters.add(id, tab, pattern);
+          }
+          Event::UserEvent(UserEvent::History { id, action, timeout_ms }) => {
+            let Some((tab, wv)) = tabs.active_entry().map(|(tab, t)| (tab, &t.webview)) else {
+              send_error(&resp_tx, id, "webview_not_started");
+              return;
+            };
+            match webview2::apply_history(&wv.webview(), action) {
+              Err(e) => send_error(&resp_tx, id, e),
+              // No history entry to move to.
+              Ok(false) => send_result(&resp_tx, id, Ok("null".to_string())),
+              Ok(true) if action == HistoryAction::Stop => send_result(&resp_tx, id, Ok("true".to_string())),
+              // Completes with the URL on the next load or same-document change, like `wait_for_navigation`.
+              Ok(true) => {
+                let now_ms = start.elapsed().as_millis() as u64;
+                pending.insert(id, now_ms, timeout_ms);
+                pending_kind.insert(id, action.as_str());
+                url_waiters.add(id, tab, None);
+              }
+            }
+          }
+          Event::UserEvent(UserEvent::HistoryState { id, forward }) => {
+            let Some(wv) = tabs.active().map(|t| &t.webview) else {
               send_error(&resp_tx, id, "webview_not_started");
               return;
             };
+            let (can_go_back, can_go_forward) = webview2::history_state(&wv.webview());
+            send_result(&resp_tx, id, Ok((if forward { can_go_forward } else { can_go_back }).to_string()));
+          }
+          Event::UserEvent(UserEvent::JsCommand { id, tab, cmd, timeout_ms }) => {
+            let wv = match tabs.target(tab) {
+              Ok((_, t)) => &t.webview,
+              Err(e) => return send_error(&resp_tx, id, e),
+            };
 
             let now_ms = start.elapsed().as_millis() as u64;
             pending.insert(id, now_ms, timeout_ms);
             pending_kind.insert(id, "js");
+            if let Some(t) = trace.borrow_mut().as_mut() {
+              t.command(now_ms, id, &cmd);
+            }
 
             let script = build_dispatch_script(&id.to_string(), cmd);
             if let Err(e) = wv.evaluate_script(&script) {
@@ -239,89 +840,489 @@ mod backend {
               send_error(&resp_tx, id, e);
             }
           }
-          Event::UserEvent(UserEvent::Ipc(body)) => match parse_ipc_envelope(&body) {
-            Ok(env) => {
+          Event::UserEvent(UserEvent::Ipc { tab, body }) => match parse_ipc_message(&body) {
+            Ok(IpcMessage::Event(ev)) => match ev.event.as_str() {
+              "console" => {
+                if let Some(t) = trace.borrow_mut().as_mut() {
+                  t.console(start.elapsed().as_millis() as u64, &ev.data);
+                }
+              }
+              "url_changed" => {
+                let url = ev.data.get("url").and_then(|u| u.as_str()).unwrap_or_default();
+                for id in url_waiters.resolve(tab, url) {
+                  if pending.complete(id) {
+                    pending_kind.remove(&id);
+                    send_result(&resp_tx, id, serde_json::to_string(url).map_err(|e| e.to_string()));
+                  }
+                }
+              }
+              "storage_seeded" => {
+                let origin = ev.data.get("origin").and_then(|o| o.as_str()).unwrap_or_default();
+                let Some(state) = seed_state.as_mut() else {
+                  return;
+                };
+                // Seed each origin once: re-register the script without the origin just seeded.
+                state.origins.retain(|o| o.origin != origin);
+                for (tab_id, script_id) in seed_script_ids.drain() {
+                  if let Some(tab) = tabs.get(tab_id) {
+                    webview2::remove_document_script(&tab.webview.webview(), &script_id);
+                  }
+                }
+                match state.seed_script() {
+                  Some(js) => {
+                    for (tab_id, tab) in tabs.iter() {
+                      add_seed_script(&tab.webview, &js, &proxy, tab_id, None);
+                    }
+                  }
+                  None => seed_state = None,
+                }
+              }
+              _ => {}
+            },
+            Ok(IpcMessage::Response(env)) => {
               let id: i64 = env.id.parse().unwrap_or(-1);
               let _had_pending = pending.complete(id);
               pending_kind.remove(&id);
+              if let Some(traced) = tabs.get(tab).filter(|_| trace.borrow().is_some()) {
+                capture_trace_frame(&traced.webview, &proxy, id);
+              }
 
               let result_json = env.result.map(|v| v.to_string()).unwrap_or_else(|| "null".to_string());
               let error = env.error.unwrap_or_default();
-              let _ = resp_tx.send(BrowserResponse {
+              let _ = resp_tx.send(BackendMessage::Response(BrowserResponse {
                 request_id: id,
                 ok: env.ok,
                 result_json,
                 error,
-              });
+              }));
             }
             Err(e) => {
-              let _ = resp_tx.send(BrowserResponse {
+              let _ = resp_tx.send(BackendMessage::Response(BrowserResponse {
                 request_id: -1,
                 ok: false,
                 result_json: "null".to_string(),
                 error: format!("ipc_parse_error: {e}"),
-              });
+              }));
             }
           },
-          Event::UserEvent(UserEvent::PageLoadFinished(url)) => {
-            if let Some(id) = goto_pending.take() {
+          Event::UserEvent(UserEvent::PageLoadFinished { tab, url }) => {
+            for id in url_waiters.resolve(tab, &url) {
+              if pending.complete(id) {
+                pending_kind.remove(&id);
+                send_result(&resp_tx, id, serde_json::to_string(&url).map_err(|e| e.to_string()));
+              }
+            }
+            if let Some(id) = goto_pending.remove(&tab) {
               pending.complete(id);
               pending_kind.remove(&id);
+              if let Some(loaded) = tabs.get(tab).filter(|_| trace.borrow().is_some()) {
+                capture_trace_frame(&loaded.webview, &proxy, id);
+              }
               let result_json = serde_json::to_string(&url).unwrap_or_else(|_| "\"\"".to_string());
-              let _ = resp_tx.send(BrowserResponse {
+              let _ = resp_tx.send(BackendMessage::Response(BrowserResponse {
                 request_id: id,
                 ok: true,
                 result_json,
                 error: String::new(),
-              });
+              }));
             }
           }
-          Event::UserEvent(UserEvent::Tick) => {
+          Event::UserEvent(UserEvent::DevToolsEvent { tab, method, params }) => {
+            let Some(entry) = network.on_cdp_event(method, &params) else {
+              return;
+            };
+            let fetch_body = capture_bodies && !entry.failed;
+            let Some(wv) = tabs.get(tab).map(|t| &t.webview).filter(|_| fetch_body) else {
+              let _ = resp_tx.send(BackendMessage::Network(Box::new(entry)));
+              return;
+            };
+
+            let body_params = serde_json::json!({ "requestId": entry.id }).to_string();
+            let proxy_body = proxy.clone();
+            let pending_entry = Box::new(entry);
+            webview2::call_devtools(&wv.webview(), "Network.getResponseBody", &body_params, move |result| {
+              let _ = proxy_body.send_event(UserEvent::NetworkBody { entry: pending_entry, result });
+            });
+          }
+          Event::UserEvent(UserEvent::NetworkBody { mut entry, result }) => {
+            if let Ok(body) = result {
+              apply_response_body(&mut entry, &body, max_body_bytes);
+            }
+            let _ = resp_tx.send(BackendMessage::Network(entry));
+          }
+          Event::UserEvent(UserEvent::SetNetworkCapture { capture_bodies: enabled, max_body_bytes: max_bytes }) => {
+            capture_bodies = enabled;
+            max_body_bytes = max_bytes;
+          }
+          Event::UserEvent(UserEvent::Screenshot { id, options, timeout_ms }) => {
+            let Some(wv) = tabs.active().map(|t| &t.webview) else {
+              send_error(&resp_tx, id, "webview_not_started");
+              return;
+            };
+
             let now_ms = start.elapsed().as_millis() as u64;
-            for id in pending.expired(now_ms) {
-              let kind = pending_kind.remove(&id).unwrap_or("cmd");
-              if goto_pending == Some(id) {
-                goto_pending = None;
+            pending.insert(id, now_ms, timeout_ms);
+            pending_kind.insert(id, "screenshot");
+
+            let proxy_shot = proxy.clone();
+            let done_options = Box::new(options.clone());
+            webview2::capture_screenshot(&wv.webview(), options, move |result| {
+              let _ = proxy_shot.send_event(UserEvent::ScreenshotDone { id, options: done_options, result });
+            });
+          }
+          Event::UserEvent(UserEvent::ScreenshotDone { id, options, result }) => {
+            // Already reported as `screenshot_timeout`.
+            if !pending.complete(id) {
+              return;
+            }
+            pending_kind.remove(&id);
+            match result.and_then(|bytes| screenshot::result_json(&options, &bytes)) {
+              Ok(result_json) => {
+                let _ = resp_tx.send(BackendMessage::Response(BrowserResponse {
+                  request_id: id,
+                  ok: true,
+                  result_json,
+                  error: String::new(),
+                }));
               }
-              let _ = resp_tx.send(BrowserResponse {
-                request_id: id,
-                ok: false,
-                result_json: "null".to_string(),
-                error: format!("{kind}_timeout"),
-              });
+              Err(e) => send_error(&resp_tx, id, e),
             }
           }
-          _ => {}
-        }
-      });
-    });
+          Event::UserEvent(UserEvent::Pdf { id, path, settings, timeout_ms }) => {
+            let Some(wv) = tabs.active().map(|t| &t.webview) else {
+              send_error(&resp_tx, id, "webview_not_started");
+              return;
+            };
+            if let Some(dir) = path
+              .as_deref()
+              .and_then(|p| std::path::Path::new(p).parent())
+              .filter(|d| !d.as_os_str().is_empty())
+            {
+              if let Err(e) = std::fs::create_dir_all(dir) {
+                send_error(&resp_tx, id, format!("pdf_mkdir_error: {e}"));
+                return;
+              }
+            }
 
-    let proxy = proxy_rx
-      .recv_timeout(std::time::Duration::from_secs(5))
-      .map_err(|_| "failed to receive event loop proxy from webview thread".to_string())?;
+            let now_ms = start.elapsed().as_millis() as u64;
+            pending.insert(id, now_ms, timeout_ms);
+            pending_kind.insert(id, "pdf");
 
-    Ok(Handle {
-      proxy,
-      rx: resp_rx,
-      join,
-    })
-  }
-}
+            let proxy_pdf = proxy.clone();
+            let done_path = path.clone();
+            webview2::print_to_pdf(&wv.webview(), &settings, path.as_deref(), move |result| {
+              let _ = proxy_pdf.send_event(UserEvent::PdfDone { id, path: done_path, result });
+            });
+          }
+          Event::UserEvent(UserEvent::PdfDone { id, path, result }) => {
+            // Already reported as `pdf_timeout`.
+            if !pending.complete(id) {
+              return;
+            }
+            pending_kind.remove(&id);
+            match result {
+              Ok(bytes) => {
+                let _ = resp_tx.send(BackendMessage::Response(BrowserResponse {
+                  request_id: id,
+                  ok: true,
+                  result_json: pdf::result_json(path.as_deref(), bytes.as_deref()),
+                  error: String::new(),
+                }));
+              }
+              Err(e) => send_error(&resp_tx, id, e),
+            }
+          }
+          Event::UserEvent(UserEvent::CookiesGet { id, urls, timeout_ms }) => {
+            let Some(wv) = tabs.active().map(|t| &t.webview) else {
+              send_error(&resp_tx, id, "webview_not_started");
+              return;
+            };
+            let manager = match webview2::cookie_manager(&wv.webview()) {
+              Ok(manager) => manager,
+              Err(e) => return send_error(&resp_tx, id, e),
+            };
 
-#[cfg(not(windows))]
-mod backend {
-  use super::*;
+            let now_ms = start.elapsed().as_millis() as u64;
+            pending.insert(id, now_ms, timeout_ms);
+            pending_kind.insert(id, "cookies");
 
-  #[derive(Debug)]
-  pub(super) struct Handle {
-    pub rx: mpsc::Receiver<BrowserResponse>,
-  }
+            let proxy_cookies = proxy.clone();
+            webview2::get_cookies(&manager, urls, move |result| {
+              let result = result.map(|list| cookies::cookies_json(&list));
+              let _ = proxy_cookies.send_event(UserEvent::CookiesDone { id, result });
+            });
+          }
+          Event::UserEvent(UserEvent::CookiesSet { id, cookies }) => {
+            let Some(wv) = tabs.active().map(|t| &t.webview) else {
+              send_error(&resp_tx, id, "webview_not_started");
+              return;
+            };
+            let result = webview2::cookie_manager(&wv.webview()).and_then(|manager| {
+              cookies.iter().try_for_each(|cookie| webview2::set_cookie(&manager, cookie))?;
+              Ok(serde_json::json!({ "count": cookies.len() }).to_string())
+            });
+            send_result(&resp_tx, id, result);
+          }
+          Event::UserEvent(UserEvent::CookiesClear { id, filter, timeout_ms }) => {
+            let Some(wv) = tabs.active().map(|t| &t.webview) else {
+              send_error(&resp_tx, id, "webview_not_started");
+              return;
+            };
+            let manager = match webview2::cookie_manager(&wv.webview()) {
+              Ok(manager) => manager,
+              Err(e) => return send_error(&resp_tx, id, e),
+            };
 
-  pub(super) fn spawn() -> Result<Handle, String> {
-    let (_tx, rx) = mpsc::channel::<BrowserResponse>();
-    Ok(Handle { rx })
-  }
-}
+            let now_ms = start.elapsed().as_millis() as u64;
+            pending.insert(id, now_ms, timeout_ms);
+            pending_kind.insert(id, "cookies");
+
+            let proxy_cookies = proxy.clone();
+            webview2::clear_cookies(&manager, filter, move |result| {
+              let result = result.map(|deleted| serde_json::json!({ "deleted": deleted }).to_string());
+              let _ = proxy_cookies.send_event(UserEvent::CookiesDone { id, result });
+            });
+          }
+          Event::UserEvent(UserEvent::CookiesDone { id, result }) => {
+            // Already reported as `cookies_timeout`.
+            if !pending.complete(id) {
+              return;
+            }
+            pending_kind.remove(&id);
+            send_result(&resp_tx, id, result);
+          }
+          Event::UserEvent(UserEvent::StorageStateSave { id, path, origins, timeout_ms }) => {
+            let Some(wv) = tabs.active().map(|t| &t.webview) else {
+              send_error(&resp_tx, id, "webview_not_started");
+              return;
+            };
+            let core = wv.webview();
+            let manager = match webview2::cookie_manager(&core) {
+              Ok(manager) => manager,
+              Err(e) => return send_error(&resp_tx, id, e),
+            };
+            let (current_url, _) = webview2::page_info(&core);
+            let current_origin = origin_of(&current_url);
+            let origins = storage_origins(&origins, &current_url);
+            let scratch = if origins.iter().any(|o| Some(o) != current_origin.as_ref()) {
+              match build_scratch(_target, &mut web_context, profile.incognito) {
+                Ok(scratch) => Some(scratch),
+                Err(e) => return send_error(&resp_tx, id, e),
+              }
+            } else {
+              None
+            };
+            let scratch_core = scratch.as_ref().map(|s| s.webview.webview());
+            let canceled = Rc::new(Cell::new(false));
+            storage_collects.insert(id, (scratch, canceled.clone()));
+
+            let now_ms = start.elapsed().as_millis() as u64;
+            pending.insert(id, now_ms, timeout_ms);
+            pending_kind.insert(id, "storage_state");
+
+            let proxy_state = proxy.clone();
+            webview2::get_cookies(&manager, Vec::new(), move |cookies| {
+              let cookies = match cookies {
+                Ok(cookies) => cookies,
+                Err(e) => {
+                  let _ = proxy_state.send_event(UserEvent::StorageStateSaved { id, path, result: Err(e) });
+                  return;
+                }
+              };
+              let scratch = scratch_core.as_ref();
+              webview2::collect_local_storage(&core, scratch, current_origin, origins, canceled, move |origins| {
+                let result = origins.map(|origins| Box::new(StorageState::new(cookies, origins)));
+                let _ = proxy_state.send_event(UserEvent::StorageStateSaved { id, path, result });
+              });
+            });
+          }
+          Event::UserEvent(UserEvent::StorageStateSaved { id, path, result }) => {
+            storage_collects.remove(&id);
+            // Already reported as `storage_state_timeout`.
+            if !pending.complete(id) {
+              return;
+            }
+            pending_kind.remove(&id);
+            let result = result.and_then(|state| {
+              state.save(std::path::Path::new(&path))?;
+              Ok(
+                serde_json::json!({ "path": path, "cookies": state.cookies.len(), "origins": state.origins.len() })
+                  .to_string(),
+              )
+            });
+            send_result(&resp_tx, id, result);
+          }
+          Event::UserEvent(UserEvent::StorageStateLoad { id, state }) => {
+            let Some(wv) = tabs.active().map(|t| &t.webview) else {
+              send_error(&resp_tx, id, "webview_not_started");
+              return;
+            };
+            let core = wv.webview();
+            let cookies_set = webview2::cookie_manager(&core)
+              .and_then(|manager| state.cookies.iter().try_for_each(|cookie| webview2::set_cookie(&manager, cookie)));
+            if let Err(e) = cookies_set {
+              return send_error(&resp_tx, id, e);
+            }
+
+            for (tab_id, script_id) in seed_script_ids.drain() {
+              if let Some(tab) = tabs.get(tab_id) {
+                webview2::remove_document_script(&tab.webview.webview(), &script_id);
+              }
+            }
+            let summary =
+              serde_json::json!({ "cookies": state.cookies.len(), "origins": state.origins.len() }).to_string();
+            match state.seed_script() {
+              Some(js) => {
+                seed_state = Some(*state);
+                // The request completes with the active tab's registration.
+                let mut request = Some((id, summary));
+                let active = tabs.active_id();
+                for (tab_id, tab) in tabs.iter() {
+                  let request = if Some(tab_id) == active { request.take() } else { None };
+                  add_seed_script(&tab.webview, &js, &proxy, tab_id, request);
+                }
+              }
+              None => {
+                seed_state = None;
+                send_result(&resp_tx, id, Ok(summary));
+              }
+            }
+          }
+          Event::UserEvent(UserEvent::AddInitScript { id, js, origin_pattern, run_now }) => {
+            let Some(wv) = tabs.active().map(|t| &t.webview) else {
+              send_error(&resp_tx, id, "webview_not_started");
+              return;
+            };
+            let script = match init_scripts.add(&js, &origin_pattern) {
+              Ok(script) => script,
+              Err(e) => return send_error(&resp_tx, id, e),
+            };
+            let source = init_scripts.get(script).unwrap_or_default().to_string();
+            if run_now {
+              if let Err(e) = wv.evaluate_script(&source) {
+                init_scripts.remove(script);
+                return send_error(&resp_tx, id, e);
+              }
+            }
+            // The request completes with the active tab's registration.
+            let active = tabs.active_id();
+            for (tab_id, tab) in tabs.iter() {
+              let request = (Some(tab_id) == active).then_some(id);
+              add_init_script(&tab.webview, &source, &proxy, tab_id, script, request);
+            }
+          }
+          Event::UserEvent(UserEvent::InitScriptRegistered { tab, script, request, result }) => {
+            if let (Ok(native_id), Some(registered)) = (&result, tabs.get(tab)) {
+              if !init_scripts.register(script, tab, native_id.clone()) {
+                webview2::remove_document_script(&registered.webview.webview(), native_id);
+              }
+            }
+            if let Some(id) = request {
+              send_result(&resp_tx, id, result.map(|_| serde_json::json!({ "id": script }).to_string()));
+            }
+          }
+          Event::UserEvent(UserEvent::RemoveInitScript { id, script }) => {
+            let Some(registrations) = init_scripts.remove(script) else {
+              return send_error(&resp_tx, id, format!("init_script_not_found: {script}"));
+            };
+            for (tab_id, native_id) in registrations {
+              if let Some(tab) = tabs.get(tab_id) {
+                webview2::remove_document_script(&tab.webview.webview(), &native_id);
+              }
+            }
+            send_result(&resp_tx, id, Ok(serde_json::json!({ "removed": script }).to_string()));
+          }
+          Event::UserEvent(UserEvent::StorageSeedScript { tab, request, result }) => {
+            if let (Ok(script_id), Some(seeded)) = (&result, tabs.get(tab)) {
+              if seed_state.is_some() {
+                seed_script_ids.insert(tab, script_id.clone());
+              } else {
+                webview2::remove_document_script(&seeded.webview.webview(), script_id);
+              }
+            }
+            if let Some((id, summary)) = request {
+              send_result(&resp_tx, id, result.map(|_| summary));
+            }
+          }
+          Event::UserEvent(UserEvent::TracingStart) => {
+            match TraceRecorder::start(start.elapsed().as_millis() as u64) {
+              Ok(recorder) => *trace.borrow_mut() = Some(recorder),
+              Err(e) => send_error(&resp_tx, -1, e),
+            }
+          }
+          Event::UserEvent(UserEvent::TracingStop { id, path }) => {
+            let recorder = trace.borrow_mut().take();
+            let Some(recorder) = recorder else {
+              send_error(&resp_tx, id, "tracing_not_started");
+              return;
+            };
+            match recorder.save(std::path::Path::new(&path)) {
+              Ok(()) => {
+                let result_json = serde_json::to_string(&path).unwrap_or_else(|_| "\"\"".to_string());
+                let _ = resp_tx.send(BackendMessage::Response(BrowserResponse {
+                  request_id: id,
+                  ok: true,
+                  result_json,
+                  error: String::new(),
+                }));
+              }
+              Err(e) => send_error(&resp_tx, id, e),
+            }
+          }
+          Event::UserEvent(UserEvent::TraceFrame { id, png }) => {
+            if let Some(t) = trace.borrow_mut().as_mut() {
+              let _ = t.frame(start.elapsed().as_millis() as u64, id, &png);
+            }
+          }
+          Event::UserEvent(UserEvent::Tick) => {
+            let now_ms = start.elapsed().as_millis() as u64;
+            for id in pending.expired(now_ms) {
+              let kind = pending_kind.remove(&id).unwrap_or("cmd");
+              goto_pending.retain(|_, goto_id| *goto_id != id);
+              url_waiters.remove(id);
+              if let Some((scratch, canceled)) = storage_collects.remove(&id) {
+                let scratch = scratch.as_ref().map(|s| s.webview.webview());
+                webview2::cancel_storage_collect(&canceled, scratch.as_ref());
+              }
+              let _ = resp_tx.send(BackendMessage::Response(BrowserResponse {
+                request_id: id,
+                ok: false,
+                result_json: "null".to_string(),
+                error: format!("{kind}_timeout"),
+              }));
+            }
+          }
+          _ => {}
+        }
+      });
+    });
+
+    let proxy = proxy_rx
+      .recv_timeout(std::time::Duration::from_secs(5))
+      .map_err(|_| "failed to receive event loop proxy from webview thread".to_string())?;
+
+    Ok(Handle {
+      proxy,
+      rx: resp_rx,
+      join,
+    })
+  }
+}
+
+#[cfg(not(windows))]
+mod backend {
+  use super::*;
+
+  #[derive(Debug)]
+  pub(super) struct Handle {
+    pub rx: mpsc::Receiver<BackendMessage>,
+  }
+
+  pub(super) fn spawn() -> Result<Handle, String> {
+    let (_tx, rx) = mpsc::channel::<BackendMessage>();
+    Ok(Handle { rx })
+  }
+}
 
 #[derive(GodotClass)]
 #[class(base = Node)]
@@ -336,7 +1337,18 @@ pub struct WryBrowser {
   #[cfg(windows)]
   join: Option<std::thread::JoinHandle<()>>,
 
-  rx: Option<mpsc::Receiver<BrowserResponse>>,
+  rx: Option<mpsc::Receiver<BackendMessage>>,
+
+  network_log: NetworkLog,
+  network_capture_bodies: bool,
+  network_max_body_bytes: usize,
+  new_window_policy: NewWindowPolicy,
+  dialog_policy: DialogPolicy,
+  permission_policy: PermissionPolicy,
+  download_dir: String,
+  navigation_policy: NavigationPolicy,
+
+  profile: ProfileSettings,
 }
 
 #[godot_api]
@@ -350,6 +1362,15 @@ impl INode for WryBrowser {
       #[cfg(windows)]
       join: None,
       rx: None,
+      network_log: NetworkLog::default(),
+      network_capture_bodies: false,
+      network_max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+      new_window_policy: NewWindowPolicy::default(),
+      dialog_policy: DialogPolicy::default(),
+      permission_policy: PermissionPolicy::default(),
+      download_dir: String::new(),
+      navigation_policy: NavigationPolicy::default(),
+      profile: ProfileSettings::default(),
     }
   }
 
@@ -367,27 +1388,136 @@ impl WryBrowser {
   #[signal]
   fn completed(request_id: i64, ok: bool, result_json: String, error: String);
 
+  #[signal]
+  fn request_finished(request_json: String);
+
+  /// A tab was opened by `tab_new` (`opener_tab_id` is -1) or by a popup / `window.open`.
+  #[signal]
+  fn tab_opened(tab_id: i64, url: String, opener_tab_id: i64);
+
+  /// A popup was blocked by the `signal` new-window policy; the game decides what to do with `url`.
+  #[signal]
+  fn popup_requested(url: String);
+
+  /// A JavaScript dialog opened; `type` is `alert`, `confirm`, `prompt` or `beforeunload`.
+  #[signal]
+  fn dialog(r#type: String, message: String, default_value: String);
+
+  /// A page asked for a permission such as `camera`, `microphone`, `geolocation`, `notifications`
+  /// or `clipboard_read`; with the `ask` decision it waits for `permission_respond`.
+  #[signal]
+  fn permission_requested(origin: String, kind: String);
+
+  /// A file input tried to open the native picker, which is suppressed; answer with `set_input_files`.
+  #[signal]
+  fn file_chooser(multiple: bool);
+
+  /// A download started; it is saved to `path` in the download directory.
+  #[signal]
+  fn download_started(download_id: i64, url: String, suggested_filename: String, path: String);
+
+  /// `total_bytes` is -1 when the size is unknown.
+  #[signal]
+  fn download_progress(download_id: i64, received_bytes: i64, total_bytes: i64);
+
+  /// `error` is empty on success, `download_canceled` or `download_interrupted: <reason>` otherwise.
+  #[signal]
+  fn download_finished(download_id: i64, ok: bool, path: String, received_bytes: i64, error: String);
+
   fn drain_responses(&mut self) {
-    let mut drained: Vec<BrowserResponse> = Vec::new();
+    let mut drained: Vec<BackendMessage> = Vec::new();
     if let Some(rx) = &self.rx {
-      while let Ok(resp) = rx.try_recv() {
-        drained.push(resp);
+      while let Ok(msg) = rx.try_recv() {
+        drained.push(msg);
       }
     }
 
-    for resp in drained {
+    for msg in drained {
       // IMPORTANT: do not emit signals synchronously from Rust methods.
       // Signal callbacks can re-enter this same Rust object (e.g. user calls `eval()` inside
       // the `completed` handler), which would trigger a nested mutable bind and panic.
       // Use Godot's deferred call so the signal is emitted later by the engine (no Rust bind held).
-      let args = [
-        StringName::from("completed").to_variant(),
-        resp.request_id.to_variant(),
-        resp.ok.to_variant(),
-        resp.result_json.to_variant(),
-        resp.error.to_variant(),
-      ];
-      self.base_mut().call_deferred("emit_signal", &args);
+      match msg {
+        BackendMessage::Response(resp) => {
+          let args = [
+            StringName::from("completed").to_variant(),
+            resp.request_id.to_variant(),
+            resp.ok.to_variant(),
+            resp.result_json.to_variant(),
+            resp.error.to_variant(),
+          ];
+          self.base_mut().call_deferred("emit_signal", &args);
+        }
+        BackendMessage::Network(entry) => {
+          let request_json = serde_json::to_string(&entry).unwrap_or_else(|_| "null".to_string());
+          self.network_log.push(*entry);
+          let args = [
+            StringName::from("request_finished").to_variant(),
+            request_json.to_variant(),
+          ];
+          self.base_mut().call_deferred("emit_signal", &args);
+        }
+        BackendMessage::Download { download_id, event } => {
+          let args = match event {
+            DownloadEvent::Started { url, suggested_filename, path } => vec![
+              StringName::from("download_started").to_variant(),
+              download_id.to_variant(),
+              url.to_variant(),
+              suggested_filename.to_variant(),
+              path.to_variant(),
+            ],
+            DownloadEvent::Progress { received_bytes, total_bytes } => vec![
+              StringName::from("download_progress").to_variant(),
+              download_id.to_variant(),
+              received_bytes.to_variant(),
+              total_bytes.to_variant(),
+            ],
+            DownloadEvent::Finished { path, received_bytes, error } => vec![
+              StringName::from("download_finished").to_variant(),
+              download_id.to_variant(),
+              error.is_none().to_variant(),
+              path.to_variant(),
+              received_bytes.to_variant(),
+              error.unwrap_or_default().to_variant(),
+            ],
+          };
+          self.base_mut().call_deferred("emit_signal", &args);
+        }
+        BackendMessage::FileChooser { multiple } => {
+          let args = [StringName::from("file_chooser").to_variant(), multiple.to_variant()];
+          self.base_mut().call_deferred("emit_signal", &args);
+        }
+        BackendMessage::Dialog { kind, message, default_text } => {
+          let args = [
+            StringName::from("dialog").to_variant(),
+            kind.to_variant(),
+            message.to_variant(),
+            default_text.to_variant(),
+          ];
+          self.base_mut().call_deferred("emit_signal", &args);
+        }
+        BackendMessage::Permission { origin, kind } => {
+          let args = [
+            StringName::from("permission_requested").to_variant(),
+            origin.to_variant(),
+            kind.to_variant(),
+          ];
+          self.base_mut().call_deferred("emit_signal", &args);
+        }
+        BackendMessage::PopupRequested { url } => {
+          let args = [StringName::from("popup_requested").to_variant(), url.to_variant()];
+          self.base_mut().call_deferred("emit_signal", &args);
+        }
+        BackendMessage::TabOpened { tab_id, url, opener_id } => {
+          let args = [
+            StringName::from("tab_opened").to_variant(),
+            tab_id.to_variant(),
+            url.to_variant(),
+            opener_id.to_variant(),
+          ];
+          self.base_mut().call_deferred("emit_signal", &args);
+        }
+      }
     }
   }
 
@@ -401,8 +1531,24 @@ impl WryBrowser {
     if self.proxy.is_some() {
       return true;
     }
-    match backend::spawn() {
+    match backend::spawn(self.profile.clone()) {
       Ok(handle) => {
+        let _ = handle.proxy.send_event(backend::UserEvent::SetNetworkCapture {
+          capture_bodies: self.network_capture_bodies,
+          max_body_bytes: self.network_max_body_bytes,
+        });
+        let _ = handle.proxy.send_event(backend::UserEvent::SetNewWindowPolicy(self.new_window_policy));
+        let _ = handle.proxy.send_event(backend::UserEvent::SetDialogPolicy(self.dialog_policy.clone()));
+        let _ = handle
+          .proxy
+          .send_event(backend::UserEvent::SetPermissionPolicy(self.permission_policy.clone()));
+        if self.download_dir.is_empty() {
+          self.download_dir = crate::args::output_path(&GString::from(crate::download::DOWNLOADS_DIR));
+        }
+        let _ = handle.proxy.send_event(backend::UserEvent::SetDownloadDir(self.download_dir.clone()));
+        let _ = handle
+          .proxy
+          .send_event(backend::UserEvent::SetNavigationPolicy(self.navigation_policy.clone()));
         self.proxy = Some(handle.proxy);
         self.rx = Some(handle.rx);
         self.join = Some(handle.join);
@@ -501,102 +1647,1068 @@ impl WryBrowser {
     self.next_request_id
   }
 
-  #[func]
-  fn goto(&mut self, url: GString, timeout_ms: i64) -> i64 {
+  fn wait_for_url_change(&mut self, pattern: Option<String>, timeout_ms: i64) -> i64 {
     #[cfg(not(windows))]
-    let _ = (&url, timeout_ms);
+    let _ = (&pattern, timeout_ms);
 
     let id = self.next_id();
     #[cfg(windows)]
     if let Some(proxy) = &self.proxy {
-      let _ = proxy.send_event(backend::UserEvent::Goto {
+      let _ = proxy.send_event(backend::UserEvent::WaitForUrl {
         id,
-        url: url.to_string(),
+        pattern,
+        timeout_ms: timeout_ms.max(0) as u64,
+      });
+    }
+    id
+  }
+
+  fn history_action(&mut self, action: HistoryAction, timeout_ms: i64) -> i64 {
+    #[cfg(not(windows))]
+    let _ = (action, timeout_ms);
+
+    let id = self.next_id();
+    #[cfg(windows)]
+    if let Some(proxy) = &self.proxy {
+      let _ = proxy.send_event(backend::UserEvent::History {
+        id,
+        action,
         timeout_ms: timeout_ms.max(0) as u64,
       });
-      return id;
     }
+    id
+  }
+
+  fn history_state(&mut self, forward: bool) -> i64 {
+    #[cfg(not(windows))]
+    let _ = forward;
 
+    let id = self.next_id();
+    #[cfg(windows)]
+    if let Some(proxy) = &self.proxy {
+      let _ = proxy.send_event(backend::UserEvent::HistoryState { id, forward });
+    }
     id
   }
 
-  #[func]
-  fn eval(&mut self, js: GString, timeout_ms: i64) -> i64 {
+  /// Queues a shim command on the active tab and returns its request id.
+  fn js_command(&mut self, cmd: Command, timeout_ms: i64) -> i64 {
+    self.js_command_in(None, cmd, timeout_ms)
+  }
+
+  /// Like [`Self::js_command`] for a given tab; `None` is the active tab.
+  fn js_command_in(&mut self, tab: Option<i64>, cmd: Command, timeout_ms: i64) -> i64 {
     #[cfg(not(windows))]
-    let _ = (&js, timeout_ms);
+    let _ = (tab, cmd, timeout_ms);
 
     let id = self.next_id();
     #[cfg(windows)]
     if let Some(proxy) = &self.proxy {
       let _ = proxy.send_event(backend::UserEvent::JsCommand {
         id,
-        cmd: Command::Eval { js: js.to_string() },
+        tab,
+        cmd,
         timeout_ms: timeout_ms.max(0) as u64,
       });
-      return id;
     }
     id
   }
 
   #[func]
-  fn click(&mut self, selector: GString, timeout_ms: i64) -> i64 {
+  fn goto(&mut self, url: GString, timeout_ms: i64) -> i64 {
+    self.goto_in(None, url.to_string(), timeout_ms)
+  }
+
+  /// Like `goto` for the tab `tab_id`, whether or not it is active.
+  #[func]
+  fn goto_in_tab(&mut self, tab_id: i64, url: GString, timeout_ms: i64) -> i64 {
+    self.goto_in(Some(tab_id), url.to_string(), timeout_ms)
+  }
+
+  fn goto_in(&mut self, tab: Option<i64>, url: String, timeout_ms: i64) -> i64 {
     #[cfg(not(windows))]
-    let _ = (&selector, timeout_ms);
+    let _ = (tab, url, timeout_ms);
 
     let id = self.next_id();
     #[cfg(windows)]
     if let Some(proxy) = &self.proxy {
-      let _ = proxy.send_event(backend::UserEvent::JsCommand {
+      let _ = proxy.send_event(backend::UserEvent::Goto {
         id,
-        cmd: Command::Click {
-          selector: selector.to_string(),
-        },
+        tab,
+        url,
         timeout_ms: timeout_ms.max(0) as u64,
       });
-      return id;
     }
     id
   }
 
+  /// Loads `html` as the active tab's document, with the automation shim installed, and completes
+  /// once it has loaded. A non-empty http(s) `base_url` serves the document at that URL, so it gets
+  /// its origin and relative URLs resolve against it; without one the document has an opaque origin
+  /// and is limited to 2 MB.
   #[func]
-  fn fill(&mut self, selector: GString, text: GString, timeout_ms: i64) -> i64 {
+  fn set_content(&mut self, html: GString, base_url: GString, timeout_ms: i64) -> i64 {
+    let content = match content::prepare(&html.to_string(), &base_url.to_string()) {
+      Ok(content) => content,
+      Err(e) => {
+        let id = self.next_id();
+        self.defer_error(id, e);
+        return id;
+      }
+    };
     #[cfg(not(windows))]
-    let _ = (&selector, &text, timeout_ms);
+    let _ = (&content, timeout_ms);
 
     let id = self.next_id();
     #[cfg(windows)]
     if let Some(proxy) = &self.proxy {
-      let _ = proxy.send_event(backend::UserEvent::JsCommand {
+      let _ = proxy.send_event(backend::UserEvent::SetContent {
         id,
-        cmd: Command::Fill {
-          selector: selector.to_string(),
-          text: text.to_string(),
-        },
+        content,
         timeout_ms: timeout_ms.max(0) as u64,
       });
-      return id;
     }
     id
   }
 
+  /// Registers `js` to run at document creation on every future page of every tab, after the
+  /// automation shim and before page scripts. A non-empty `origin_pattern` limits it to matching
+  /// origins (`https://*.example.com`, or a bare host for any scheme and port) and runs it inside a
+  /// function, so its top-level declarations are not globals. `run_now` also runs it on the active
+  /// tab's current page, and the script is dropped again when that fails. Completes with `{ id }`
+  /// for `remove_init_script`.
   #[func]
-  fn wait_for_selector(&mut self, selector: GString, timeout_ms: i64) -> i64 {
+  fn add_init_script(&mut self, js: GString, origin_pattern: GString, run_now: bool) -> i64 {
     #[cfg(not(windows))]
-    let _ = (&selector, timeout_ms);
+    let _ = (&js, &origin_pattern, run_now);
 
     let id = self.next_id();
     #[cfg(windows)]
     if let Some(proxy) = &self.proxy {
-      let _ = proxy.send_event(backend::UserEvent::JsCommand {
+      let _ = proxy.send_event(backend::UserEvent::AddInitScript {
         id,
-        cmd: Command::WaitForSelector {
-          selector: selector.to_string(),
-          timeout_ms: timeout_ms.max(0) as u64,
-        },
-        timeout_ms: timeout_ms.max(0) as u64,
+        js: js.to_string(),
+        origin_pattern: origin_pattern.to_string(),
+        run_now,
       });
+    }
+    id
+  }
+
+  /// Unregisters a script from `add_init_script`; pages already loaded keep its effects.
+  #[func]
+  fn remove_init_script(&mut self, script_id: i64) -> i64 {
+    let id = self.next_id();
+    if script_id <= 0 {
+      self.defer_error(id, format!("init_script_not_found: {script_id}"));
       return id;
     }
+    #[cfg(windows)]
+    if let Some(proxy) = &self.proxy {
+      let _ = proxy.send_event(backend::UserEvent::RemoveInitScript { id, script: script_id as u64 });
+    }
     id
   }
+
+  #[func]
+  fn eval(&mut self, js: GString, timeout_ms: i64) -> i64 {
+    self.js_command(Command::Eval { js: js.to_string() }, timeout_ms)
+  }
+
+  #[func]
+  fn click(&mut self, selector: GString, timeout_ms: i64) -> i64 {
+    let cmd = Command::Click {
+      selector: Target::from_selector(&selector.to_string()),
+    };
+    self.js_command(cmd, timeout_ms)
+  }
+
+  #[func]
+  fn fill(&mut self, selector: GString, text: GString, timeout_ms: i64) -> i64 {
+    let cmd = Command::Fill {
+      selector: Target::from_selector(&selector.to_string()),
+      text: text.to_string(),
+    };
+    self.js_command(cmd, timeout_ms)
+  }
+
+  /// Completes with the text content of the element matching `selector` (or `handle=<id>`).
+  #[func]
+  fn text(&mut self, selector: GString, timeout_ms: i64) -> i64 {
+    let selector = Target::from_selector(&selector.to_string());
+    self.js_command(Command::Text { selector }, timeout_ms)
+  }
+
+  /// Completes with attribute `name` of the element matching `selector` (or `handle=<id>`), or null.
+  #[func]
+  fn attr(&mut self, selector: GString, name: GString, timeout_ms: i64) -> i64 {
+    let cmd = Command::Attr {
+      selector: Target::from_selector(&selector.to_string()),
+      name: name.to_string(),
+    };
+    self.js_command(cmd, timeout_ms)
+  }
+
+  /// Completes with `[{ text, attrs }]` for every element matching `selector`, up to `limit`
+  /// (0 = all), where `attrs` maps each requested attribute name to its value or null.
+  #[func]
+  fn query_all(&mut self, selector: GString, attrs: PackedStringArray, limit: i64, timeout_ms: i64) -> i64 {
+    let cmd = Command::QueryAll {
+      selector: selector.to_string(),
+      attrs: attrs.as_slice().iter().map(GString::to_string).collect(),
+      limit: limit.max(0) as u64,
+    };
+    self.js_command(cmd, timeout_ms)
+  }
+
+  /// Completes with the number of elements matching `selector`.
+  #[func]
+  fn count(&mut self, selector: GString, timeout_ms: i64) -> i64 {
+    self.js_command(Command::Count { selector: selector.to_string() }, timeout_ms)
+  }
+
+  #[func]
+  fn inner_html(&mut self, selector: GString, timeout_ms: i64) -> i64 {
+    let selector = Target::from_selector(&selector.to_string());
+    self.js_command(Command::InnerHtml { selector }, timeout_ms)
+  }
+
+  #[func]
+  fn outer_html(&mut self, selector: GString, timeout_ms: i64) -> i64 {
+    let selector = Target::from_selector(&selector.to_string());
+    self.js_command(Command::OuterHtml { selector }, timeout_ms)
+  }
+
+  /// Completes with the value of the matching `input`, `textarea` or `select` (else `not_input`).
+  #[func]
+  fn input_value(&mut self, selector: GString, timeout_ms: i64) -> i64 {
+    let selector = Target::from_selector(&selector.to_string());
+    self.js_command(Command::InputValue { selector }, timeout_ms)
+  }
+
+  /// Completes with whether the element is rendered with a non-empty box; false when nothing matches.
+  #[func]
+  fn is_visible(&mut self, selector: GString, timeout_ms: i64) -> i64 {
+    let selector = Target::from_selector(&selector.to_string());
+    self.js_command(Command::IsVisible { selector }, timeout_ms)
+  }
+
+  #[func]
+  fn is_enabled(&mut self, selector: GString, timeout_ms: i64) -> i64 {
+    let selector = Target::from_selector(&selector.to_string());
+    self.js_command(Command::IsEnabled { selector }, timeout_ms)
+  }
+
+  /// Completes with the checked state of a checkbox, radio or `aria-checked` element (else
+  /// `not_checkable`).
+  #[func]
+  fn is_checked(&mut self, selector: GString, timeout_ms: i64) -> i64 {
+    let selector = Target::from_selector(&selector.to_string());
+    self.js_command(Command::IsChecked { selector }, timeout_ms)
+  }
+
+  /// Completes with the element's viewport rect `{ x, y, width, height }`, or null when hidden.
+  #[func]
+  fn bounding_box(&mut self, selector: GString, timeout_ms: i64) -> i64 {
+    let selector = Target::from_selector(&selector.to_string());
+    self.js_command(Command::BoundingBox { selector }, timeout_ms)
+  }
+
+  /// Selects options of the matching `<select>`: each entry is a value-or-label string, an index,
+  /// or a `{ value, label, index }` dictionary. Completes with the selected values.
+  #[func]
+  fn select_option(&mut self, selector: GString, options: VariantArray, timeout_ms: i64) -> i64 {
+    let options = match serde_json::from_str::<Vec<OptionMatch>>(&crate::args::array_json(&options)) {
+      Ok(options) => options,
+      Err(e) => {
+        let id = self.next_id();
+        self.defer_error(id, format!("select_options_invalid: {e}"));
+        return id;
+      }
+    };
+    let selector = Target::from_selector(&selector.to_string());
+    self.js_command(Command::SelectOption { selector, options }, timeout_ms)
+  }
+
+  /// Checks or unchecks a checkbox or radio by clicking it, so the page sees real click events.
+  #[func]
+  fn set_checked(&mut self, selector: GString, checked: bool, timeout_ms: i64) -> i64 {
+    let selector = Target::from_selector(&selector.to_string());
+    self.js_command(Command::SetChecked { selector, checked }, timeout_ms)
+  }
+
+  #[func]
+  fn focus(&mut self, selector: GString, timeout_ms: i64) -> i64 {
+    let selector = Target::from_selector(&selector.to_string());
+    self.js_command(Command::Focus { selector }, timeout_ms)
+  }
+
+  #[func]
+  fn blur(&mut self, selector: GString, timeout_ms: i64) -> i64 {
+    let selector = Target::from_selector(&selector.to_string());
+    self.js_command(Command::Blur { selector }, timeout_ms)
+  }
+
+  /// Empties an input, textarea or contenteditable element, firing `input` and `change`.
+  #[func]
+  fn clear(&mut self, selector: GString, timeout_ms: i64) -> i64 {
+    let selector = Target::from_selector(&selector.to_string());
+    self.js_command(Command::Clear { selector }, timeout_ms)
+  }
+
+  /// Submits the form of the matching element (or the form itself), running validation and
+  /// `submit` handlers; a matching submit button is used as the submitter.
+  #[func]
+  fn submit(&mut self, selector: GString, timeout_ms: i64) -> i64 {
+    let selector = Target::from_selector(&selector.to_string());
+    self.js_command(Command::Submit { selector }, timeout_ms)
+  }
+
+  /// Registers the first element matching `selector` and completes with its handle id (null when
+  /// nothing matches). Pass `handle=<id>` as the selector of later commands; they fail with
+  /// `stale_element` once the node is detached.
+  #[func]
+  fn query(&mut self, selector: GString, timeout_ms: i64) -> i64 {
+    self.js_command(Command::Query { selector: selector.to_string() }, timeout_ms)
+  }
+
+  /// Frees element handles (all of them when `handles` is empty); completes with the count freed.
+  #[func]
+  fn dispose_handles(&mut self, handles: PackedInt64Array, timeout_ms: i64) -> i64 {
+    let handles: Vec<u64> = handles.as_slice().iter().filter_map(|h| u64::try_from(*h).ok()).collect();
+
+    self.js_command(Command::DisposeHandles { handles }, timeout_ms)
+  }
+
+  #[func]
+  fn wait_for_selector(&mut self, selector: GString, timeout_ms: i64) -> i64 {
+    let cmd = Command::WaitForSelector {
+      selector: selector.to_string(),
+      timeout_ms: timeout_ms.max(0) as u64,
+      state: SelectorState::Attached,
+    };
+    self.js_command(cmd, timeout_ms)
+  }
+
+  /// Waits until the element matching `selector` is `attached`, `detached`, `visible` or `hidden`.
+  #[func]
+  fn wait_for_selector_state(&mut self, selector: GString, state: GString, timeout_ms: i64) -> i64 {
+    let state = match SelectorState::from_name(&state.to_string()) {
+      Ok(state) => state,
+      Err(e) => {
+        let id = self.next_id();
+        self.defer_error(id, e);
+        return id;
+      }
+    };
+    let cmd = Command::WaitForSelector {
+      selector: selector.to_string(),
+      timeout_ms: timeout_ms.max(0) as u64,
+      state,
+    };
+    self.js_command(cmd, timeout_ms)
+  }
+
+  /// Re-evaluates `js` (a function or an expression) until it is truthy and completes with that
+  /// value; passes that throw count as not ready yet. `polling` is `raf` (default), `mutation` or
+  /// an interval in milliseconds.
+  #[func]
+  fn wait_for_function(&mut self, js: GString, polling: Variant, timeout_ms: i64) -> i64 {
+    let polling = if polling.is_nil() { Ok(Polling::Raf) } else { Polling::from_name(&polling.to_string()) };
+    let polling = match polling {
+      Ok(polling) => polling,
+      Err(e) => {
+        let id = self.next_id();
+        self.defer_error(id, e);
+        return id;
+      }
+    };
+    let cmd = Command::WaitForFunction {
+      js: js.to_string(),
+      polling,
+      timeout_ms: timeout_ms.max(0) as u64,
+    };
+    self.js_command(cmd, timeout_ms)
+  }
+
+  /// Completes with the URL once the active tab's URL matches `pattern` (a glob: `**` matches
+  /// anything, `*` anything but `/`), immediately if it already does. Same-document changes
+  /// (`history.pushState`, back/forward, hash changes) count as well as full page loads.
+  #[func]
+  fn wait_for_url(&mut self, pattern: GString, timeout_ms: i64) -> i64 {
+    self.wait_for_url_change(Some(pattern.to_string()), timeout_ms)
+  }
+
+  /// Completes with the new URL after the active tab's next navigation, full page load or
+  /// same-document. Issue it before the click that navigates.
+  #[func]
+  fn wait_for_navigation(&mut self, timeout_ms: i64) -> i64 {
+    self.wait_for_url_change(None, timeout_ms)
+  }
+
+  /// Navigates back through the active tab's history and completes with the new URL, or with
+  /// `null` when there is no previous entry.
+  #[func]
+  fn go_back(&mut self, timeout_ms: i64) -> i64 {
+    self.history_action(HistoryAction::Back, timeout_ms)
+  }
+
+  /// Navigates forward; completes with the new URL, or `null` when there is no next entry.
+  #[func]
+  fn go_forward(&mut self, timeout_ms: i64) -> i64 {
+    self.history_action(HistoryAction::Forward, timeout_ms)
+  }
+
+  /// Reloads the active tab and completes with its URL once loaded.
+  #[func]
+  fn reload(&mut self, timeout_ms: i64) -> i64 {
+    self.history_action(HistoryAction::Reload, timeout_ms)
+  }
+
+  /// Stops loading the active tab; completes with `true` straight away.
+  #[func]
+  fn stop_loading(&mut self) -> i64 {
+    self.history_action(HistoryAction::Stop, 0)
+  }
+
+  /// Completes with whether the active tab has a previous history entry.
+  #[func]
+  fn can_go_back(&mut self) -> i64 {
+    self.history_state(false)
+  }
+
+  /// Completes with whether the active tab has a next history entry.
+  #[func]
+  fn can_go_forward(&mut self) -> i64 {
+    self.history_state(true)
+  }
+
+  /// Sets the files of the `input[type=file]` matching `selector` (an empty list clears it).
+  /// Files are read from Godot or OS paths and handed to the page as real `File` objects with a
+  /// MIME type guessed from the extension. Completes with the number of files.
+  #[func]
+  fn set_input_files(&mut self, selector: GString, paths: PackedStringArray, timeout_ms: i64) -> i64 {
+    let id = self.next_id();
+    let paths: Vec<String> = paths
+      .as_slice()
+      .iter()
+      .map(crate::args::output_path)
+      .filter(|p| !p.is_empty())
+      .collect();
+
+    #[cfg(not(windows))]
+    let _ = (&selector, &paths, timeout_ms);
+
+    #[cfg(windows)]
+    if let Some(proxy) = &self.proxy {
+      let _ = proxy.send_event(backend::UserEvent::SetInputFiles {
+        id,
+        selector: selector.to_string(),
+        paths,
+        timeout_ms: timeout_ms.max(0) as u64,
+      });
+    }
+    id
+  }
+
+  /// Accessibility snapshot of the page, or of the element matching `root` when not empty.
+  /// `format` is `yaml` (default, an indented outline) or `json` (the node tree). Node refs stay
+  /// stable across snapshots and work as `aria-ref=<ref>` selectors in later commands.
+  #[func]
+  fn snapshot(&mut self, root: GString, format: GString, timeout_ms: i64) -> i64 {
+    let format = match SnapshotFormat::from_name(&format.to_string()) {
+      Ok(format) => format,
+      Err(e) => {
+        let id = self.next_id();
+        self.defer_error(id, e);
+        return id;
+      }
+    };
+    let root = Some(root.to_string()).filter(|r| !r.trim().is_empty());
+    self.js_command(Command::Snapshot { root, format }, timeout_ms)
+  }
+
+  /// Visible page content as Markdown (headings, lists, links, tables) for LLM context. Reads
+  /// `root` when not empty, otherwise the detected main content, and cuts the text to `max_chars`
+  /// (0 = no limit). Completes with `{ title, url, markdown, truncated }`.
+  #[func]
+  fn extract_content(&mut self, root: GString, max_chars: i64, timeout_ms: i64) -> i64 {
+    let root = Some(root.to_string()).filter(|r| !r.trim().is_empty());
+
+    let cmd = Command::ExtractContent {
+      root,
+      max_chars: max_chars.max(0) as u64,
+    };
+    self.js_command(cmd, timeout_ms)
+  }
+
+  /// Sets where downloads are saved (default `user://downloads`); existing names get a ` (n)` suffix.
+  /// The directory must be under `user://`, so pages cannot drop files elsewhere on disk.
+  #[func]
+  fn set_download_dir(&mut self, path: GString) -> bool {
+    let dir = match crate::download::download_dir(&path.to_string()) {
+      Ok(dir) => dir,
+      Err(e) => {
+        self.defer_error(-1, e);
+        return false;
+      }
+    };
+    self.download_dir = crate::args::output_path(&GString::from(dir.as_str()));
+
+    #[cfg(windows)]
+    if let Some(proxy) = &self.proxy {
+      let _ = proxy.send_event(backend::UserEvent::SetDownloadDir(self.download_dir.clone()));
+    }
+    true
+  }
+
+  /// Cancels a running download; `download_finished` follows with `download_canceled`.
+  /// Completes with `{ canceled }`, or fails with `download_not_found`.
+  #[func]
+  fn cancel_download(&mut self, download_id: i64) -> i64 {
+    #[cfg(not(windows))]
+    let _ = download_id;
+
+    let id = self.next_id();
+    #[cfg(windows)]
+    if let Some(proxy) = &self.proxy {
+      let _ = proxy.send_event(backend::UserEvent::CancelDownload { id, download: download_id });
+    }
+    id
+  }
+
+  /// Finished requests recorded by the network observer, oldest first, as a JSON array.
+  #[func]
+  fn network_requests(&self) -> GString {
+    self.network_log.to_json().into()
+  }
+
+  #[func]
+  fn network_clear(&mut self) {
+    self.network_log.clear();
+  }
+
+  #[func]
+  fn set_network_log_capacity(&mut self, capacity: i64) {
+    self.network_log.set_capacity(capacity.max(1) as usize);
+  }
+
+  /// Enables response body capture; bodies longer than `max_body_bytes` are truncated.
+  #[func]
+  fn set_network_capture(&mut self, capture_bodies: bool, max_body_bytes: i64) {
+    self.network_capture_bodies = capture_bodies;
+    self.network_max_body_bytes = max_body_bytes.max(0) as usize;
+
+    #[cfg(windows)]
+    if let Some(proxy) = &self.proxy {
+      let _ = proxy.send_event(backend::UserEvent::SetNetworkCapture {
+        capture_bodies,
+        max_body_bytes: self.network_max_body_bytes,
+      });
+    }
+  }
+
+  /// Sets how popups, `window.open` and `target=_blank` links are handled: `"new_tab"` (default),
+  /// `"same_view"`, `"deny"` or `"signal"` (emit `popup_requested(url)` instead of opening anything).
+  #[func]
+  fn set_new_window_policy(&mut self, policy: GString) -> bool {
+    let policy = match NewWindowPolicy::from_name(&policy.to_string()) {
+      Ok(policy) => policy,
+      Err(e) => {
+        self.defer_error(-1, e);
+        return false;
+      }
+    };
+    self.new_window_policy = policy;
+
+    #[cfg(windows)]
+    if let Some(proxy) = &self.proxy {
+      let _ = proxy.send_event(backend::UserEvent::SetNewWindowPolicy(policy));
+    }
+    true
+  }
+
+  /// Restricts navigations and subresource loads. `config` keys: `allowed_hosts`, `blocked_hosts`
+  /// (patterns like `example.com` or `*.example.com`), `allowed_schemes` and `block_private_ips`.
+  /// `block_private_ips` only recognizes IP literals and `localhost`: host names are not resolved,
+  /// so a public name pointing at a private address (e.g. `127.0.0.1.nip.io`) still loads.
+  /// A blocked `goto` fails with `navigation_blocked`; an empty dictionary lifts all restrictions.
+  #[func]
+  fn set_navigation_policy(&mut self, config: Dictionary) -> bool {
+    let policy = match NavigationPolicy::from_json(&crate::args::dictionary_json(&config)) {
+      Ok(policy) => policy,
+      Err(e) => {
+        self.defer_error(-1, e);
+        return false;
+      }
+    };
+    self.navigation_policy = policy;
+
+    #[cfg(windows)]
+    if let Some(proxy) = &self.proxy {
+      let _ = proxy.send_event(backend::UserEvent::SetNavigationPolicy(self.navigation_policy.clone()));
+    }
+    true
+  }
+
+  /// Sets how JavaScript dialogs are answered: `"dismiss"` (default), `"accept"` (prompts get
+  /// `prompt_text`, or their default value when empty) or `"manual"` (wait for `dialog_respond`).
+  /// Every dialog is reported through the `dialog` signal.
+  #[func]
+  fn set_dialog_policy(&mut self, policy: GString, prompt_text: GString) -> bool {
+    let policy = match DialogPolicy::from_name(&policy.to_string(), &prompt_text.to_string()) {
+      Ok(policy) => policy,
+      Err(e) => {
+        self.defer_error(-1, e);
+        return false;
+      }
+    };
+    self.dialog_policy = policy;
+
+    #[cfg(windows)]
+    if let Some(proxy) = &self.proxy {
+      let _ = proxy.send_event(backend::UserEvent::SetDialogPolicy(self.dialog_policy.clone()));
+    }
+    true
+  }
+
+  /// Sets how permission requests are decided: `{ "default": "deny", "camera": "allow" }` with
+  /// `default` (engine behaviour), `allow`, `deny` or `ask` (wait for `permission_respond`).
+  /// Every request is reported through `permission_requested`.
+  #[func]
+  fn set_permission_policy(&mut self, policy: Dictionary) -> bool {
+    let policy = match PermissionPolicy::from_json(&crate::args::dictionary_json(&policy)) {
+      Ok(policy) => policy,
+      Err(e) => {
+        self.defer_error(-1, e);
+        return false;
+      }
+    };
+    self.permission_policy = policy;
+
+    #[cfg(windows)]
+    if let Some(proxy) = &self.proxy {
+      let _ = proxy.send_event(backend::UserEvent::SetPermissionPolicy(self.permission_policy.clone()));
+    }
+    true
+  }
+
+  /// Answers the oldest waiting permission request matching `origin` and `kind` (empty matches
+  /// any). Completes with `{ origin, kind, allowed }`, or fails with `permission_not_pending`.
+  #[func]
+  fn permission_respond(&mut self, origin: GString, kind: GString, allow: bool) -> i64 {
+    #[cfg(not(windows))]
+    let _ = (&origin, &kind, allow);
+
+    let id = self.next_id();
+    #[cfg(windows)]
+    if let Some(proxy) = &self.proxy {
+      let _ = proxy.send_event(backend::UserEvent::PermissionRespond {
+        id,
+        origin: origin.to_string(),
+        kind: kind.to_string(),
+        allow,
+      });
+    }
+    id
+  }
+
+  /// Answers the oldest open dialog; `text` is the prompt result (its default value when empty).
+  /// Completes with `{ type, accepted }`, or fails with `dialog_not_open`.
+  #[func]
+  fn dialog_respond(&mut self, accept: bool, text: GString) -> i64 {
+    #[cfg(not(windows))]
+    let _ = (accept, &text);
+
+    let id = self.next_id();
+    #[cfg(windows)]
+    if let Some(proxy) = &self.proxy {
+      let _ = proxy.send_event(backend::UserEvent::DialogRespond {
+        id,
+        accept,
+        text: text.to_string(),
+      });
+    }
+    id
+  }
+
+  /// Starts recording commands, native actions (screenshots, cookies, tabs, ...), navigations,
+  /// responses, console output and per-action frames. Frames are written to a temporary archive
+  /// as they arrive, so long traces do not grow in memory.
+  #[func]
+  fn tracing_start(&mut self) {
+    #[cfg(windows)]
+    if let Some(proxy) = &self.proxy {
+      let _ = proxy.send_event(backend::UserEvent::TracingStart);
+    }
+  }
+
+  /// Stops recording and writes the trace archive (zip of `trace.jsonl` + PNG frames) to `path`.
+  #[func]
+  fn tracing_stop(&mut self, path: GString) -> i64 {
+    #[cfg(not(windows))]
+    let _ = &path;
+
+    let id = self.next_id();
+    #[cfg(windows)]
+    if let Some(proxy) = &self.proxy {
+      let _ = proxy.send_event(backend::UserEvent::TracingStop {
+        id,
+        path: crate::args::output_path(&path),
+      });
+    }
+    id
+  }
+
+  /// Captures the page natively. Options: `format` ("png" | "jpeg"), `quality`, `full_page`,
+  /// `selector` or `clip` `{x, y, width, height}`, `path` and `timeout_ms`.
+  ///
+  /// Completes with `{ format, size, path }` when `path` is set, otherwise `{ format, size, base64 }`.
+  #[func]
+  fn screenshot(&mut self, options: Dictionary) -> i64 {
+    let id = self.next_id();
+    let mut options = match ScreenshotOptions::from_json(&crate::args::dictionary_json(&options)) {
+      Ok(options) => options,
+      Err(e) => {
+        self.defer_error(id, e);
+        return id;
+      }
+    };
+    options.path = options
+      .path
+      .take()
+      .filter(|p| !p.trim().is_empty())
+      .map(|p| crate::args::output_path(&GString::from(p)));
+
+    #[cfg(not(windows))]
+    let _ = &options;
+
+    #[cfg(windows)]
+    if let Some(proxy) = &self.proxy {
+      let timeout_ms = options.timeout_ms(10_000);
+      let _ = proxy.send_event(backend::UserEvent::Screenshot { id, options, timeout_ms });
+    }
+    id
+  }
+
+  /// Prints the page to PDF. Options: `format` (paper name, default "letter"), `width`, `height`,
+  /// `margin` `{top, right, bottom, left}`, `landscape`, `scale`, `print_background`,
+  /// `display_header_footer` and `timeout_ms`. Lengths are inches or strings with `px`/`in`/`cm`/`mm`.
+  ///
+  /// Completes with `{ format, path, size }` when `path` is set, otherwise `{ format, size, base64 }`.
+  #[func]
+  fn pdf(&mut self, path: GString, options: Dictionary) -> i64 {
+    let id = self.next_id();
+    let options = match PdfOptions::from_json(&crate::args::dictionary_json(&options)) {
+      Ok(options) => options,
+      Err(e) => {
+        self.defer_error(id, e);
+        return id;
+      }
+    };
+    let settings = match options.settings() {
+      Ok(settings) => settings,
+      Err(e) => {
+        self.defer_error(id, e);
+        return id;
+      }
+    };
+    let path = Some(path)
+      .filter(|p| !p.to_string().trim().is_empty())
+      .map(|p| crate::args::output_path(&p));
+
+    #[cfg(not(windows))]
+    let _ = (&path, &settings);
+
+    #[cfg(windows)]
+    if let Some(proxy) = &self.proxy {
+      let timeout_ms = options.timeout_ms(30_000);
+      let _ = proxy.send_event(backend::UserEvent::Pdf { id, path, settings, timeout_ms });
+    }
+    id
+  }
+
+  /// Reads cookies through the native cookie manager, including `HttpOnly` ones.
+  /// An empty `urls` list returns every cookie in the profile.
+  ///
+  /// Completes with an array of `{ name, value, domain, path, expires, httpOnly, secure, sameSite }`.
+  #[func]
+  fn cookies_get(&mut self, urls: PackedStringArray, timeout_ms: i64) -> i64 {
+    let id = self.next_id();
+    let urls: Vec<String> = urls.as_slice().iter().map(|u| u.to_string()).filter(|u| !u.is_empty()).collect();
+
+    #[cfg(not(windows))]
+    let _ = (&urls, timeout_ms);
+
+    #[cfg(windows)]
+    if let Some(proxy) = &self.proxy {
+      let _ = proxy.send_event(backend::UserEvent::CookiesGet {
+        id,
+        urls,
+        timeout_ms: timeout_ms.max(0) as u64,
+      });
+    }
+    id
+  }
+
+  /// Adds or replaces cookies. Each entry needs `name`, `value` and either `url` or `domain`;
+  /// `path`, `expires`, `httpOnly`, `secure` and `sameSite` are optional.
+  #[func]
+  fn cookies_set(&mut self, cookies: VariantArray) -> i64 {
+    let id = self.next_id();
+    let cookies = match CookieParam::list_from_json(&crate::args::array_json(&cookies))
+      .and_then(|list| list.into_iter().map(CookieParam::resolve).collect::<Result<Vec<_>, _>>())
+    {
+      Ok(cookies) => cookies,
+      Err(e) => {
+        self.defer_error(id, e);
+        return id;
+      }
+    };
+
+    #[cfg(not(windows))]
+    let _ = &cookies;
+
+    #[cfg(windows)]
+    if let Some(proxy) = &self.proxy {
+      let _ = proxy.send_event(backend::UserEvent::CookiesSet { id, cookies });
+    }
+    id
+  }
+
+  /// Deletes cookies matching `filter` (`name`, `domain`, `path`); an empty filter clears all.
+  /// Completes with `{ deleted }`.
+  #[func]
+  fn cookies_clear(&mut self, filter: Dictionary, timeout_ms: i64) -> i64 {
+    let id = self.next_id();
+    let filter = match CookieFilter::from_json(&crate::args::dictionary_json(&filter)) {
+      Ok(filter) => filter,
+      Err(e) => {
+        self.defer_error(id, e);
+        return id;
+      }
+    };
+
+    #[cfg(not(windows))]
+    let _ = (&filter, timeout_ms);
+
+    #[cfg(windows)]
+    if let Some(proxy) = &self.proxy {
+      let _ = proxy.send_event(backend::UserEvent::CookiesClear {
+        id,
+        filter,
+        timeout_ms: timeout_ms.max(0) as u64,
+      });
+    }
+    id
+  }
+
+  /// Selects the profile used by the next `start`/`start_view`: `name` (isolated data under
+  /// `user://wry_profiles/<name>`), `data_dir` (also under `user://wry_profiles`) and `incognito`.
+  /// Fails while the backend is running.
+  #[func]
+  fn set_profile(&mut self, config: Dictionary) -> bool {
+    #[cfg(windows)]
+    if self.proxy.is_some() {
+      self.defer_error(-1, "profile_backend_running".to_string());
+      return false;
+    }
+    let resolved = ProfileConfig::from_json(&crate::args::dictionary_json(&config))
+      .and_then(|config| config.resolve(|dir| crate::args::output_path(&GString::from(dir))));
+    match resolved {
+      Ok(profile) => {
+        self.profile = profile;
+        true
+      }
+      Err(e) => {
+        self.defer_error(-1, e);
+        false
+      }
+    }
+  }
+
+  /// Deletes the data directory of a profile (`name` or `data_dir`), e.g. when a save slot is removed.
+  /// The profile must not be in use by this node. Completes with `{ path, deleted }`.
+  #[func]
+  fn delete_profile_data(&mut self, config: Dictionary) -> i64 {
+    let id = self.next_id();
+    let resolved = ProfileConfig::from_json(&crate::args::dictionary_json(&config))
+      .and_then(|config| config.resolve(|dir| crate::args::output_path(&GString::from(dir))));
+    let dir = match resolved.and_then(|p| p.data_dir.ok_or_else(|| "profile_default_not_deletable".to_string())) {
+      Ok(dir) => dir,
+      Err(e) => {
+        self.defer_error(id, e);
+        return id;
+      }
+    };
+
+    #[cfg(windows)]
+    let in_use = self.proxy.is_some() && self.profile.data_dir.as_deref() == Some(dir.as_str());
+    #[cfg(not(windows))]
+    let in_use = false;
+    if in_use {
+      self.defer_error(id, "profile_in_use".to_string());
+      return id;
+    }
+
+    let root = crate::args::output_path(&GString::from(crate::profile::PROFILES_DIR));
+    match crate::profile::delete_data_dir(&dir, &root) {
+      Ok(deleted) => {
+        let result_json = serde_json::json!({ "path": dir, "deleted": deleted }).to_string();
+        self.defer_result(id, result_json);
+      }
+      Err(e) => self.defer_error(id, e),
+    }
+    id
+  }
+
+  /// Saves every cookie of the profile plus `localStorage` of `origins` (the current page's origin
+  /// when empty) as a versioned Playwright-style `storageState` file. Origins other than the current
+  /// one are read from a blank placeholder page in a hidden webview of the same profile; the active
+  /// tab is left untouched.
+  ///
+  /// Completes with `{ path, cookies, origins }`.
+  #[func]
+  fn storage_state_save(&mut self, path: GString, origins: PackedStringArray, timeout_ms: i64) -> i64 {
+    let id = self.next_id();
+    let path = crate::args::output_path(&path);
+    if path.is_empty() {
+      self.defer_error(id, "storage_state_path_empty".to_string());
+      return id;
+    }
+    let origins: Vec<String> = origins.as_slice().iter().map(|o| o.to_string()).filter(|o| !o.is_empty()).collect();
+
+    #[cfg(not(windows))]
+    let _ = (&path, &origins, timeout_ms);
+
+    #[cfg(windows)]
+    if let Some(proxy) = &self.proxy {
+      let _ = proxy.send_event(backend::UserEvent::StorageStateSave {
+        id,
+        path,
+        origins,
+        timeout_ms: timeout_ms.max(0) as u64,
+      });
+    }
+    id
+  }
+
+  /// Restores a file written by `storage_state_save`: cookies are set right away and `localStorage`
+  /// is seeded the first time each origin is loaded. Call it before the first `goto`.
+  ///
+  /// Completes with `{ cookies, origins }`.
+  #[func]
+  fn storage_state_load(&mut self, path: GString) -> i64 {
+    let id = self.next_id();
+    let state = match StorageState::load(std::path::Path::new(&crate::args::output_path(&path))) {
+      Ok(state) => state,
+      Err(e) => {
+        self.defer_error(id, e);
+        return id;
+      }
+    };
+
+    #[cfg(not(windows))]
+    let _ = &state;
+
+    #[cfg(windows)]
+    if let Some(proxy) = &self.proxy {
+      let _ = proxy.send_event(backend::UserEvent::StorageStateLoad {
+        id,
+        state: Box::new(state),
+      });
+    }
+    id
+  }
+
+  /// Opens a tab (navigated to `url` unless empty) without activating it. Completes with `{ id, url }`.
+  #[func]
+  fn tab_new(&mut self, url: GString) -> i64 {
+    #[cfg(not(windows))]
+    let _ = &url;
+
+    let id = self.next_id();
+    #[cfg(windows)]
+    if let Some(proxy) = &self.proxy {
+      let _ = proxy.send_event(backend::UserEvent::TabNew { id, url: url.to_string() });
+    }
+    id
+  }
+
+  /// Closes a tab (`-1` is the active one); closing the active tab activates its neighbour.
+  /// Completes with `{ closed, active }`.
+  #[func]
+  fn tab_close(&mut self, tab_id: i64) -> i64 {
+    #[cfg(not(windows))]
+    let _ = tab_id;
+
+    let id = self.next_id();
+    #[cfg(windows)]
+    if let Some(proxy) = &self.proxy {
+      let _ = proxy.send_event(backend::UserEvent::TabClose { id, tab: tab_id });
+    }
+    id
+  }
+
+  /// Makes `tab_id` the target of later commands; its page state is kept. Completes with `{ active }`.
+  #[func]
+  fn tab_select(&mut self, tab_id: i64) -> i64 {
+    #[cfg(not(windows))]
+    let _ = tab_id;
+
+    let id = self.next_id();
+    #[cfg(windows)]
+    if let Some(proxy) = &self.proxy {
+      let _ = proxy.send_event(backend::UserEvent::TabSelect { id, tab: tab_id });
+    }
+    id
+  }
+
+  /// Completes with an array of `{ id, url, title, active }`.
+  #[func]
+  fn tab_list(&mut self) -> i64 {
+    let id = self.next_id();
+    #[cfg(windows)]
+    if let Some(proxy) = &self.proxy {
+      let _ = proxy.send_event(backend::UserEvent::TabList { id });
+    }
+    id
+  }
+
+  /// Runs a shim command (e.g. `{ "cmd": "click", "selector": "#ok" }`) in the tab `tab_id`, whether
+  /// or not it is active. Completes like the matching single-tab func.
+  #[func]
+  fn command_in_tab(&mut self, tab_id: i64, command: Dictionary, timeout_ms: i64) -> i64 {
+    match serde_json::from_str::<Command>(&crate::args::dictionary_json(&command)) {
+      Ok(cmd) => self.js_command_in(Some(tab_id), cmd, timeout_ms),
+      Err(e) => {
+        let id = self.next_id();
+        self.defer_error(id, format!("command_invalid: {e}"));
+        id
+      }
+    }
+  }
+
+  fn defer_error(&mut self, request_id: i64, error: String) {
+    let args = [
+      StringName::from("completed").to_variant(),
+      request_id.to_variant(),
+      false.to_variant(),
+      "null".to_variant(),
+      error.to_variant(),
+    ];
+    self.base_mut().call_deferred("emit_signal", &args);
+  }
+
+  fn defer_result(&mut self, request_id: i64, result_json: String) {
+    let args = [
+      StringName::from("completed").to_variant(),
+      request_id.to_variant(),
+      true.to_variant(),
+      result_json.to_variant(),
+      "".to_variant(),
+    ];
+    self.base_mut().call_deferred("emit_signal", &args);
+  }
+
 }
@@ -0,0 +1,84 @@
+use std::io::Cursor;
+
+use godot_wry_playwright::video::{ApngWriter, VideoError};
+
+fn chunk(kind: &[u8; 4], data: &[u8]) -> Vec<u8> {
+  let mut crc = crc32fast::Hasher::new();
+  crc.update(kind);
+  crc.update(data);
+  let mut out = (data.len() as u32).to_be_bytes().to_vec();
+  out.extend_from_slice(kind);
+  out.extend_from_slice(data);
+  out.extend_from_slice(&crc.finalize().to_be_bytes());
+  out
+}
+
+fn png(width: u32, height: u32, idat: &[u8]) -> Vec<u8> {
+  let mut ihdr = width.to_be_bytes().to_vec();
+  ihdr.extend_from_slice(&height.to_be_bytes());
+  ihdr.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+  let mut out = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+  out.extend(chunk(b"IHDR", &ihdr));
+  out.extend(chunk(b"IDAT", idat));
+  out.extend(chunk(b"IEND", &[]));
+  out
+}
+
+fn chunks(bytes: &[u8]) -> Vec<(String, Vec<u8>)> {
+  let mut out = Vec::new();
+  let mut pos = 8;
+  while pos < bytes.len() {
+    let len = u32::from_be_bytes(bytes[pos..pos + 4].try_into().unwrap()) as usize;
+    let kind = String::from_utf8(bytes[pos + 4..pos + 8].to_vec()).unwrap();
+    out.push((kind, bytes[pos + 8..pos + 8 + len].to_vec()));
+    pos += 12 + len;
+  }
+  out
+}
+
+#[test]
+fn remuxes_frames_into_apng_with_timestamp_delays() {
+  let mut writer = ApngWriter::new(Cursor::new(Vec::new()), 10);
+  writer.push_frame(&png(4, 3, b"frame-a"), 1_000).unwrap();
+  writer.push_frame(&png(4, 3, b"frame-b"), 1_250).unwrap();
+  assert_eq!(writer.frame_count(), 2);
+  assert_eq!(writer.duration_ms(), 350);
+  assert_eq!(writer.finish().unwrap(), 2);
+}
+
+#[test]
+fn finished_file_has_actl_fctl_and_fdat_chunks() {
+  let mut buf = Vec::new();
+  {
+    let mut writer = ApngWriter::new(Cursor::new(&mut buf), 10);
+    writer.push_frame(&png(4, 3, b"frame-a"), 0).unwrap();
+    writer.push_frame(&png(4, 3, b"frame-b"), 250).unwrap();
+    writer.finish().unwrap();
+  }
+
+  let parsed = chunks(&buf);
+  let kinds: Vec<&str> = parsed.iter().map(|(k, _)| k.as_str()).collect();
+  assert_eq!(kinds, vec!["IHDR", "acTL", "fcTL", "IDAT", "fcTL", "fdAT", "IEND"]);
+
+  let actl = &parsed[1].1;
+  assert_eq!(u32::from_be_bytes(actl[0..4].try_into().unwrap()), 2);
+
+  let first_delay = u16::from_be_bytes(parsed[2].1[20..22].try_into().unwrap());
+  let last_delay = u16::from_be_bytes(parsed[4].1[20..22].try_into().unwrap());
+  assert_eq!(first_delay, 250);
+  assert_eq!(last_delay, 100);
+
+  // fdAT carries the next sequence number ahead of the original IDAT payload.
+  assert_eq!(&parsed[5].1[..4], &2u32.to_be_bytes());
+  assert_eq!(&parsed[5].1[4..], b"frame-b");
+}
+
+#[test]
+fn rejects_frames_with_different_geometry() {
+  let mut writer = ApngWriter::new(Cursor::new(Vec::new()), 10);
+  writer.push_frame(&png(4, 3, b"a"), 0).unwrap();
+  assert!(matches!(writer.push_frame(&png(8, 3, b"b"), 10), Err(VideoError::SizeMismatch)));
+  assert!(matches!(writer.push_frame(b"not a png", 20), Err(VideoError::NotPng)));
+  assert_eq!(writer.frame_count(), 1);
+}
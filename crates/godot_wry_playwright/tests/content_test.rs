@@ -0,0 +1,28 @@
+use godot_wry_playwright::content::{document_url, prepare, Content, MAX_CONTENT_BYTES};
+
+#[test]
+fn base_url_is_served_as_the_document_url() {
+  let html = "<script>/* <base href=x> */</script><p>hi</p>";
+  assert_eq!(
+    prepare(html, " http://127.0.0.1:8080/app/ "),
+    Ok(Content { html: html.to_string(), url: Some("http://127.0.0.1:8080/app/".to_string()) })
+  );
+  assert!(prepare("<p>", "file:///C:/app/").unwrap_err().starts_with("content_base_url_invalid"));
+  assert!(prepare("<p>", "about:blank").unwrap_err().starts_with("content_base_url_invalid"));
+}
+
+#[test]
+fn document_urls_are_normalized_like_requests() {
+  assert_eq!(document_url("HTTPS://Example.COM:443").as_deref(), Some("https://example.com/"));
+  assert_eq!(document_url("http://x.test?a=1#top").as_deref(), Some("http://x.test/?a=1"));
+  assert_eq!(document_url("http://x.test:8080/App/#f").as_deref(), Some("http://x.test:8080/App/"));
+  assert_eq!(document_url("data:text/html,hi"), None);
+}
+
+#[test]
+fn only_string_documents_are_size_limited() {
+  assert_eq!(prepare("<p>hi</p>", ""), Ok(Content { html: "<p>hi</p>".to_string(), url: None }));
+  let big = "x".repeat(MAX_CONTENT_BYTES + 1);
+  assert!(prepare(&big, "").unwrap_err().starts_with("content_too_large"));
+  assert!(prepare(&big, "https://example.com/").is_ok());
+}
@@ -0,0 +1,70 @@
+use godot_wry_playwright::cookies::{cookies_json, dedupe, Cookie, CookieFilter, CookieParam, SameSite};
+use serde_json::{json, Value};
+
+fn cookie(name: &str, domain: &str, path: &str) -> Cookie {
+  Cookie {
+    name: name.into(),
+    value: "v".into(),
+    domain: domain.into(),
+    path: path.into(),
+    expires: -1.0,
+    http_only: false,
+    secure: false,
+    same_site: SameSite::Lax,
+  }
+}
+
+#[test]
+fn url_cookies_take_domain_path_and_secure_from_the_url() {
+  let list = CookieParam::list_from_json(
+    r#"[{"name":"sid","value":"1","url":"https://user@example.com:8443/app/page?x=1","httpOnly":true},
+        {"name":"pref","value":"dark","domain":".example.com","same_site":"strict","expires":1700000000.0}]"#,
+  )
+  .unwrap();
+  let resolved: Vec<Cookie> = list.into_iter().map(|c| c.resolve().unwrap()).collect();
+
+  assert_eq!(resolved[0].domain, "example.com");
+  assert_eq!(resolved[0].path, "/app");
+  assert!(resolved[0].secure && resolved[0].http_only);
+  assert_eq!(resolved[0].expires, -1.0);
+
+  assert_eq!(resolved[1].path, "/");
+  assert_eq!(resolved[1].same_site, SameSite::Strict);
+  assert_eq!(resolved[1].expires, 1_700_000_000.0);
+  assert!(!resolved[1].secure);
+}
+
+#[test]
+fn invalid_cookie_params_are_rejected() {
+  let resolve = |json: &str| CookieParam::list_from_json(json).unwrap().remove(0).resolve();
+  assert_eq!(resolve(r#"[{"value":"x","domain":"a.com"}]"#), Err("cookie_missing_name".into()));
+  assert!(resolve(r#"[{"name":"a","value":"x"}]"#).unwrap_err().starts_with("cookie_missing_domain"));
+  assert!(resolve(r#"[{"name":"a","value":"x","url":"not a url"}]"#).is_err());
+
+  let none = resolve(r#"[{"name":"a","value":"x","domain":"a.com","sameSite":"None"}]"#).unwrap();
+  assert!(none.secure, "SameSite=None implies Secure");
+}
+
+#[test]
+fn filter_matches_ignoring_leading_dot_and_case() {
+  let c = cookie("sid", ".Example.com", "/");
+  assert!(CookieFilter::default().matches(&c));
+  assert!(CookieFilter::from_json(r#"{"domain":"example.com"}"#).unwrap().matches(&c));
+  assert!(!CookieFilter::from_json(r#"{"name":"other"}"#).unwrap().matches(&c));
+  assert!(!CookieFilter::from_json(r#"{"path":"/app"}"#).unwrap().matches(&c));
+}
+
+#[test]
+fn records_serialize_in_playwright_layout_without_duplicates() {
+  let merged = dedupe(vec![cookie("a", "x.com", "/"), cookie("b", "x.com", "/"), cookie("a", "x.com", "/")]);
+  assert_eq!(merged.len(), 2);
+
+  let json: Value = serde_json::from_str(&cookies_json(&merged[..1])).unwrap();
+  assert_eq!(
+    json,
+    json!([{
+      "name": "a", "value": "v", "domain": "x.com", "path": "/", "expires": -1.0,
+      "httpOnly": false, "secure": false, "sameSite": "Lax"
+    }])
+  );
+}
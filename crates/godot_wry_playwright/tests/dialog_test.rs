@@ -0,0 +1,39 @@
+use godot_wry_playwright::dialog::{DialogAnswer, DialogKind, DialogPolicy};
+
+#[test]
+fn policies_parse_with_dismiss_as_default() {
+  assert_eq!(DialogPolicy::default(), DialogPolicy::Dismiss);
+  assert_eq!(DialogPolicy::from_name("", ""), Ok(DialogPolicy::Dismiss));
+  assert_eq!(DialogPolicy::from_name("Manual", ""), Ok(DialogPolicy::Manual));
+  assert_eq!(
+    DialogPolicy::from_name("accept", "yes"),
+    Ok(DialogPolicy::Accept { prompt_text: "yes".into() })
+  );
+  assert!(DialogPolicy::from_name("ignore", "").unwrap_err().starts_with("dialog_policy_invalid"));
+}
+
+#[test]
+fn automatic_answers_follow_the_policy() {
+  let accept = DialogPolicy::from_name("accept", "").unwrap();
+  assert_eq!(
+    accept.answer(DialogKind::Prompt, "default"),
+    Some(DialogAnswer { accept: true, text: Some("default".into()) })
+  );
+  assert_eq!(accept.answer(DialogKind::Confirm, ""), Some(DialogAnswer { accept: true, text: None }));
+
+  let typed = DialogPolicy::from_name("accept", "typed").unwrap();
+  assert_eq!(typed.answer(DialogKind::Prompt, "default").unwrap().text.as_deref(), Some("typed"));
+
+  assert_eq!(
+    DialogPolicy::Dismiss.answer(DialogKind::BeforeUnload, ""),
+    Some(DialogAnswer { accept: false, text: None })
+  );
+  assert_eq!(DialogPolicy::Manual.answer(DialogKind::Alert, ""), None);
+}
+
+#[test]
+fn only_accepted_prompts_carry_text() {
+  assert_eq!(DialogAnswer::new(false, "x", DialogKind::Prompt, "d").text, None);
+  assert_eq!(DialogAnswer::new(true, "x", DialogKind::Alert, "").text, None);
+  assert_eq!(DialogKind::BeforeUnload.as_str(), "beforeunload");
+}
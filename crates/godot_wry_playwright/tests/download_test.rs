@@ -0,0 +1,37 @@
+use godot_wry_playwright::download::{download_dir, sanitize_filename, unique_path};
+
+#[test]
+fn suggested_names_become_single_safe_components() {
+  assert_eq!(sanitize_filename("report.pdf"), "report.pdf");
+  assert_eq!(sanitize_filename("../../etc/passwd"), "_.._etc_passwd");
+  assert_eq!(sanitize_filename("a:b*c?.txt"), "a_b_c_.txt");
+  assert_eq!(sanitize_filename("  "), "download");
+  assert_eq!(sanitize_filename(".."), "download");
+}
+
+#[test]
+fn taken_names_get_a_numbered_suffix() {
+  let dir = std::env::temp_dir().join(format!("gwry_download_test_{}", std::process::id()));
+  std::fs::create_dir_all(&dir).unwrap();
+
+  assert_eq!(unique_path(&dir, "data.csv"), dir.join("data.csv"));
+  std::fs::write(dir.join("data.csv"), b"1").unwrap();
+  assert_eq!(unique_path(&dir, "data.csv"), dir.join("data (1).csv"));
+  std::fs::write(dir.join("data (1).csv"), b"2").unwrap();
+  assert_eq!(unique_path(&dir, "data.csv"), dir.join("data (2).csv"));
+
+  std::fs::write(dir.join("README"), b"3").unwrap();
+  assert_eq!(unique_path(&dir, "README"), dir.join("README (1)"));
+
+  std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn download_dirs_stay_under_user() {
+  assert_eq!(download_dir("user://downloads/run_1"), Ok("user://downloads/run_1".to_string()));
+  assert_eq!(download_dir(" exports/./pdf/ "), Ok("user://exports/pdf".to_string()));
+  assert_eq!(download_dir(""), Err("download_dir_empty".to_string()));
+  for outside in ["res://downloads", "/tmp/downloads", "C:\\Downloads", "user://../escape", "a/../../b"] {
+    assert_eq!(download_dir(outside), Err(format!("download_dir_outside_user: {outside}")), "{outside}");
+  }
+}
@@ -0,0 +1,40 @@
+use godot_wry_playwright::init_script::{origin_regex, wrap, InitScripts};
+
+#[test]
+fn origin_globs_become_anchored_regexes() {
+  assert_eq!(origin_regex("https://example.com"), r"^https:\/\/example\.com$");
+  assert_eq!(origin_regex("https://*.example.com"), r"^https:\/\/[^/]*\.example\.com$");
+  assert_eq!(origin_regex("localhost"), r"^[^/]*:\/\/localhost(:\d+)?$");
+  assert_eq!(origin_regex("http://127.0.0.1:**"), r"^http:\/\/127\.0\.0\.1:.*$");
+}
+
+#[test]
+fn scripts_are_guarded_only_with_a_pattern() {
+  assert_eq!(wrap("window.x = 1;", ""), "window.x = 1;");
+  assert_eq!(wrap("window.x = 1;", "*"), "window.x = 1;");
+  assert_eq!(
+    wrap("window.x = 1;", "localhost"),
+    "if (new RegExp(\"^[^/]*:\\\\/\\\\/localhost(:\\\\d+)?$\").test(location.origin)) (function () {\nwindow.x = 1;\n}).call(globalThis);"
+  );
+  let strict = wrap("\"use strict\";\nconst y = 2;", "localhost");
+  assert!(strict.contains("(function () {\n\"use strict\";\nconst y = 2;\n})"));
+}
+
+#[test]
+fn registry_tracks_engine_ids_per_tab() {
+  let mut scripts = InitScripts::new();
+  assert_eq!(scripts.add("  ", ""), Err("init_script_empty".into()));
+  let first = scripts.add("window.a = 1;", "").unwrap();
+  let second = scripts.add("window.b = 2;", "example.com").unwrap();
+  assert_eq!(scripts.iter().map(|(id, _)| id).collect::<Vec<_>>(), vec![first, second]);
+
+  assert!(scripts.register(first, 1, "n1".into()));
+  assert!(scripts.register(first, 2, "n2".into()));
+  assert!(scripts.register(second, 1, "n3".into()));
+  scripts.remove_tab(2);
+  assert_eq!(scripts.remove(first), Some(vec![(1, "n1".to_string())]));
+  assert_eq!(scripts.remove(first), None);
+  // A registration finishing after removal must be undone by the caller.
+  assert!(!scripts.register(first, 3, "n4".into()));
+  assert_eq!(scripts.get(second).map(|s| s.contains("window.b = 2;")), Some(true));
+}
@@ -0,0 +1,81 @@
+use godot_wry_playwright::navigation::{url_matches, NavigationPolicy, UrlWaiters};
+
+#[test]
+fn empty_policy_allows_everything() {
+  let policy = NavigationPolicy::from_json("{}").unwrap();
+  assert!(policy.is_unrestricted());
+  assert_eq!(policy.check("http://127.0.0.1:8080/"), Ok(()));
+  assert!(NavigationPolicy::from_json(r#"{"allowed_hosts":"x"}"#).unwrap_err().starts_with("navigation_policy_invalid"));
+}
+
+#[test]
+fn host_patterns_allow_and_block() {
+  let policy = NavigationPolicy::from_json(
+    r#"{"allowed_hosts":["*.example.com","docs.rs"],"blocked_hosts":["ads.example.com"]}"#,
+  )
+  .unwrap();
+  assert_eq!(policy.check("https://example.com/"), Ok(()));
+  assert_eq!(policy.check("https://user@WWW.Example.com:443/path?q"), Ok(()));
+  assert_eq!(policy.check("https://docs.rs/serde"), Ok(()));
+  assert_eq!(policy.check("about:blank"), Ok(()));
+  assert_eq!(policy.check("https://ads.example.com/x.js"), Err("navigation_blocked: host ads.example.com".into()));
+  assert_eq!(policy.check("https://notexample.com/"), Err("navigation_blocked: host notexample.com not allowed".into()));
+  assert!(policy.check("https://sub.docs.rs/").is_err());
+}
+
+#[test]
+fn schemes_and_private_addresses_are_blocked() {
+  let policy = NavigationPolicy::from_json(r#"{"allowed_schemes":["https"],"block_private_ips":true}"#).unwrap();
+  assert_eq!(policy.check("https://93.184.216.34/"), Ok(()));
+  assert_eq!(policy.check("data:text/html,hi"), Ok(()));
+  assert_eq!(policy.check("http://example.com/"), Err("navigation_blocked: scheme http".into()));
+  assert!(policy.check("file:///C:/secret.txt").is_err());
+  for url in [
+    "https://localhost:3000/",
+    "https://10.0.0.1/",
+    "https://192.168.1.20/",
+    "https://169.254.169.254/latest",
+    "https://100.64.0.1/",
+    "https://[::1]:8080/",
+    "https://[fd00::1]/",
+    "https://[::ffff:127.0.0.1]/",
+  ] {
+    assert!(policy.check(url).unwrap_err().starts_with("navigation_blocked: private address"), "{url}");
+  }
+}
+
+#[test]
+fn url_globs_match() {
+  assert!(url_matches("", "https://example.com/"));
+  assert!(url_matches("https://example.com/", "https://example.com/"));
+  assert!(!url_matches("https://example.com/", "https://example.com/a"));
+  assert!(url_matches("**/checkout", "https://shop.test/cart/checkout"));
+  assert!(url_matches("https://shop.test/*/item?", "https://shop.test/cart/item7"));
+  assert!(!url_matches("https://shop.test/*/item", "https://shop.test/a/b/item"));
+  assert!(url_matches("**/search?q=*", "https://x.test/search?q=godot"));
+  assert!(url_matches("**#done", "https://x.test/page#done"));
+  assert!(url_matches("*?*", "aa/bbb"));
+}
+
+#[test]
+fn many_wildcards_do_not_backtrack() {
+  let url = format!("https://x.test/{}", "a".repeat(5000));
+  assert!(!url_matches("**a*a*a*a*a*a*a*a*a*a*a*a*b", &url));
+  assert!(url_matches("**a*a*a*a*a*a*a*a*a*a*a*a", &url));
+}
+
+#[test]
+fn url_waiters_resolve_per_tab() {
+  let mut waiters = UrlWaiters::new();
+  waiters.add(1, 1, Some("**/done".into()));
+  waiters.add(2, 1, None);
+  waiters.add(3, 2, None);
+  assert_eq!(waiters.resolve(1, "https://x.test/step1"), vec![2]);
+  assert_eq!(waiters.resolve(1, "https://x.test/done"), vec![1]);
+  assert!(waiters.resolve(1, "https://x.test/done").is_empty());
+  assert!(!waiters.remove(1));
+  waiters.add(4, 2, Some("**/never".into()));
+  assert!(waiters.remove(4));
+  assert_eq!(waiters.remove_tab(2), vec![3]);
+  assert!(waiters.resolve(2, "https://x.test/").is_empty());
+}
@@ -0,0 +1,112 @@
+use godot_wry_playwright::network::{apply_response_body, NetworkLog, NetworkRequest, NetworkTracker};
+use serde_json::json;
+
+fn send(tracker: &mut NetworkTracker, method: &str, params: serde_json::Value) -> Option<NetworkRequest> {
+  tracker.on_cdp_event(method, &params.to_string())
+}
+
+#[test]
+fn tracker_folds_request_lifecycle_into_record() {
+  let mut tracker = NetworkTracker::new();
+  assert!(send(
+    &mut tracker,
+    "Network.requestWillBeSent",
+    json!({
+      "requestId": "r1",
+      "timestamp": 10.0,
+      "wallTime": 1700000000.5,
+      "type": "XHR",
+      "request": {"method": "POST", "url": "https://example.com/api", "postData": "{\"a\":1}"}
+    }),
+  )
+  .is_none());
+  assert!(send(
+    &mut tracker,
+    "Network.responseReceived",
+    json!({
+      "requestId": "r1",
+      "timestamp": 10.25,
+      "type": "XHR",
+      "response": {"status": 201, "statusText": "Created", "mimeType": "application/json"}
+    }),
+  )
+  .is_none());
+
+  let entry = send(
+    &mut tracker,
+    "Network.loadingFinished",
+    json!({"requestId": "r1", "timestamp": 10.5, "encodedDataLength": 321}),
+  )
+  .expect("finished record");
+
+  assert_eq!(entry.method, "POST");
+  assert_eq!(entry.url, "https://example.com/api");
+  assert_eq!(entry.resource_type, "xhr");
+  assert_eq!(entry.status, 201);
+  assert_eq!(entry.mime_type, "application/json");
+  assert_eq!(entry.started_at_ms, 1700000000500.0);
+  assert_eq!(entry.response_ms, Some(250.0));
+  assert_eq!(entry.duration_ms, 500.0);
+  assert_eq!(entry.request_body_size, 7);
+  assert_eq!(entry.encoded_data_length, 321);
+  assert!(!entry.failed);
+}
+
+#[test]
+fn tracker_reports_failures_and_redirect_hops() {
+  let mut tracker = NetworkTracker::new();
+  send(
+    &mut tracker,
+    "Network.requestWillBeSent",
+    json!({"requestId": "r2", "timestamp": 1.0, "type": "Document", "request": {"method": "GET", "url": "http://a.test/"}}),
+  );
+  let hop = send(
+    &mut tracker,
+    "Network.requestWillBeSent",
+    json!({
+      "requestId": "r2",
+      "timestamp": 1.1,
+      "type": "Document",
+      "request": {"method": "GET", "url": "https://a.test/"},
+      "redirectResponse": {"status": 301, "statusText": "Moved Permanently"}
+    }),
+  )
+  .expect("redirect hop");
+  assert_eq!(hop.url, "http://a.test/");
+  assert_eq!(hop.status, 301);
+
+  let failed = send(
+    &mut tracker,
+    "Network.loadingFailed",
+    json!({"requestId": "r2", "timestamp": 2.0, "errorText": "net::ERR_CONNECTION_REFUSED"}),
+  )
+  .expect("failed record");
+  assert_eq!(failed.url, "https://a.test/");
+  assert!(failed.failed);
+  assert_eq!(failed.error_text.as_deref(), Some("net::ERR_CONNECTION_REFUSED"));
+}
+
+#[test]
+fn response_body_is_truncated_at_cap() {
+  let mut entry = NetworkRequest::default();
+  apply_response_body(&mut entry, r#"{"body":"hello world","base64Encoded":false}"#, 5);
+  assert_eq!(entry.body.as_deref(), Some("hello"));
+  assert!(entry.body_truncated);
+  assert!(!entry.body_base64);
+}
+
+#[test]
+fn log_drops_oldest_entries_past_capacity() {
+  let mut log = NetworkLog::with_capacity(2);
+  for id in ["a", "b", "c"] {
+    log.push(NetworkRequest {
+      id: id.to_string(),
+      ..Default::default()
+    });
+  }
+  assert_eq!(log.len(), 2);
+
+  let parsed: serde_json::Value = serde_json::from_str(&log.to_json()).expect("json");
+  let ids: Vec<&str> = parsed.as_array().unwrap().iter().map(|e| e["id"].as_str().unwrap()).collect();
+  assert_eq!(ids, vec!["b", "c"]);
+}
@@ -0,0 +1,45 @@
+use godot_wry_playwright::pdf::{parse_length, result_json, PdfOptions};
+use serde_json::{json, Value};
+
+#[test]
+fn lengths_accept_inches_and_css_units() {
+  assert_eq!(parse_length(&json!(1.5)), Ok(1.5));
+  assert_eq!(parse_length(&json!("96px")), Ok(1.0));
+  assert_eq!(parse_length(&json!("2.54cm")), Ok(1.0));
+  assert_eq!(parse_length(&json!(" 25.4 mm ")), Ok(1.0));
+  assert_eq!(parse_length(&json!("0.5in")), Ok(0.5));
+  assert!(parse_length(&json!("3pt")).is_err());
+  assert!(parse_length(&json!(-1)).is_err());
+}
+
+#[test]
+fn settings_resolve_paper_margins_and_flags() {
+  let options = PdfOptions::from_json(
+    r#"{"format":"A4","landscape":true,"scale":0.8,"print_background":true,
+        "margin":{"top":"1cm","bottom":0.25},"timeout_ms":5000.0}"#,
+  )
+  .expect("options");
+  let settings = options.settings().expect("settings");
+  assert_eq!((settings.page_width, settings.page_height), (8.27, 11.7));
+  assert!((settings.margin_top - 1.0 / 2.54).abs() < 1e-9);
+  assert_eq!(settings.margin_bottom, 0.25);
+  assert_eq!(settings.margin_left, 0.0);
+  assert!(settings.landscape && settings.print_background);
+  assert_eq!(settings.scale, 0.8);
+  assert_eq!(options.timeout_ms(30_000), 5_000);
+
+  let custom = PdfOptions::from_json(r#"{"width":"800px","height":4}"#).unwrap().settings().unwrap();
+  assert_eq!((custom.page_width, custom.page_height), (800.0 / 96.0, 4.0));
+
+  let default = PdfOptions::default().settings().unwrap();
+  assert_eq!((default.page_width, default.page_height, default.scale), (8.5, 11.0, 1.0));
+
+  assert!(PdfOptions::from_json(r#"{"format":"b5"}"#).unwrap().settings().is_err());
+  assert!(PdfOptions::from_json(r#"{"scale":3.0}"#).unwrap().settings().is_err());
+}
+
+#[test]
+fn result_reports_base64_for_in_memory_documents() {
+  let result: Value = serde_json::from_str(&result_json(None, Some(b"%PDF"))).unwrap();
+  assert_eq!(result, json!({"format": "pdf", "size": 4, "base64": "JVBERg=="}));
+}
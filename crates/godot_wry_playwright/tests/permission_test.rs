@@ -0,0 +1,31 @@
+use godot_wry_playwright::permission::{request_matches, PermissionDecision, PermissionKind, PermissionPolicy};
+
+#[test]
+fn default_policy_leaves_requests_to_the_engine() {
+  let policy = PermissionPolicy::from_json("{}").unwrap();
+  assert_eq!(policy.decide(PermissionKind::Camera), PermissionDecision::Default);
+  assert_eq!(PermissionKind::from_name("Clipboard-Read"), Some(PermissionKind::ClipboardRead));
+  assert_eq!(PermissionKind::from_name("telepathy"), None);
+}
+
+#[test]
+fn overrides_win_over_the_default() {
+  let policy =
+    PermissionPolicy::from_json(r#"{"default":"deny","camera":"grant","geolocation":"ask","microphone":""}"#).unwrap();
+  assert_eq!(policy.decide(PermissionKind::Camera), PermissionDecision::Allow);
+  assert_eq!(policy.decide(PermissionKind::Geolocation), PermissionDecision::Ask);
+  assert_eq!(policy.decide(PermissionKind::Microphone), PermissionDecision::Default);
+  assert_eq!(policy.decide(PermissionKind::Notifications), PermissionDecision::Deny);
+
+  assert!(PermissionPolicy::from_json(r#"{"camera":"maybe"}"#).unwrap_err().starts_with("permission_policy_invalid"));
+  assert!(PermissionPolicy::from_json(r#"{"telepathy":"allow"}"#).unwrap_err().starts_with("permission_kind_invalid"));
+}
+
+#[test]
+fn respond_filters_match_origin_and_kind() {
+  let origin = "https://meet.example.com";
+  assert!(request_matches(origin, PermissionKind::Camera, "", ""));
+  assert!(request_matches(origin, PermissionKind::Camera, "https://meet.example.com/", "camera"));
+  assert!(!request_matches(origin, PermissionKind::Camera, "", "microphone"));
+  assert!(!request_matches(origin, PermissionKind::Camera, "https://example.com", ""));
+}
@@ -0,0 +1,77 @@
+use godot_wry_playwright::profile::{delete_data_dir, ProfileConfig, ProfileSettings};
+
+fn globalize(path: &str) -> String {
+  path.replace("user://", "/home/player/.local/share/game/")
+}
+
+#[test]
+fn named_profiles_get_their_own_data_directory() {
+  let config = ProfileConfig::from_json(r#"{"name":"npc_merchant","incognito":false}"#).unwrap();
+  assert_eq!(
+    config.resolve(globalize),
+    Ok(ProfileSettings {
+      data_dir: Some("/home/player/.local/share/game/wry_profiles/npc_merchant".into()),
+      incognito: false,
+    })
+  );
+
+  let explicit = ProfileConfig::from_json(r#"{"name":"ignored","data_dir":"slots/1"}"#).unwrap();
+  assert_eq!(explicit.data_dir_path(), Ok(Some("user://wry_profiles/slots/1".into())));
+  let prefixed = ProfileConfig::from_json(r#"{"data_dir":"user://wry_profiles/slots//2/"}"#).unwrap();
+  assert_eq!(prefixed.data_dir_path(), Ok(Some("user://wry_profiles/slots/2".into())));
+
+  let default = ProfileConfig::from_json(r#"{"incognito":true}"#).unwrap();
+  assert_eq!(
+    default.resolve(globalize),
+    Ok(ProfileSettings {
+      data_dir: None,
+      incognito: true,
+    })
+  );
+}
+
+#[test]
+fn profile_names_cannot_escape_the_profiles_directory() {
+  for name in ["../save", ".hidden", "a/b", "slot 1", ""] {
+    let config = ProfileConfig {
+      name: Some(name.into()),
+      ..Default::default()
+    };
+    if name.is_empty() {
+      assert_eq!(config.data_dir_path(), Ok(None));
+    } else {
+      assert!(config.data_dir_path().is_err(), "{name} should be rejected");
+    }
+  }
+}
+
+#[test]
+fn data_dirs_cannot_leave_the_profiles_directory() {
+  for dir in ["C:/", "/etc", "res://", "user://saves", "user://wry_profiles/../x", "a/../../b", "a\\b", "./a"] {
+    let config = ProfileConfig {
+      data_dir: Some(dir.into()),
+      ..Default::default()
+    };
+    assert!(config.data_dir_path().unwrap_err().starts_with("profile_invalid_data_dir"), "{dir} should be rejected");
+  }
+}
+
+#[test]
+fn deleting_profile_data_reports_whether_it_existed() {
+  let root = std::env::temp_dir().join(format!("gwry_profile_test_{}", std::process::id()));
+  let dir = root.join("slot");
+  std::fs::create_dir_all(dir.join("Default")).unwrap();
+  std::fs::write(dir.join("Default/Cookies"), b"x").unwrap();
+
+  let root_path = root.to_string_lossy().to_string();
+  let path = dir.to_string_lossy().to_string();
+  assert_eq!(delete_data_dir(&path, &root_path), Ok(true));
+  assert!(!dir.exists());
+  assert_eq!(delete_data_dir(&path, &root_path), Ok(false));
+
+  assert!(delete_data_dir(&root_path, &root_path).unwrap_err().starts_with("profile_outside_root"));
+  let outside = std::env::temp_dir().to_string_lossy().to_string();
+  assert!(delete_data_dir(&outside, &root_path).unwrap_err().starts_with("profile_outside_root"));
+  assert!(root.exists());
+  std::fs::remove_dir_all(&root).unwrap();
+}
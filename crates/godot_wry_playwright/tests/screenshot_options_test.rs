@@ -0,0 +1,105 @@
+use godot_wry_playwright::screenshot::{
+  decode_capture, full_page_metrics, parse_content_size, parse_element_rect, Clip, ImageFormat, ScreenshotOptions,
+  MAX_FULL_PAGE_PIXELS,
+};
+use serde_json::json;
+
+#[test]
+fn options_parse_godot_json_with_float_numbers() {
+  let opts = ScreenshotOptions::from_json(r#"{"format":"jpg","quality":55.0,"full_page":true,"timeout_ms":2500.0}"#)
+    .expect("options");
+  assert_eq!(opts.format, ImageFormat::Jpeg);
+  assert_eq!(opts.quality, Some(55.0));
+  assert!(opts.full_page);
+  assert_eq!(opts.timeout_ms(10_000), 2_500);
+  assert!(ScreenshotOptions::from_json(r#"{"format":"gif"}"#).is_err());
+}
+
+#[test]
+fn capture_params_prefer_element_then_clip_then_full_page() {
+  let page = Clip {
+    x: 0.0,
+    y: 0.0,
+    width: 800.0,
+    height: 4000.0,
+  };
+  let element = Clip {
+    x: 10.0,
+    y: 20.0,
+    width: 30.0,
+    height: 40.0,
+  };
+
+  let full = ScreenshotOptions {
+    full_page: true,
+    ..Default::default()
+  };
+  let params = full.capture_params(None, Some(page));
+  assert_eq!(params["format"], json!("png"));
+  assert_eq!(params["clip"]["height"], json!(4000.0));
+  assert_eq!(params["captureBeyondViewport"], json!(true));
+  assert!(params.get("quality").is_none());
+
+  let params = full.capture_params(Some(element), Some(page));
+  assert_eq!(params["clip"]["x"], json!(10.0));
+
+  let jpeg = ScreenshotOptions {
+    format: ImageFormat::Jpeg,
+    ..Default::default()
+  };
+  let params = jpeg.capture_params(None, None);
+  assert_eq!(params["quality"], json!(80));
+  assert!(params.get("clip").is_none());
+}
+
+#[test]
+fn element_rect_and_layout_results_are_parsed() {
+  let opts = ScreenshotOptions {
+    selector: Some("#card \"x\"".into()),
+    ..Default::default()
+  };
+  let expr = opts.element_rect_expression().expect("expression");
+  assert!(expr.contains(r##"g.element("#card \"x\"")"##));
+  let by_handle = ScreenshotOptions {
+    selector: Some("handle=7".into()),
+    ..Default::default()
+  };
+  assert!(by_handle.element_rect_expression().expect("expression").contains(r#"g.element({"handle":7})"#));
+
+  let rect = parse_element_rect(r#"{"result":{"type":"object","value":{"x":1,"y":2,"width":3,"height":4}}}"#)
+    .expect("rect");
+  assert_eq!(rect.width, 3.0);
+  assert_eq!(
+    parse_element_rect(r#"{"result":{"type":"object","subtype":"null","value":null}}"#),
+    Err("not_found".to_string())
+  );
+
+  let stale = r#"{"result":{"type":"object"},"exceptionDetails":{"text":"Uncaught","exception":{"description":"Error: stale_element\n    at element (<anonymous>:1:1)"}}}"#;
+  assert_eq!(parse_element_rect(stale), Err("stale_element".to_string()));
+  let syntax = r#"{"exceptionDetails":{"text":"Uncaught","exception":{"description":"SyntaxError: '##' is not a valid selector"}}}"#;
+  assert!(parse_element_rect(syntax).unwrap_err().starts_with("screenshot_selector_error: SyntaxError"));
+
+  let size = parse_content_size(r#"{"cssContentSize":{"x":0,"y":0,"width":1280,"height":5000}}"#).expect("size");
+  assert_eq!(size.height, 5000.0);
+
+  assert_eq!(decode_capture(r#"{"data":"aGk="}"#), Ok(b"hi".to_vec()));
+}
+
+#[test]
+fn full_page_lays_out_at_content_size_within_the_surface_limit() {
+  let page = Clip {
+    x: 0.0,
+    y: 0.0,
+    width: 1279.5,
+    height: 4000.2,
+  };
+  assert_eq!(
+    full_page_metrics(page),
+    Ok(json!({ "width": 1280, "height": 4001, "deviceScaleFactor": 0, "mobile": false }))
+  );
+  let huge = Clip {
+    height: MAX_FULL_PAGE_PIXELS + 1.0,
+    ..page
+  };
+  assert!(full_page_metrics(huge).unwrap_err().starts_with("screenshot_full_page_too_large"));
+}
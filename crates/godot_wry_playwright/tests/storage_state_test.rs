@@ -0,0 +1,89 @@
+use godot_wry_playwright::cookies::{Cookie, SameSite};
+use godot_wry_playwright::storage_state::{
+  origin_of, parse_local_storage, read_local_storage_expression, storage_origins, OriginState, StorageEntry,
+  StorageState, SENTINEL_PATH,
+};
+
+fn sample() -> StorageState {
+  StorageState::new(
+    vec![Cookie {
+      name: "sid".into(),
+      value: "abc".into(),
+      domain: "example.com".into(),
+      path: "/".into(),
+      expires: -1.0,
+      http_only: true,
+      secure: true,
+      same_site: SameSite::Lax,
+    }],
+    vec![OriginState {
+      origin: "https://example.com".into(),
+      local_storage: vec![StorageEntry {
+        name: "theme".into(),
+        value: "dark".into(),
+      }],
+    }],
+  )
+}
+
+#[test]
+fn state_round_trips_through_a_versioned_file() {
+  let path = std::env::temp_dir().join(format!("gwry_state_{}/state.json", std::process::id()));
+  let state = sample();
+  state.save(&path).unwrap();
+
+  let text = std::fs::read_to_string(&path).unwrap();
+  assert!(text.contains("\"version\": 1") && text.contains("\"localStorage\"") && text.contains("\"httpOnly\""));
+  assert_eq!(StorageState::load(&path), Ok(state));
+  let _ = std::fs::remove_dir_all(path.parent().unwrap());
+}
+
+#[test]
+fn unknown_or_missing_versions_are_rejected() {
+  assert_eq!(
+    StorageState::from_json(r#"{"cookies":[],"origins":[]}"#),
+    Err("storage_state_missing_version".into())
+  );
+  assert_eq!(
+    StorageState::from_json(r#"{"version":2,"cookies":[],"origins":[]}"#),
+    Err("storage_state_unsupported_version: 2".into())
+  );
+}
+
+#[test]
+fn seed_script_targets_origins_with_entries_only() {
+  let mut state = sample();
+  let js = state.seed_script().expect("script");
+  assert!(js.contains(r#"{"https://example.com":[["theme","dark"]]}[location.origin]"#));
+  assert!(js.contains("sendEvent(\"storage_seeded\""));
+
+  state.origins[0].local_storage.clear();
+  assert_eq!(state.seed_script(), None);
+}
+
+#[test]
+fn origins_are_normalized() {
+  assert_eq!(origin_of("HTTPS://Example.com:443/a?b"), Some("https://example.com".into()));
+  assert_eq!(origin_of("http://user:pw@localhost:8080"), Some("http://localhost:8080".into()));
+  assert_eq!(origin_of("about:blank"), None);
+  assert_eq!(origin_of("file:///tmp/x.html"), None);
+}
+
+#[test]
+fn storage_origins_put_the_current_page_first() {
+  let origins = vec!["https://b.test/x".to_string(), "https://a.test".to_string(), "https://B.test:443/".to_string()];
+  assert_eq!(storage_origins(&origins, "https://a.test/page"), vec!["https://a.test", "https://b.test"]);
+  assert_eq!(storage_origins(&[], "http://localhost:8080/"), vec!["http://localhost:8080"]);
+  assert!(storage_origins(&[], "about:blank").is_empty());
+}
+
+#[test]
+fn local_storage_results_are_parsed() {
+  assert!(read_local_storage_expression(true).contains(SENTINEL_PATH));
+  assert!(!read_local_storage_expression(false).contains(SENTINEL_PATH));
+
+  let entries = parse_local_storage(r#"{"result":{"type":"object","value":[["a","1"],["b","2"]]}}"#).unwrap();
+  assert_eq!(entries.map(|e| e.len()), Some(2));
+  assert_eq!(parse_local_storage(r#"{"result":{"type":"object","value":null}}"#), Ok(None));
+  assert!(parse_local_storage(r#"{"exceptionDetails":{"text":"boom"}}"#).is_err());
+}
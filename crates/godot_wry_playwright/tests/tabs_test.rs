@@ -0,0 +1,69 @@
+use godot_wry_playwright::tabs::{NewWindowPolicy, TabInfo, TabSet};
+use serde_json::json;
+
+#[test]
+fn first_tab_is_active_and_ids_are_not_reused() {
+  let mut tabs: TabSet<&str> = TabSet::new();
+  assert!(tabs.active().is_none());
+
+  let a = tabs.open("a");
+  let b = tabs.open("b");
+  assert_eq!((a, b), (1, 2));
+  assert_eq!(tabs.active_entry(), Some((a, &"a")));
+
+  assert_eq!(tabs.close(b), Some("b"));
+  let c = tabs.try_open(|id| Ok::<_, ()>(if id == 3 { "c" } else { "?" })).unwrap();
+  assert_eq!(c, 3);
+  assert_eq!(tabs.get(c), Some(&"c"));
+
+  assert_eq!(tabs.try_open(|_| Err::<&str, _>("build failed")), Err("build failed"));
+  assert_eq!(tabs.len(), 2);
+}
+
+#[test]
+fn closing_the_active_tab_activates_its_neighbour() {
+  let mut tabs: TabSet<()> = TabSet::new();
+  let ids: Vec<i64> = (0..3).map(|_| tabs.open(())).collect();
+
+  assert!(tabs.select(ids[1]));
+  tabs.close(ids[1]);
+  assert_eq!(tabs.active_id(), Some(ids[2]), "right neighbour");
+  tabs.close(ids[2]);
+  assert_eq!(tabs.active_id(), Some(ids[0]), "last remaining");
+
+  tabs.close(ids[0]);
+  assert!(tabs.is_empty());
+  assert_eq!(tabs.active_id(), None);
+  assert_eq!(tabs.close(ids[0]), None);
+  assert!(!tabs.select(ids[0]));
+}
+
+#[test]
+fn commands_target_a_tab_by_id_or_the_active_one() {
+  let mut tabs: TabSet<&str> = TabSet::new();
+  assert_eq!(tabs.target(None), Err("webview_not_started".to_string()));
+
+  let a = tabs.open("a");
+  let b = tabs.open("b");
+  assert_eq!(tabs.target(None), Ok((a, &"a")));
+  assert_eq!(tabs.target(Some(b)), Ok((b, &"b")));
+  assert_eq!(tabs.target(Some(9)), Err("tab_not_found: 9".to_string()));
+}
+
+#[test]
+fn tab_info_serializes_for_tab_list() {
+  let info = TabInfo { id: 2, url: "https://example.com/".into(), title: "Example".into(), active: true };
+  assert_eq!(
+    serde_json::to_value(&info).unwrap(),
+    json!({ "id": 2, "url": "https://example.com/", "title": "Example", "active": true })
+  );
+}
+
+#[test]
+fn new_window_policy_names() {
+  assert_eq!(NewWindowPolicy::default(), NewWindowPolicy::NewTab);
+  assert_eq!(NewWindowPolicy::from_name("same-view"), Ok(NewWindowPolicy::SameView));
+  assert_eq!(NewWindowPolicy::from_name(" DENY "), Ok(NewWindowPolicy::Deny));
+  assert_eq!(NewWindowPolicy::from_name("signal"), Ok(NewWindowPolicy::Signal));
+  assert!(NewWindowPolicy::from_name("popup").unwrap_err().starts_with("new_window_policy_invalid"));
+}
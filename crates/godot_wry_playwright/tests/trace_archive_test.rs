@@ -0,0 +1,73 @@
+use std::io::{Cursor, Read};
+
+use godot_wry_playwright::trace::{TraceRecorder, MAX_TRACED_RESULT_BYTES, TRACE_EVENTS_FILE};
+use godot_wry_playwright_core::protocol::Command;
+use serde_json::{json, Value};
+
+fn read_events<R: Read + std::io::Seek>(archive: &mut zip::ZipArchive<R>) -> Vec<Value> {
+  let mut lines = String::new();
+  archive
+    .by_name(TRACE_EVENTS_FILE)
+    .expect("events file")
+    .read_to_string(&mut lines)
+    .unwrap();
+  lines.lines().map(|l| serde_json::from_str(l).unwrap()).collect()
+}
+
+#[test]
+fn archive_contains_event_lines_and_frames() {
+  let mut trace = TraceRecorder::new(1_000, Cursor::new(Vec::new()));
+  trace.navigation_started(1_010, 1, "https://example.com/");
+  trace.command(1_020, 2, &Command::Click { selector: "#go".into() });
+  trace.console(1_025, &json!({"level": "log", "text": "clicked"}));
+  trace.response(1_050, 2, true, "true", "");
+  let frame_file = trace.frame(1_060, 2, &[0x89, b'P', b'N', b'G']).expect("frame");
+
+  let cursor = trace.finish().expect("write archive");
+  let mut archive = zip::ZipArchive::new(Cursor::new(cursor.into_inner())).expect("read archive");
+
+  let events = read_events(&mut archive);
+  let types: Vec<&str> = events.iter().map(|e| e["type"].as_str().unwrap()).collect();
+  assert_eq!(types, vec!["trace_start", "navigation", "command", "console", "response", "frame"]);
+  assert_eq!(events[2]["command"], json!({"cmd": "click", "selector": "#go"}));
+  assert_eq!(events[4]["duration_ms"], json!(30));
+  assert_eq!(events[4]["t_ms"], json!(50));
+  assert_eq!(events[4]["result"], json!(true));
+
+  let mut png = Vec::new();
+  archive.by_name(&frame_file).expect("frame").read_to_end(&mut png).unwrap();
+  assert_eq!(png, vec![0x89, b'P', b'N', b'G']);
+}
+
+#[test]
+fn native_actions_and_large_results_are_recorded_compactly() {
+  let mut trace = TraceRecorder::new(0, Cursor::new(Vec::new()));
+  trace.action(5, 7, "screenshot", json!({ "full_page": true }));
+  let big = format!("\"{}\"", "A".repeat(MAX_TRACED_RESULT_BYTES));
+  trace.response(9, 7, true, &big, "");
+  trace.action(10, 8, "tab_select", json!({ "tab": 3 }));
+  trace.response(12, 8, false, "null", "tab_not_found: 3");
+
+  let cursor = trace.finish().expect("write archive");
+  let mut archive = zip::ZipArchive::new(Cursor::new(cursor.into_inner())).expect("read archive");
+  let events = read_events(&mut archive);
+  assert_eq!(events[1]["action"], json!("screenshot"));
+  assert_eq!(events[1]["params"], json!({ "full_page": true }));
+  assert_eq!(events[2]["result_bytes"], json!(big.len()));
+  assert_eq!(events[2]["duration_ms"], json!(4));
+  assert!(events[2].get("result").is_none());
+  assert_eq!(events[4]["error"], json!("tab_not_found: 3"));
+}
+
+#[test]
+fn saved_trace_moves_the_temp_archive() {
+  let dir = std::env::temp_dir().join(format!("gwry-trace-test-{}", std::process::id()));
+  let path = dir.join("out/trace.zip");
+  let mut trace = TraceRecorder::start(0).expect("start");
+  trace.frame(1, 1, b"png").expect("frame");
+  trace.save(&path).expect("save");
+
+  let mut archive = zip::ZipArchive::new(std::fs::File::open(&path).unwrap()).expect("read archive");
+  assert_eq!(read_events(&mut archive).len(), 2);
+  std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,32 @@
+use std::path::Path;
+
+use godot_wry_playwright::upload::{mime_type, read_input_files};
+
+#[test]
+fn mime_type_is_detected_from_the_extension() {
+  assert_eq!(mime_type(Path::new("avatar.PNG")), "image/png");
+  assert_eq!(mime_type(Path::new("dir/report.pdf")), "application/pdf");
+  assert_eq!(mime_type(Path::new("notes.txt")), "text/plain");
+  assert_eq!(mime_type(Path::new("save.bin")), "application/octet-stream");
+  assert_eq!(mime_type(Path::new("no_extension")), "application/octet-stream");
+}
+
+#[test]
+fn files_are_read_as_base64_with_name_and_type() {
+  let dir = std::env::temp_dir().join(format!("gwry_upload_test_{}", std::process::id()));
+  std::fs::create_dir_all(&dir).unwrap();
+  let path = dir.join("hello.txt");
+  std::fs::write(&path, b"hello").unwrap();
+
+  let files = read_input_files(&[path.to_string_lossy().into_owned()]).unwrap();
+  assert_eq!(files.len(), 1);
+  assert_eq!(files[0].name, "hello.txt");
+  assert_eq!(files[0].mime_type, "text/plain");
+  assert_eq!(files[0].base64, "aGVsbG8=");
+
+  let missing = dir.join("missing.png").to_string_lossy().into_owned();
+  assert!(read_input_files(&[missing]).unwrap_err().starts_with("input_file_not_found"));
+  assert!(read_input_files(&[]).unwrap().is_empty());
+
+  std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -12,15 +12,236 @@ pub struct IpcEnvelope {
   pub error: Option<String>,
 }
 
+/// Unsolicited message posted by the shim (console output, page events), not tied to a request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IpcEvent {
+  pub event: String,
+  #[serde(default)]
+  pub data: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum IpcMessage {
+  Event(IpcEvent),
+  Response(IpcEnvelope),
+}
+
+/// A file for `set_input_files`, read by the host and passed inline as base64.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InputFile {
+  pub name: String,
+  pub mime_type: String,
+  pub base64: String,
+}
+
+/// An element registered by [`Command::Query`]; it stays valid until disposed or detached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ElementHandle {
+  pub handle: u64,
+}
+
+/// The element a command acts on: a selector (CSS or `aria-ref=<ref>`) or an element handle.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Target {
+  Selector(String),
+  Handle(ElementHandle),
+}
+
+impl Target {
+  /// Reads `handle=<id>` as a handle and anything else as a selector.
+  pub fn from_selector(selector: &str) -> Self {
+    match selector.trim().strip_prefix("handle=").and_then(|id| id.trim().parse().ok()) {
+      Some(handle) => Self::Handle(ElementHandle { handle }),
+      None => Self::Selector(selector.to_string()),
+    }
+  }
+}
+
+impl From<&str> for Target {
+  fn from(selector: &str) -> Self {
+    Self::Selector(selector.to_string())
+  }
+}
+
+impl From<String> for Target {
+  fn from(selector: String) -> Self {
+    Self::Selector(selector)
+  }
+}
+
+impl From<ElementHandle> for Target {
+  fn from(handle: ElementHandle) -> Self {
+    Self::Handle(handle)
+  }
+}
+
+/// Picks `<option>`s for [`Command::SelectOption`]: a string matches value or label, a number
+/// matches the index, and an object matches when all given fields do.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum OptionMatch {
+  ValueOrLabel(String),
+  Index(u64),
+  Fields {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    value: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    index: Option<u64>,
+  },
+}
+
+/// When [`Command::WaitForFunction`] re-checks its predicate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Polling {
+  /// Every animation frame (every 100 ms while the page is hidden and frames are paused).
+  #[default]
+  Raf,
+  /// On every DOM mutation.
+  Mutation,
+  /// Every `n` milliseconds.
+  Interval(u64),
+}
+
+impl Polling {
+  /// Parses `raf`, `mutation` or an interval in milliseconds.
+  pub fn from_name(name: &str) -> Result<Self, String> {
+    match name.trim().to_ascii_lowercase().as_str() {
+      "raf" | "" => Ok(Self::Raf),
+      "mutation" => Ok(Self::Mutation),
+      other => match other.parse::<u64>() {
+        Ok(ms) if ms > 0 => Ok(Self::Interval(ms)),
+        _ => Err(format!("polling_invalid: {other}")),
+      },
+    }
+  }
+}
+
+/// The condition [`Command::WaitForSelector`] waits for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SelectorState {
+  /// Present in the DOM.
+  #[default]
+  Attached,
+  /// Absent from the DOM.
+  Detached,
+  /// Present with a non-empty, visible box.
+  Visible,
+  /// Absent or not visible.
+  Hidden,
+}
+
+impl SelectorState {
+  pub fn from_name(name: &str) -> Result<Self, String> {
+    match name.trim().to_ascii_lowercase().as_str() {
+      "attached" | "" => Ok(Self::Attached),
+      "detached" => Ok(Self::Detached),
+      "visible" => Ok(Self::Visible),
+      "hidden" => Ok(Self::Hidden),
+      other => Err(format!("selector_state_invalid: {other}")),
+    }
+  }
+}
+
+/// Output of [`Command::Snapshot`]: an indented YAML outline or the node tree as JSON.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotFormat {
+  #[default]
+  Yaml,
+  Json,
+}
+
+impl SnapshotFormat {
+  pub fn from_name(name: &str) -> Result<Self, String> {
+    match name.trim().to_ascii_lowercase().as_str() {
+      "yaml" | "" => Ok(Self::Yaml),
+      "json" => Ok(Self::Json),
+      other => Err(format!("snapshot_format_invalid: {other}")),
+    }
+  }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "cmd", rename_all = "snake_case")]
 pub enum Command {
   Eval { js: String },
-  Click { selector: String },
-  Fill { selector: String, text: String },
-  Text { selector: String },
-  Attr { selector: String, name: String },
-  WaitForSelector { selector: String, timeout_ms: u64 },
+  Click { selector: Target },
+  Fill { selector: Target, text: String },
+  Text { selector: Target },
+  Attr { selector: Target, name: String },
+  /// Text content and the requested attributes of every match, up to `limit` (0 = all).
+  /// Resolves to `[{ text, attrs: { name: value } }]`.
+  QueryAll {
+    selector: String,
+    #[serde(default)]
+    attrs: Vec<String>,
+    #[serde(default)]
+    limit: u64,
+  },
+  Count { selector: String },
+  InnerHtml { selector: Target },
+  OuterHtml { selector: Target },
+  /// Value of an `input`, `textarea` or `select`.
+  InputValue { selector: Target },
+  /// False when nothing matches, instead of failing with `not_found`.
+  IsVisible { selector: Target },
+  IsEnabled { selector: Target },
+  IsChecked { selector: Target },
+  /// Viewport rect `{ x, y, width, height }`, or null when the element is not visible.
+  BoundingBox { selector: Target },
+  /// Selects the matching options of a `<select>` (only the first for single selects; an empty
+  /// list clears it). Resolves to the selected values.
+  SelectOption { selector: Target, options: Vec<OptionMatch> },
+  /// Clicks a checkbox or radio when its state differs from `checked`.
+  SetChecked { selector: Target, checked: bool },
+  Focus { selector: Target },
+  Blur { selector: Target },
+  /// Empties an input, textarea or contenteditable element.
+  Clear { selector: Target },
+  /// Submits the element's form (or the form itself) through `requestSubmit`, running validation.
+  Submit { selector: Target },
+  /// Registers the first element matching `selector`; resolves to its handle id, or null.
+  Query { selector: String },
+  /// Frees the given handles, or every handle when empty; resolves to the number freed.
+  DisposeHandles { handles: Vec<u64> },
+  WaitForSelector {
+    selector: String,
+    timeout_ms: u64,
+    #[serde(default)]
+    state: SelectorState,
+  },
+  /// Re-evaluates `js` (a function or an expression) until it returns a truthy value, which the
+  /// command resolves with.
+  WaitForFunction {
+    js: String,
+    #[serde(default)]
+    polling: Polling,
+    timeout_ms: u64,
+  },
+  /// Replaces the selection of an `input[type=file]`; an empty list clears it.
+  SetInputFiles { selector: String, files: Vec<InputFile> },
+  /// Accessibility tree of the page, or of the element matching `root`. Every node carries a
+  /// ref that stays stable across snapshots and is accepted by any selector as `aria-ref=<ref>`.
+  Snapshot {
+    #[serde(default)]
+    root: Option<String>,
+    #[serde(default)]
+    format: SnapshotFormat,
+  },
+  /// Visible content as Markdown, from `root` or the detected main content, cut to `max_chars`
+  /// (0 = no limit). Resolves to `{ title, url, markdown, truncated }`.
+  ExtractContent {
+    #[serde(default)]
+    root: Option<String>,
+    #[serde(default)]
+    max_chars: u64,
+  },
 }
 
 #[derive(Error, Debug)]
@@ -35,6 +256,10 @@ pub fn parse_ipc_envelope(s: &str) -> Result<IpcEnvelope, ProtocolError> {
   serde_json::from_str::<IpcEnvelope>(s).map_err(|e| ProtocolError::InvalidJson(e.to_string()))
 }
 
+pub fn parse_ipc_message(s: &str) -> Result<IpcMessage, ProtocolError> {
+  serde_json::from_str::<IpcMessage>(s).map_err(|e| ProtocolError::InvalidJson(e.to_string()))
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 struct DispatchRequest {
   id: String,
@@ -67,7 +292,7 @@ pub fn build_dispatch_script(id: &str, cmd: Command) -> String {
 }
 
 pub fn automation_shim_js() -> &'static str {
-  r#"
+  r##"
 (function () {
   if (window.__gwry && window.__gwry.__installed) return;
 
@@ -87,36 +312,658 @@ pub fn automation_shim_js() -> &'static str {
     postMessage({ id: String(id), ok: false, result: null, error: String(error) });
   }
 
+  function sendEvent(event, data) {
+    postMessage({ event: String(event), data: data ?? null });
+  }
+
+  function formatConsoleArg(arg) {
+    if (typeof arg === "string") return arg;
+    try {
+      return JSON.stringify(arg);
+    } catch (_) {
+      return String(arg);
+    }
+  }
+
+  ["log", "info", "warn", "error", "debug"].forEach(function (level) {
+    var original = console[level];
+    if (typeof original !== "function") return;
+    console[level] = function () {
+      try {
+        sendEvent("console", {
+          level: level,
+          text: Array.prototype.map.call(arguments, formatConsoleArg).join(" "),
+        });
+      } catch (_) {}
+      return original.apply(console, arguments);
+    };
+  });
+
+  // Same-document navigations never fire a page load; report them so URL waits can resolve.
+  function notifyUrlChanged() {
+    try {
+      sendEvent("url_changed", { url: String(location.href) });
+    } catch (_) {}
+  }
+
+  ["pushState", "replaceState"].forEach(function (name) {
+    var original = history[name];
+    if (typeof original !== "function") return;
+    history[name] = function () {
+      var result = original.apply(this, arguments);
+      notifyUrlChanged();
+      return result;
+    };
+  });
+  window.addEventListener("popstate", notifyUrlChanged);
+  window.addEventListener("hashchange", notifyUrlChanged);
+
+  var refElements = new Map();
+  var elementRefs = new WeakMap();
+  var nextRef = 1;
+
+  function refFor(el) {
+    var ref = elementRefs.get(el);
+    if (!ref) {
+      ref = "e" + nextRef++;
+      elementRefs.set(el, ref);
+      refElements.set(ref, new WeakRef(el));
+    }
+    return ref;
+  }
+
+  function byRef(ref) {
+    var weak = refElements.get(ref);
+    var el = weak && weak.deref();
+    return el && el.isConnected ? el : null;
+  }
+
+  var handles = new Map();
+  var elementHandles = new WeakMap();
+  var nextHandle = 1;
+
+  function handleFor(el) {
+    var handle = elementHandles.get(el);
+    if (!handle) {
+      handle = nextHandle++;
+      elementHandles.set(el, handle);
+      handles.set(handle, new WeakRef(el));
+    }
+    return handle;
+  }
+
+  function disposeHandles(ids) {
+    var list = Array.isArray(ids) && ids.length ? ids.map(Number) : Array.from(handles.keys());
+    var freed = 0;
+    list.forEach(function (handle) {
+      var weak = handles.get(handle);
+      if (!weak) return;
+      var el = weak.deref();
+      if (el) elementHandles.delete(el);
+      handles.delete(handle);
+      freed++;
+    });
+    return freed;
+  }
+
+  function live(weak) {
+    var el = weak.deref();
+    if (!el || !el.isConnected) throw new Error("stale_element");
+    return el;
+  }
+
+  // Resolves a command target, failing with not_found / handle_not_found / stale_element.
+  function element(target) {
+    if (target && typeof target === "object") {
+      var weak = handles.get(Number(target.handle));
+      if (!weak) throw new Error("handle_not_found");
+      return live(weak);
+    }
+    var s = String(target);
+    if (s.indexOf("aria-ref=") === 0) {
+      var ref = refElements.get(s.slice(9));
+      if (!ref) throw new Error("not_found");
+      return live(ref);
+    }
+    var el = document.querySelector(s);
+    if (!el) throw new Error("not_found");
+    return el;
+  }
+
   function qs(selector) {
-    return document.querySelector(selector);
+    var s = String(selector);
+    if (s.indexOf("aria-ref=") === 0) return byRef(s.slice(9));
+    return document.querySelector(s);
   }
 
-  function waitForSelector(selector, timeoutMs) {
-    return new Promise(function (resolve, reject) {
-      var el = qs(selector);
-      if (el) return resolve(true);
+  function qsa(selector) {
+    var s = String(selector);
+    if (s.indexOf("aria-ref=") === 0) return [byRef(s.slice(9))].filter(Boolean);
+    return Array.prototype.slice.call(document.querySelectorAll(s));
+  }
+
+  function isVisible(el) {
+    if (typeof el.checkVisibility === "function") {
+      if (!el.checkVisibility({ visibilityProperty: true, opacityProperty: false })) return false;
+    } else if (getComputedStyle(el).visibility !== "visible") {
+      return false;
+    }
+    var rect = el.getBoundingClientRect();
+    return rect.width > 0 && rect.height > 0;
+  }
+
+  function optionMatches(option, index, want) {
+    if (typeof want === "string") return option.value === want || option.label === want;
+    if (typeof want === "number") return index === want;
+    return (want.value == null || option.value === want.value) &&
+      (want.label == null || option.label === want.label) &&
+      (want.index == null || index === want.index);
+  }
+
+  // Assigns through the prototype setter so framework-tracked inputs see the change.
+  function setNativeValue(el, value) {
+    var proto = el.localName === "textarea" ? HTMLTextAreaElement.prototype : HTMLInputElement.prototype;
+    var descriptor = Object.getOwnPropertyDescriptor(proto, "value");
+    if (descriptor && descriptor.set) descriptor.set.call(el, value);
+    else el.value = value;
+  }
+
+  function isChecked(el) {
+    var tag = el.localName;
+    var type = String(el.type || "").toLowerCase();
+    if (tag === "input" && (type === "checkbox" || type === "radio")) return el.checked;
+    var aria = el.getAttribute("aria-checked");
+    if (aria !== null) return aria === "true";
+    throw new Error("not_checkable");
+  }
+
+  var MAX_TEXT = 200;
+  var SKIP_TAGS = ["script", "style", "template", "noscript", "head", "meta", "link", "title"];
+  var IMPLICIT_ROLES = {
+    article: "article", aside: "complementary", blockquote: "blockquote", button: "button",
+    details: "group", dialog: "dialog", fieldset: "group", figure: "figure", form: "form",
+    h1: "heading", h2: "heading", h3: "heading", h4: "heading", h5: "heading", h6: "heading",
+    hr: "separator", iframe: "iframe", li: "listitem", main: "main", menu: "list", meter: "meter",
+    nav: "navigation", ol: "list", optgroup: "group", option: "option", output: "status",
+    p: "paragraph", progress: "progressbar", summary: "button", table: "table", tbody: "rowgroup",
+    td: "cell", textarea: "textbox", tfoot: "rowgroup", th: "columnheader", thead: "rowgroup",
+    tr: "row", ul: "list",
+  };
+  var NAME_FROM_CONTENT = [
+    "button", "cell", "checkbox", "columnheader", "heading", "link", "menuitem", "menuitemcheckbox",
+    "menuitemradio", "option", "radio", "row", "rowheader", "switch", "tab", "tooltip", "treeitem",
+  ];
+  var VALUE_ROLES = ["textbox", "searchbox", "spinbutton", "slider"];
+
+  function clip(text) {
+    text = String(text || "").replace(/\s+/g, " ").trim();
+    return text.length > MAX_TEXT ? text.slice(0, MAX_TEXT - 1) + "…" : text;
+  }
+
+  function implicitRole(el) {
+    var tag = el.localName;
+    switch (tag) {
+      case "a":
+      case "area":
+        return el.hasAttribute("href") ? "link" : null;
+      case "img":
+        return el.getAttribute("alt") === "" ? null : "img";
+      case "input": {
+        var type = String(el.type || "text").toLowerCase();
+        if (type === "checkbox" || type === "radio") return type;
+        if (type === "range") return "slider";
+        if (type === "number") return "spinbutton";
+        if (["button", "submit", "reset", "image", "file"].indexOf(type) >= 0) return "button";
+        if (el.hasAttribute("list")) return "combobox";
+        return type === "search" ? "searchbox" : "textbox";
+      }
+      case "select":
+        return el.multiple || el.size > 1 ? "listbox" : "combobox";
+      case "section":
+        return el.hasAttribute("aria-label") || el.hasAttribute("aria-labelledby") ? "region" : null;
+      case "header":
+        return el.closest("article,aside,main,nav,section") ? null : "banner";
+      case "footer":
+        return el.closest("article,aside,main,nav,section") ? null : "contentinfo";
+      default:
+        return IMPLICIT_ROLES[tag] || null;
+    }
+  }
+
+  function roleOf(el) {
+    var explicit = String(el.getAttribute("role") || "").trim().split(/\s+/)[0];
+    if (explicit) return explicit === "none" ? "presentation" : explicit;
+    return implicitRole(el);
+  }
+
+  function accessibleName(el, role) {
+    var labelledBy = el.getAttribute("aria-labelledby");
+    if (labelledBy) {
+      var joined = clip(labelledBy.split(/\s+/).map(function (id) {
+        var target = document.getElementById(id);
+        return target ? target.textContent : "";
+      }).join(" "));
+      if (joined) return joined;
+    }
+    var label = clip(el.getAttribute("aria-label"));
+    if (label) return label;
+    var tag = el.localName;
+    if (tag === "input" || tag === "textarea" || tag === "select") {
+      var type = String(el.type || "").toLowerCase();
+      if (type === "submit" || type === "reset" || type === "button") {
+        return clip(el.value || (type === "submit" ? "Submit" : type === "reset" ? "Reset" : ""));
+      }
+      if (type === "image") return clip(el.alt || el.value);
+      var labels = clip(Array.prototype.map.call(el.labels || [], function (l) { return l.textContent; }).join(" "));
+      return labels || clip(el.getAttribute("title") || el.getAttribute("placeholder"));
+    }
+    if (tag === "img" || tag === "area") return clip(el.getAttribute("alt") || el.getAttribute("title"));
+    var caption = tag === "fieldset" ? el.querySelector(":scope > legend")
+      : tag === "table" ? el.querySelector(":scope > caption")
+      : tag === "figure" ? el.querySelector(":scope > figcaption") : null;
+    if (caption) return clip(caption.textContent);
+    if (NAME_FROM_CONTENT.indexOf(role) >= 0) {
+      var text = clip(el.innerText || el.textContent);
+      if (text) return text;
+    }
+    return clip(el.getAttribute("title"));
+  }
+
+  function describeState(el, role, node) {
+    var ariaChecked = el.getAttribute("aria-checked");
+    if (el.localName === "input" && (role === "checkbox" || role === "radio")) {
+      node.checked = el.indeterminate ? "mixed" : el.checked;
+    } else if (ariaChecked !== null) {
+      node.checked = ariaChecked === "mixed" ? "mixed" : ariaChecked === "true";
+    }
+    if (el.getAttribute("aria-disabled") === "true" || el.matches(":disabled")) node.disabled = true;
+    var expanded = el.getAttribute("aria-expanded");
+    if (expanded !== null) node.expanded = expanded === "true";
+    else if (el.localName === "details") node.expanded = el.open;
+    else if (el.localName === "summary" && el.parentElement && el.parentElement.localName === "details") {
+      node.expanded = el.parentElement.open;
+    }
+    if (el.getAttribute("aria-selected") === "true" || (el.localName === "option" && el.selected)) node.selected = true;
+    var pressed = el.getAttribute("aria-pressed");
+    if (pressed === "true" || pressed === "mixed") node.pressed = pressed === "mixed" ? "mixed" : true;
+    if (role === "heading") {
+      node.level = Number(el.getAttribute("aria-level")) || Number(el.localName.slice(1)) || 2;
+    }
+    if (VALUE_ROLES.indexOf(role) >= 0 && typeof el.value === "string" && el.value !== "") {
+      node.value = el.type === "password" ? "***" : clip(el.value);
+    }
+  }
+
+  function pushText(out, text) {
+    if (!text) return;
+    if (typeof out[out.length - 1] === "string") out[out.length - 1] = clip(out[out.length - 1] + " " + text);
+    else out.push(text);
+  }
+
+  function snapshotChildren(parent, out) {
+    var children = parent.localName === "slot" && parent.assignedNodes().length
+      ? parent.assignedNodes({ flatten: true })
+      : (parent.shadowRoot || parent).childNodes;
+    Array.prototype.forEach.call(children, function (child) {
+      if (child.nodeType === 3) pushText(out, clip(child.textContent));
+      else if (child.nodeType === 1) snapshotElement(child, out);
+    });
+  }
 
+  function snapshotElement(el, out) {
+    if (SKIP_TAGS.indexOf(el.localName) >= 0 || el.getAttribute("aria-hidden") === "true") return;
+    // Options of a closed select have no box but are still part of the tree.
+    var boxless = el.localName === "option" || el.localName === "optgroup";
+    if (!boxless && hidden(el)) return;
+    var role = roleOf(el);
+    if (!role || role === "presentation" || role === "generic" || getComputedStyle(el).visibility !== "visible") {
+      snapshotChildren(el, out);
+      return;
+    }
+    var node = { role: role, name: accessibleName(el, role), ref: refFor(el) };
+    describeState(el, role, node);
+    var children = [];
+    if (VALUE_ROLES.indexOf(role) < 0 && role !== "iframe") snapshotChildren(el, children);
+    if (children.length === 1 && children[0] === node.name) children = [];
+    if (children.length) node.children = children;
+    out.push(node);
+  }
+
+  function hidden(el) {
+    return typeof el.checkVisibility === "function" ? !el.checkVisibility() : getComputedStyle(el).display === "none";
+  }
+
+  var BLOCK_TAGS = [
+    "address", "article", "aside", "blockquote", "body", "dd", "details", "dialog", "div", "dl", "dt",
+    "fieldset", "figcaption", "figure", "footer", "form", "h1", "h2", "h3", "h4", "h5", "h6", "header",
+    "hr", "li", "main", "nav", "ol", "p", "pre", "section", "summary", "table", "ul",
+  ];
+  var SKIP_CONTENT = SKIP_TAGS.concat(["svg", "canvas", "iframe", "video", "audio", "object", "select", "textarea", "input"]);
+  var BOILERPLATE_ROLES = ["navigation", "banner", "contentinfo", "complementary", "search"];
+  var POSITIVE_HINT = /article|body|content|entry|main|page|post|text|blog|story/i;
+  var NEGATIVE_HINT = /comment|footer|footnote|header|menu|meta|nav|related|share|sidebar|social|sponsor|promo|banner|widget/i;
+
+  function childNodesOf(el) {
+    return Array.prototype.slice.call((el.shadowRoot || el).childNodes);
+  }
+
+  function linkDensity(el) {
+    var length = el.textContent.length || 1;
+    var links = 0;
+    Array.prototype.forEach.call(el.querySelectorAll("a"), function (a) { links += a.textContent.length; });
+    return links / length;
+  }
+
+  function classWeight(el) {
+    var hint = (el.getAttribute("class") || "") + " " + (el.id || "");
+    return (POSITIVE_HINT.test(hint) ? 25 : 0) - (NEGATIVE_HINT.test(hint) ? 25 : 0);
+  }
+
+  // Readability-style detection: a single main landmark or article wins, otherwise the container
+  // whose paragraphs carry the most text (less link-heavy, class hints considered).
+  function mainContent() {
+    var body = document.body || document.documentElement;
+    var visible = function (el) { return !hidden(el); };
+    var landmarks = Array.prototype.filter.call(document.querySelectorAll("main, [role=main]"), visible);
+    if (landmarks.length === 1) return landmarks[0];
+    var articles = Array.prototype.filter.call(document.querySelectorAll("article"), visible);
+    if (articles.length === 1) return articles[0];
+
+    var scores = new Map();
+    Array.prototype.forEach.call(body.querySelectorAll("p, pre, td, blockquote, li"), function (p) {
+      var text = p.textContent.replace(/\s+/g, " ").trim();
+      if (text.length < 25 || hidden(p)) return;
+      var score = 1 + text.split(",").length + Math.min(3, Math.floor(text.length / 100));
+      [p.parentElement, p.parentElement && p.parentElement.parentElement].forEach(function (candidate, level) {
+        if (!candidate || candidate === document.documentElement) return;
+        if (!scores.has(candidate)) scores.set(candidate, classWeight(candidate));
+        scores.set(candidate, scores.get(candidate) + (level === 0 ? score : score / 2));
+      });
+    });
+    var best = null;
+    var bestScore = 0;
+    scores.forEach(function (score, candidate) {
+      var adjusted = score * (1 - linkDensity(candidate));
+      if (adjusted > bestScore) {
+        best = candidate;
+        bestScore = adjusted;
+      }
+    });
+    if (!best) return body;
+    // Prefer the parent when it holds comparable content, so sibling sections are kept.
+    while (best.parentElement && best.parentElement !== body && scores.has(best.parentElement) &&
+      scores.get(best.parentElement) * (1 - linkDensity(best.parentElement)) >= bestScore * 0.75) {
+      best = best.parentElement;
+    }
+    return best;
+  }
+
+  // Only landmark-level boilerplate is dropped: a header or footer inside the article keeps its text.
+  function mdSkipped(el, ctx) {
+    if (SKIP_CONTENT.indexOf(el.localName) >= 0 || hidden(el)) return true;
+    return ctx.auto && BOILERPLATE_ROLES.indexOf(roleOf(el)) >= 0;
+  }
+
+  function mdInline(node, ctx) {
+    if (node.nodeType === 3) return node.textContent;
+    if (node.nodeType !== 1 || mdSkipped(node, ctx)) return "";
+    var tag = node.localName;
+    if (tag === "br") return "\n";
+    if (tag === "img") {
+      var alt = clip(node.getAttribute("alt"));
+      if (!alt) return "";
+      return /^data:/i.test(node.src) ? alt : "![" + alt + "](" + node.src + ")";
+    }
+    if (tag === "code" && !node.closest("pre")) {
+      var code = node.textContent.trim();
+      return code ? "`" + code + "`" : "";
+    }
+    var inner = childNodesOf(node).map(function (child) { return mdInline(child, ctx); }).join("");
+    var text = inner.replace(/[ \t\r\n]+/g, " ").trim();
+    if (!text) return /\s/.test(inner) ? " " : "";
+    var wrap = function (s) { return (/^\s/.test(inner) ? " " : "") + s + (/\s$/.test(inner) ? " " : ""); };
+    if (tag === "a") {
+      var href = node.getAttribute("href") ? node.href : "";
+      return wrap(href && !/^javascript:/i.test(href) ? "[" + text + "](" + href + ")" : text);
+    }
+    if (tag === "strong" || tag === "b") return wrap("**" + text + "**");
+    if (tag === "em" || tag === "i") return wrap("*" + text + "*");
+    if (tag === "del" || tag === "s") return wrap("~~" + text + "~~");
+    return inner;
+  }
+
+  function mdInlineText(el, ctx) {
+    return childNodesOf(el).map(function (child) { return mdInline(child, ctx); }).join("")
+      .replace(/[ \t]+/g, " ").replace(/ *\n */g, "\n").trim();
+  }
+
+  function mdList(list, ctx) {
+    var ordered = list.localName === "ol";
+    var n = Number(list.getAttribute("start")) || 1;
+    var pad = "  ".repeat(ctx.list);
+    var lines = [];
+    Array.prototype.forEach.call(list.children, function (li) {
+      if (li.localName !== "li" || hidden(li)) return;
+      var parts = [];
+      var nested = [];
+      mdBlocks(li, parts, { auto: ctx.auto, list: ctx.list + 1, nested: nested });
+      var text = parts.join(" ").replace(/\n/g, " ");
+      if (!text && !nested.length) return;
+      lines.push(pad + (ordered ? n++ + ". " : "- ") + text);
+      lines.push.apply(lines, nested);
+    });
+    return lines.join("\n");
+  }
+
+  function mdTable(table, ctx) {
+    var rows = Array.prototype.filter.call(table.rows, function (row) { return !hidden(row); }).map(function (row) {
+      return Array.prototype.map.call(row.cells, function (cell) {
+        return mdInlineText(cell, ctx).replace(/\n/g, " ").replace(/\|/g, "\\|");
+      });
+    }).filter(function (cells) { return cells.some(Boolean); });
+    if (!rows.length) return "";
+    var width = Math.max.apply(null, rows.map(function (cells) { return cells.length; }));
+    var line = function (cells) {
+      var padded = cells.slice();
+      while (padded.length < width) padded.push("");
+      return "| " + padded.join(" | ") + " |";
+    };
+    return [line(rows[0]), "|" + " --- |".repeat(width)].concat(rows.slice(1).map(line)).join("\n");
+  }
+
+  function mdBlock(el, out, ctx) {
+    var tag = el.localName;
+    if (/^h[1-6]$/.test(tag)) {
+      var heading = mdInlineText(el, ctx).replace(/\n/g, " ");
+      if (heading) out.push("#".repeat(Number(tag[1])) + " " + heading);
+    } else if (tag === "hr") {
+      out.push("---");
+    } else if (tag === "pre") {
+      var code = el.textContent.replace(/\n+$/, "");
+      if (code.trim()) out.push("```\n" + code + "\n```");
+    } else if (tag === "ul" || tag === "ol") {
+      var list = mdList(el, ctx);
+      if (list) (ctx.nested || out).push(list);
+    } else if (tag === "table") {
+      var table = mdTable(el, ctx);
+      if (table) out.push(table);
+    } else if (tag === "blockquote") {
+      var quoted = [];
+      mdBlocks(el, quoted, ctx);
+      if (quoted.length) out.push(quoted.join("\n\n").split("\n").map(function (l) { return "> " + l; }).join("\n"));
+    } else {
+      mdBlocks(el, out, ctx);
+    }
+  }
+
+  function mdBlocks(el, out, ctx) {
+    var buffer = "";
+    var flush = function () {
+      var text = buffer.replace(/[ \t\r\n]*\n[ \t\r\n]*/g, "\n").replace(/[ \t\r]+/g, " ").trim();
+      if (text) out.push(text);
+      buffer = "";
+    };
+    childNodesOf(el).forEach(function (child) {
+      if (child.nodeType === 3) {
+        buffer += child.textContent.replace(/[ \t\r\n]+/g, " ");
+      } else if (child.nodeType === 1 && !mdSkipped(child, ctx)) {
+        if (BLOCK_TAGS.indexOf(child.localName) < 0) {
+          buffer += mdInline(child, ctx);
+        } else {
+          flush();
+          mdBlock(child, out, ctx);
+        }
+      }
+    });
+    flush();
+  }
+
+  function extractContent(rootSelector, maxChars) {
+    var auto = !rootSelector;
+    var out = [];
+    mdBlock(auto ? mainContent() : element(rootSelector), out, { auto: auto, list: 0 });
+    var markdown = out.join("\n\n").replace(/\n{3,}/g, "\n\n").trim();
+    var limit = Number(maxChars || 0);
+    var truncated = limit > 0 && markdown.length > limit;
+    if (truncated) {
+      // Cut at a line break when one is reasonably close to the budget.
+      var cut = markdown.lastIndexOf("\n", limit);
+      markdown = markdown.slice(0, cut > limit / 2 ? cut : limit).trim();
+    }
+    return { title: document.title, url: location.href, markdown: markdown, truncated: truncated };
+  }
+
+  function yamlText(text) {
+    return /^[A-Za-z0-9\u00C0-\uFFFF][^:#\[\]{}"'\n]*$/.test(text) && !/\s$/.test(text) ? text : JSON.stringify(text);
+  }
+
+  function toYaml(nodes, indent) {
+    return nodes.map(function (node) {
+      if (typeof node === "string") return indent + "- text: " + yamlText(node);
+      var line = indent + "- " + node.role;
+      if (node.name) line += " " + JSON.stringify(node.name);
+      if (node.checked === "mixed") line += " [checked=mixed]";
+      else if (node.checked) line += " [checked]";
+      if (node.disabled) line += " [disabled]";
+      if (node.expanded === true) line += " [expanded]";
+      else if (node.expanded === false) line += " [expanded=false]";
+      if (node.selected) line += " [selected]";
+      if (node.pressed) line += node.pressed === "mixed" ? " [pressed=mixed]" : " [pressed]";
+      if (node.level) line += " [level=" + node.level + "]";
+      line += " [ref=" + node.ref + "]";
+      if (node.children) return line + ":\n" + toYaml(node.children, indent + "  ");
+      return node.value ? line + ": " + yamlText(node.value) : line;
+    }).join("\n");
+  }
+
+  function snapshot(rootSelector, format) {
+    var nodes = [];
+    if (rootSelector) {
+      snapshotElement(element(rootSelector), nodes);
+    } else {
+      snapshotChildren(document.body || document.documentElement, nodes);
+    }
+    return format === "json" ? nodes : toYaml(nodes, "");
+  }
+
+  // Resolves with the first truthy value of `predicate`, re-checked according to `polling`.
+  function waitUntil(predicate, polling, timeoutMs) {
+    return new Promise(function (resolve, reject) {
       var done = false;
-      var timeout = setTimeout(function () {
+      var busy = false;
+      var obs = null;
+      var timer = null;
+      var frame = null;
+      var timeout = null;
+
+      function finish(settle, value) {
         if (done) return;
         done = true;
-        try { obs.disconnect(); } catch (_) {}
-        reject(new Error("timeout"));
-      }, Math.max(0, Number(timeoutMs || 0)));
+        clearTimeout(timeout);
+        if (timer) clearInterval(timer);
+        if (frame) cancelAnimationFrame(frame);
+        if (obs) obs.disconnect();
+        settle(value);
+      }
 
-      var obs = new MutationObserver(function () {
-        var el2 = qs(selector);
-        if (el2 && !done) {
-          done = true;
-          clearTimeout(timeout);
-          try { obs.disconnect(); } catch (_) {}
-          resolve(true);
+      function check() {
+        if (done || busy) return;
+        var value;
+        try {
+          value = predicate();
+        } catch (e) {
+          return finish(reject, e);
         }
-      });
-      obs.observe(document.documentElement || document, { childList: true, subtree: true });
+        busy = true;
+        Promise.resolve(value).then(function (result) {
+          busy = false;
+          if (result) finish(resolve, result);
+        }, function (e) {
+          busy = false;
+          finish(reject, e);
+        });
+      }
+
+      timeout = setTimeout(function () {
+        finish(reject, new Error("timeout"));
+      }, Math.max(0, Number(timeoutMs || 0)));
+
+      if (polling === "mutation") {
+        obs = new MutationObserver(check);
+        obs.observe(document.documentElement || document, {
+          childList: true, subtree: true, attributes: true, characterData: true,
+        });
+      } else if (polling && typeof polling === "object") {
+        timer = setInterval(check, Math.max(1, Number(polling.interval) || 100));
+      } else if (document.visibilityState === "hidden") {
+        // Hidden webviews pause animation frames.
+        timer = setInterval(check, 100);
+      } else {
+        var loop = function () {
+          check();
+          if (!done) frame = requestAnimationFrame(loop);
+        };
+        frame = requestAnimationFrame(loop);
+      }
+      check();
     });
   }
 
+  function selectorState(selector, state) {
+    var el = qs(selector);
+    switch (state) {
+      case "detached":
+        return !el;
+      case "visible":
+        return !!el && isVisible(el);
+      case "hidden":
+        return !el || !isVisible(el);
+      default:
+        return !!el;
+    }
+  }
+
+  function waitForSelector(selector, timeoutMs, state) {
+    // Visibility can change through styles alone, which mutations of the tree do not reveal.
+    var polling = state === "visible" || state === "hidden" ? "raf" : "mutation";
+    return waitUntil(function () { return selectorState(selector, state); }, polling, timeoutMs);
+  }
+
+  // `js` is compiled once without being run; each poll evaluates it (calling it when it is a
+  // function) and a pass that throws or rejects counts as "not yet".
+  function waitForFunction(js, polling, timeoutMs) {
+    var compiled = new Function("return (" + String(js).replace(/[\s;]+$/, "") + "\n);");
+    return waitUntil(function () {
+      try {
+        var value = compiled();
+        if (typeof value === "function") value = value();
+        return Promise.resolve(value).catch(function () { return false; });
+      } catch (e) {
+        return false;
+      }
+    }, polling, timeoutMs);
+  }
+
   async function dispatch(msg) {
     var id = msg && msg.id;
     var cmd = msg && msg.cmd;
@@ -133,15 +980,13 @@ pub fn automation_shim_js() -> &'static str {
           return;
         }
         case "click": {
-          var el = qs(msg.selector);
-          if (!el) throw new Error("not_found");
+          var el = element(msg.selector);
           el.click();
           sendOk(id, true);
           return;
         }
         case "fill": {
-          var el2 = qs(msg.selector);
-          if (!el2) throw new Error("not_found");
+          var el2 = element(msg.selector);
           el2.value = String(msg.text ?? "");
           el2.dispatchEvent(new Event("input", { bubbles: true }));
           el2.dispatchEvent(new Event("change", { bubbles: true }));
@@ -149,22 +994,196 @@ pub fn automation_shim_js() -> &'static str {
           return;
         }
         case "text": {
-          var el3 = qs(msg.selector);
-          if (!el3) throw new Error("not_found");
+          var el3 = element(msg.selector);
           sendOk(id, el3.textContent ?? "");
           return;
         }
         case "attr": {
-          var el4 = qs(msg.selector);
-          if (!el4) throw new Error("not_found");
+          var el4 = element(msg.selector);
           sendOk(id, el4.getAttribute(String(msg.name)));
           return;
         }
         case "wait_for_selector": {
-          await waitForSelector(msg.selector, msg.timeout_ms);
+          await waitForSelector(msg.selector, msg.timeout_ms, msg.state);
+          sendOk(id, true);
+          return;
+        }
+        case "wait_for_function": {
+          sendOk(id, await waitForFunction(msg.js, msg.polling, msg.timeout_ms));
+          return;
+        }
+        case "set_input_files": {
+          var input = element(msg.selector);
+          if (String(input.tagName).toLowerCase() !== "input" || String(input.type).toLowerCase() !== "file") {
+            throw new Error("not_file_input");
+          }
+          var files = Array.isArray(msg.files) ? msg.files : [];
+          if (files.length > 1 && !input.multiple) throw new Error("not_multiple");
+          var transfer = new DataTransfer();
+          files.forEach(function (file) {
+            var raw = atob(String(file.base64 || ""));
+            var bytes = new Uint8Array(raw.length);
+            for (var i = 0; i < raw.length; i++) bytes[i] = raw.charCodeAt(i);
+            transfer.items.add(new File([bytes], String(file.name), { type: String(file.mime_type || "") }));
+          });
+          input.files = transfer.files;
+          input.dispatchEvent(new Event("input", { bubbles: true }));
+          input.dispatchEvent(new Event("change", { bubbles: true }));
+          sendOk(id, files.length);
+          return;
+        }
+        case "query_all": {
+          var limit = Number(msg.limit || 0);
+          var matches = qsa(msg.selector);
+          if (limit > 0) matches = matches.slice(0, limit);
+          var names = Array.isArray(msg.attrs) ? msg.attrs.map(String) : [];
+          sendOk(id, matches.map(function (match) {
+            var attrs = {};
+            names.forEach(function (name) { attrs[name] = match.getAttribute(name); });
+            return { text: match.textContent ?? "", attrs: attrs };
+          }));
+          return;
+        }
+        case "count": {
+          sendOk(id, qsa(msg.selector).length);
+          return;
+        }
+        case "inner_html": {
+          sendOk(id, element(msg.selector).innerHTML);
+          return;
+        }
+        case "outer_html": {
+          sendOk(id, element(msg.selector).outerHTML);
+          return;
+        }
+        case "input_value": {
+          var field = element(msg.selector);
+          if (["input", "textarea", "select"].indexOf(field.localName) < 0) throw new Error("not_input");
+          sendOk(id, field.value);
+          return;
+        }
+        case "is_visible": {
+          var shown = false;
+          try {
+            shown = isVisible(element(msg.selector));
+          } catch (e) {
+            if (!e || e.message !== "not_found") throw e;
+          }
+          sendOk(id, shown);
+          return;
+        }
+        case "is_enabled": {
+          var control = element(msg.selector);
+          sendOk(id, !control.matches(":disabled") && control.getAttribute("aria-disabled") !== "true");
+          return;
+        }
+        case "is_checked": {
+          sendOk(id, isChecked(element(msg.selector)));
+          return;
+        }
+        case "bounding_box": {
+          var boxed = element(msg.selector);
+          if (!isVisible(boxed)) {
+            sendOk(id, null);
+            return;
+          }
+          var rect = boxed.getBoundingClientRect();
+          sendOk(id, { x: rect.x, y: rect.y, width: rect.width, height: rect.height });
+          return;
+        }
+        case "select_option": {
+          var select = element(msg.selector);
+          if (select.localName !== "select") throw new Error("not_select");
+          if (select.disabled) throw new Error("element_disabled");
+          var options = Array.prototype.slice.call(select.options);
+          var wanted = Array.isArray(msg.options) ? msg.options : [];
+          wanted.forEach(function (want) {
+            if (!options.some(function (option, index) { return optionMatches(option, index, want); })) {
+              throw new Error("option_not_found: " + JSON.stringify(want));
+            }
+          });
+          var picked = options.filter(function (option, index) {
+            return wanted.some(function (want) { return optionMatches(option, index, want); });
+          });
+          if (!select.multiple) picked = picked.slice(0, 1);
+          select.focus();
+          options.forEach(function (option) { option.selected = picked.indexOf(option) >= 0; });
+          if (!picked.length && !select.multiple) select.selectedIndex = -1;
+          select.dispatchEvent(new Event("input", { bubbles: true }));
+          select.dispatchEvent(new Event("change", { bubbles: true }));
+          sendOk(id, picked.map(function (option) { return option.value; }));
+          return;
+        }
+        case "set_checked": {
+          var box = element(msg.selector);
+          var wantChecked = !!msg.checked;
+          if (isChecked(box) !== wantChecked) {
+            if (!wantChecked && box.localName === "input" && String(box.type).toLowerCase() === "radio") {
+              throw new Error("cannot_uncheck_radio");
+            }
+            box.click();
+            if (isChecked(box) !== wantChecked) throw new Error("checked_state_unchanged");
+          }
+          sendOk(id, wantChecked);
+          return;
+        }
+        case "focus": {
+          element(msg.selector).focus();
+          sendOk(id, true);
+          return;
+        }
+        case "blur": {
+          element(msg.selector).blur();
           sendOk(id, true);
           return;
         }
+        case "clear": {
+          var cleared = element(msg.selector);
+          cleared.focus();
+          if (cleared.isContentEditable) {
+            cleared.textContent = "";
+          } else if (cleared.localName === "input" || cleared.localName === "textarea") {
+            setNativeValue(cleared, "");
+          } else {
+            throw new Error("not_input");
+          }
+          cleared.dispatchEvent(new InputEvent("input", { bubbles: true, inputType: "deleteContentBackward" }));
+          if (!cleared.isContentEditable) cleared.dispatchEvent(new Event("change", { bubbles: true }));
+          sendOk(id, true);
+          return;
+        }
+        case "submit": {
+          var source = element(msg.selector);
+          var form = source.localName === "form" ? source : (source.form || source.closest("form"));
+          if (!form) throw new Error("form_not_found");
+          var submitter = source !== form && String(source.type).toLowerCase() === "submit" && source.form === form
+            ? source : null;
+          if (typeof form.requestSubmit === "function") {
+            if (submitter) form.requestSubmit(submitter);
+            else form.requestSubmit();
+          } else {
+            form.submit();
+          }
+          sendOk(id, true);
+          return;
+        }
+        case "query": {
+          var found = qs(msg.selector);
+          sendOk(id, found ? handleFor(found) : null);
+          return;
+        }
+        case "dispose_handles": {
+          sendOk(id, disposeHandles(msg.handles));
+          return;
+        }
+        case "extract_content": {
+          sendOk(id, extractContent(msg.root, msg.max_chars));
+          return;
+        }
+        case "snapshot": {
+          sendOk(id, snapshot(msg.root, msg.format));
+          return;
+        }
         default:
           throw new Error("unsupported_cmd:" + String(cmd));
       }
@@ -176,7 +1195,10 @@ pub fn automation_shim_js() -> &'static str {
   window.__gwry = {
     __installed: true,
     dispatch: dispatch,
+    sendEvent: sendEvent,
+    query: qs,
+    element: element,
   };
 })();
-"#
+"##
 }
@@ -1,4 +1,7 @@
-use godot_wry_playwright_core::protocol::{build_dispatch_script, parse_ipc_envelope, Command};
+use godot_wry_playwright_core::protocol::{
+  build_dispatch_script, parse_ipc_envelope, parse_ipc_message, Command, ElementHandle, InputFile, IpcMessage, OptionMatch, Polling, SelectorState, SnapshotFormat,
+  Target,
+};
 use pretty_assertions::assert_eq;
 use serde_json::json;
 
@@ -29,3 +32,134 @@ fn build_dispatch_script_includes_id_and_cmd() {
   assert!(script.contains(r#""cmd":"eval""#), "script should include command");
 }
 
+#[test]
+fn set_input_files_serializes_files_inline() {
+  let cmd = Command::SetInputFiles {
+    selector: "#avatar".into(),
+    files: vec![InputFile { name: "a.png".into(), mime_type: "image/png".into(), base64: "iVBO".into() }],
+  };
+  assert_eq!(
+    serde_json::to_value(&cmd).unwrap(),
+    json!({
+      "cmd": "set_input_files",
+      "selector": "#avatar",
+      "files": [{ "name": "a.png", "mime_type": "image/png", "base64": "iVBO" }]
+    })
+  );
+}
+
+#[test]
+fn targets_serialize_as_selector_or_handle() {
+  assert_eq!(
+    serde_json::to_value(Command::Click { selector: "#go".into() }).unwrap(),
+    json!({ "cmd": "click", "selector": "#go" })
+  );
+  let by_handle = Command::Text { selector: Target::from_selector("handle=7") };
+  assert_eq!(serde_json::to_value(&by_handle).unwrap(), json!({ "cmd": "text", "selector": { "handle": 7 } }));
+  assert_eq!(serde_json::from_value::<Command>(json!({ "cmd": "text", "selector": { "handle": 7 } })).unwrap(), by_handle);
+  assert_eq!(Target::from_selector("handle=x"), Target::Selector("handle=x".into()));
+  assert_eq!(Target::from(ElementHandle { handle: 2 }), Target::Handle(ElementHandle { handle: 2 }));
+  assert_eq!(
+    serde_json::to_value(Command::DisposeHandles { handles: vec![] }).unwrap(),
+    json!({ "cmd": "dispose_handles", "handles": [] })
+  );
+}
+
+#[test]
+fn snapshot_defaults_to_whole_page_yaml() {
+  let cmd: Command = serde_json::from_value(json!({ "cmd": "snapshot" })).unwrap();
+  assert_eq!(cmd, Command::Snapshot { root: None, format: SnapshotFormat::Yaml });
+  assert_eq!(
+    serde_json::to_value(Command::Snapshot { root: Some("main".into()), format: SnapshotFormat::Json }).unwrap(),
+    json!({ "cmd": "snapshot", "root": "main", "format": "json" })
+  );
+  assert_eq!(SnapshotFormat::from_name("JSON"), Ok(SnapshotFormat::Json));
+  assert!(SnapshotFormat::from_name("xml").unwrap_err().starts_with("snapshot_format_invalid"));
+}
+
+#[test]
+fn query_all_defaults_to_text_only_without_limit() {
+  let cmd: Command = serde_json::from_value(json!({ "cmd": "query_all", "selector": "li" })).unwrap();
+  assert_eq!(cmd, Command::QueryAll { selector: "li".into(), attrs: vec![], limit: 0 });
+  assert_eq!(
+    serde_json::to_value(Command::BoundingBox { selector: Target::from_selector("handle=3") }).unwrap(),
+    json!({ "cmd": "bounding_box", "selector": { "handle": 3 } })
+  );
+  assert_eq!(
+    serde_json::to_value(Command::InputValue { selector: "#q".into() }).unwrap(),
+    json!({ "cmd": "input_value", "selector": "#q" })
+  );
+}
+
+#[test]
+fn option_matches_accept_strings_indexes_and_fields() {
+  let options: Vec<OptionMatch> = serde_json::from_value(json!(["blue", 2, { "label": "Red" }])).unwrap();
+  assert_eq!(
+    options,
+    vec![
+      OptionMatch::ValueOrLabel("blue".into()),
+      OptionMatch::Index(2),
+      OptionMatch::Fields { value: None, label: Some("Red".into()), index: None },
+    ]
+  );
+  let cmd = Command::SelectOption { selector: "#color".into(), options };
+  assert_eq!(
+    serde_json::to_value(&cmd).unwrap(),
+    json!({ "cmd": "select_option", "selector": "#color", "options": ["blue", 2, { "label": "Red" }] })
+  );
+  assert_eq!(
+    serde_json::to_value(Command::SetChecked { selector: "#agree".into(), checked: false }).unwrap(),
+    json!({ "cmd": "set_checked", "selector": "#agree", "checked": false })
+  );
+}
+
+#[test]
+fn waits_default_to_attached_and_animation_frames() {
+  let cmd: Command =
+    serde_json::from_value(json!({ "cmd": "wait_for_selector", "selector": "h1", "timeout_ms": 500 })).unwrap();
+  assert_eq!(
+    cmd,
+    Command::WaitForSelector { selector: "h1".into(), timeout_ms: 500, state: SelectorState::Attached }
+  );
+  assert_eq!(SelectorState::from_name("Hidden"), Ok(SelectorState::Hidden));
+  assert!(SelectorState::from_name("gone").unwrap_err().starts_with("selector_state_invalid"));
+
+  let cmd = Command::WaitForFunction {
+    js: "() => window.ready".into(),
+    polling: Polling::Interval(250),
+    timeout_ms: 5000,
+  };
+  assert_eq!(
+    serde_json::to_value(&cmd).unwrap(),
+    json!({ "cmd": "wait_for_function", "js": "() => window.ready", "polling": { "interval": 250 }, "timeout_ms": 5000 })
+  );
+  assert_eq!(Polling::from_name(""), Ok(Polling::Raf));
+  assert_eq!(Polling::from_name("mutation"), Ok(Polling::Mutation));
+  assert_eq!(Polling::from_name("250"), Ok(Polling::Interval(250)));
+  assert!(Polling::from_name("0").unwrap_err().starts_with("polling_invalid"));
+}
+
+#[test]
+fn extract_content_defaults_to_main_content_without_limit() {
+  let cmd: Command = serde_json::from_value(json!({ "cmd": "extract_content" })).unwrap();
+  assert_eq!(cmd, Command::ExtractContent { root: None, max_chars: 0 });
+  assert_eq!(
+    serde_json::to_value(Command::ExtractContent { root: Some("#post".into()), max_chars: 4000 }).unwrap(),
+    json!({ "cmd": "extract_content", "root": "#post", "max_chars": 4000 })
+  );
+}
+
+#[test]
+fn parse_ipc_message_distinguishes_events_from_responses() {
+  let event = parse_ipc_message(r#"{"event":"console","data":{"level":"log","text":"hi"}}"#).expect("event");
+  match event {
+    IpcMessage::Event(ev) => {
+      assert_eq!(ev.event, "console");
+      assert_eq!(ev.data, json!({"level":"log","text":"hi"}));
+    }
+    other => panic!("expected event, got {other:?}"),
+  }
+
+  let resp = parse_ipc_message(r#"{"id":"7","ok":true,"result":1,"error":null}"#).expect("response");
+  assert!(matches!(resp, IpcMessage::Response(env) if env.id == "7" && env.ok));
+}
@@ -10,5 +10,22 @@ fn automation_shim_defines_dispatch_and_ipc() {
     "shim should use window.ipc.postMessage"
   );
   assert!(js.contains("MutationObserver"), "shim should support DOM waits");
+  assert!(js.contains("sendEvent(\"console\""), "shim should forward console output");
+  assert!(js.contains("sendEvent: sendEvent"), "shim should expose sendEvent to init scripts");
+  assert!(js.contains("new DataTransfer()"), "shim should populate file inputs through DataTransfer");
+  assert!(js.contains("case \"snapshot\""), "shim should build accessibility snapshots");
+  assert!(js.contains("\"aria-ref=\""), "selectors should accept snapshot refs");
+  assert!(js.contains("new WeakRef(el)"), "handles should not keep detached nodes alive");
+  assert!(js.contains("\"stale_element\""), "detached handles should fail with stale_element");
+  assert!(js.contains("case \"extract_content\""), "shim should extract page content as Markdown");
+  assert!(js.contains("requestAnimationFrame"), "wait_for_function should poll on animation frames");
+  assert!(js.contains("requestSubmit"), "submit should run validation and submit handlers");
+  assert!(js.contains("sendEvent(\"url_changed\""), "shim should report same-document navigations");
+  assert!(js.contains("\"popstate\""), "back/forward within a page should count as navigation");
+  for cmd in [
+    "query_all", "count", "inner_html", "outer_html", "input_value", "is_visible", "is_enabled", "is_checked",
+    "bounding_box", "select_option", "set_checked", "focus", "blur", "clear", "submit", "wait_for_function",
+  ] {
+    assert!(js.contains(&format!("case \"{cmd}\"")), "shim should handle {cmd}");
+  }
 }
-
@@ -6,6 +6,8 @@ signal completed(request_id: int, ok: bool, result_json: String, error: String)
 @export var auto_start: bool = true
 @export var default_timeout_ms: int = 5_000
 const _M31_LEGACY_ERROR_MARKERS := ["snapshot_filename_empty"]
+# Resolves CSS selectors and `aria-ref=<ref>` snapshot refs inside payload scripts.
+const _QUERY_JS := "(selector) => (window.__gwry && window.__gwry.query ? window.__gwry.query(selector) : document.querySelector(selector))"
 
 var _browser: WryBrowser
 var _texture_browser: WryTextureBrowser
@@ -20,23 +22,28 @@ var _texture_w: int = 1024
 var _texture_h: int = 768
 var _texture_fps: int = 3
 var _snapshot_save_map: Dictionary = {}
-var _tab_urls: Array[String] = [""]
-var _active_tab_index: int = 0
-var _pending_tab_nav: Dictionary = {}
 var _open_retry_state: Dictionary = {}
+# Cookie requests whose native result is filtered, or chained into `cookies_set`, before completing.
+var _cookie_requests: Dictionary = {}
+# Native `set_input_files` requests, mapped to the file names they upload.
+var _upload_requests: Dictionary = {}
 var _next_local_request_id: int = -1
 var _texture_last_error: String = ""
 
 signal frame_png(png_bytes: PackedByteArray)
+# Re-emitted from the backend; `opener_tab_id` is -1 for `tab_new`.
+signal tab_opened(tab_id: int, url: String, opener_tab_id: int)
 
 
 func _ready() -> void:
 	_browser = WryBrowser.new()
 	add_child(_browser)
 	_browser.completed.connect(_on_browser_completed)
+	_browser.tab_opened.connect(_on_tab_opened)
 	_texture_browser = WryTextureBrowser.new()
 	add_child(_texture_browser)
 	_texture_browser.completed.connect(_on_texture_browser_completed)
+	_texture_browser.tab_opened.connect(_on_tab_opened)
 	_texture_browser.frame_png.connect(func(png_bytes: PackedByteArray) -> void:
 		frame_png.emit(png_bytes)
 	)
@@ -54,43 +61,42 @@ func _process(_delta: float) -> void:
 func _on_texture_browser_completed(request_id: int, ok: bool, result_json: String, error: String) -> void:
 	if _maybe_handle_open_retry(request_id, ok, result_json, error):
 		return
+	if _maybe_handle_cookie_request(request_id, ok, result_json, error):
+		return
+	if _maybe_handle_upload_request(request_id, ok, error):
+		return
 
 	if request_id < 0 and not ok and error.strip_edges() != "":
 		_texture_last_error = error
 	completed.emit(request_id, ok, result_json, error)
 
 
+func _on_tab_opened(tab_id: int, url: String, opener_tab_id: int) -> void:
+	tab_opened.emit(tab_id, url, opener_tab_id)
+
+
 func _on_browser_completed(request_id: int, ok: bool, result_json: String, error: String) -> void:
 	if _maybe_handle_open_retry(request_id, ok, result_json, error):
 		return
+	if _maybe_handle_cookie_request(request_id, ok, result_json, error):
+		return
+	if _maybe_handle_upload_request(request_id, ok, error):
+		return
 
 	if request_id < 0 and not ok and _view_mode and _is_view_start_error(error):
 		_started = false
 		_view_mode = false
-		_pending_tab_nav.clear()
 		_open_retry_state.clear()
 
-	if _pending_tab_nav.has(request_id):
-		var tab_index := int(_pending_tab_nav.get(request_id, _active_tab_index))
-		_pending_tab_nav.erase(request_id)
-		if ok:
-			_update_tab_url_for_index_from_result(tab_index, result_json)
-
 	if _snapshot_save_map.has(request_id):
-		var save_spec: Variant = _snapshot_save_map.get(request_id, "")
+		var raw_path := String(_snapshot_save_map.get(request_id, ""))
 		_snapshot_save_map.erase(request_id)
 		if ok:
-			var raw_path := ""
-			var tag := "snapshot"
-
-			if save_spec is Dictionary:
-				var save_info: Dictionary = save_spec
-				raw_path = String(save_info.get("path", ""))
-				tag = String(save_info.get("tag", "snapshot"))
-			else:
-				raw_path = String(save_spec)
-
-			var save_error := _save_text_to_file(raw_path, result_json, tag)
+			var content_text := result_json
+			var parsed: Variant = JSON.parse_string(result_json)
+			if parsed is String:
+				content_text = parsed
+			var save_error := _save_text_to_file(raw_path, content_text, "snapshot")
 			if save_error != "":
 				completed.emit(request_id, false, "null", save_error)
 				return
@@ -104,17 +110,14 @@ func _maybe_handle_open_retry(request_id: int, ok: bool, result_json: String, er
 
 	var state: Dictionary = _open_retry_state[request_id]
 	var origin_id := int(state.get("origin_id", request_id))
-	var tab_index := int(state.get("tab_index", _active_tab_index))
 	var timeout_ms := int(state.get("timeout_ms", 10_000))
 	var attempt := int(state.get("attempt", 0))
 	var max_attempts := int(state.get("max_attempts", 3))
 	var url := String(state.get("url", ""))
 
 	if not ok and String(error).contains("webview_not_started") and attempt < max_attempts and url != "":
-		_pending_tab_nav.erase(request_id)
 		var retry_id := _active_backend_goto(url, timeout_ms)
 		if retry_id > 0:
-			_pending_tab_nav[retry_id] = tab_index
 			state["attempt"] = attempt + 1
 			_open_retry_state.erase(request_id)
 			_open_retry_state[retry_id] = state
@@ -123,15 +126,77 @@ func _maybe_handle_open_retry(request_id: int, ok: bool, result_json: String, er
 	_open_retry_state.erase(request_id)
 
 	if origin_id != request_id:
-		_pending_tab_nav.erase(request_id)
-		if ok:
-			_update_tab_url_for_index_from_result(tab_index, result_json)
 		completed.emit(origin_id, ok, result_json, error)
 		return true
 
 	return false
 
 
+func _maybe_handle_cookie_request(request_id: int, ok: bool, result_json: String, error: String) -> bool:
+	if not _cookie_requests.has(request_id):
+		return false
+
+	var state: Dictionary = _cookie_requests[request_id]
+	_cookie_requests.erase(request_id)
+	var origin_id := int(state.get("origin_id", request_id))
+	if not ok:
+		completed.emit(origin_id, false, "null", error)
+		return true
+
+	var parsed: Variant = JSON.parse_string(result_json)
+	var cookies: Array = parsed if parsed is Array else []
+	match String(state.get("op", "")):
+		"list":
+			var domain := String(state.get("domain", ""))
+			var listed: Array = []
+			for cookie in cookies:
+				if _cookie_matches_domain(String((cookie as Dictionary).get("domain", "")), domain):
+					listed.append(cookie)
+			completed.emit(origin_id, true, JSON.stringify(listed), "")
+		"get":
+			var found: Variant = null
+			for cookie in cookies:
+				if String((cookie as Dictionary).get("name", "")) == String(state.get("name", "")):
+					found = cookie
+					break
+			completed.emit(origin_id, true, JSON.stringify(found), "")
+		"set":
+			var url := String(parsed) if parsed is String else ""
+			if not (url.begins_with("http://") or url.begins_with("https://")):
+				completed.emit(origin_id, false, "null", "cookie_page_url_unsupported: %s" % url)
+				return true
+			var param := {"name": state.get("name", ""), "value": state.get("value", ""), "url": url}
+			var set_id := _active_backend_cookies_set([param])
+			_cookie_requests[set_id] = {"op": "set_done", "origin_id": origin_id, "cookie": param}
+		"set_done":
+			completed.emit(origin_id, true, JSON.stringify(state.get("cookie", {})), "")
+	return true
+
+
+# A cookie for `example.com` (or `.example.com`) is listed under `example.com` and its subdomains.
+func _cookie_matches_domain(cookie_domain: String, domain: String) -> bool:
+	if domain == "":
+		return true
+	var bare := cookie_domain.trim_prefix(".")
+	return domain == bare or domain.ends_with("." + bare)
+
+
+# Keeps the session's `{ count, names }` result and error codes for native uploads.
+func _maybe_handle_upload_request(request_id: int, ok: bool, error: String) -> bool:
+	if not _upload_requests.has(request_id):
+		return false
+
+	var names: Array = _upload_requests[request_id]
+	_upload_requests.erase(request_id)
+	if ok:
+		completed.emit(request_id, true, JSON.stringify({"count": names.size(), "names": names}), "")
+	elif error.contains("not_file_input"):
+		completed.emit(request_id, false, "null", "upload_target_not_file_input")
+	else:
+		completed.emit(request_id, false, "null", error)
+	return true
+
+
 func _is_view_start_error(error_text: String) -> bool:
 	var raw := String(error_text).strip_edges()
 	if raw == "":
@@ -146,9 +211,11 @@ func _exit_tree() -> void:
 		_browser.stop()
 
 
-func _start_view_mode(x: int, y: int, width: int, height: int) -> bool:
+func _start_view_mode(x: int, y: int, width: int, height: int, profile: Dictionary = {}) -> bool:
 	var w := max(1, width)
 	var h := max(1, height)
+	if not profile.is_empty() and not _browser.set_profile(profile):
+		return false
 	var started_ok := _browser.start_view(x, y, w, h)
 	if not started_ok:
 		return false
@@ -166,9 +233,12 @@ func _ensure_started_with_options(options: Dictionary = {}) -> bool:
 	if _started:
 		return true
 
+	var profile_data := options.get("profile", null)
+	var profile: Dictionary = profile_data if profile_data is Dictionary else {}
+
 	var texture_data := options.get("texture", null)
 	if texture_data is Dictionary:
-		return _start_texture_mode(texture_data as Dictionary)
+		return _start_texture_mode(texture_data as Dictionary, profile)
 
 	var rect_data := options.get("view_rect", null)
 	if rect_data is Dictionary:
@@ -177,16 +247,19 @@ func _ensure_started_with_options(options: Dictionary = {}) -> bool:
 		var y := int(view_rect.get("y", _view_y))
 		var width := int(view_rect.get("width", _view_w))
 		var height := int(view_rect.get("height", _view_h))
-		return _start_view_mode(x, y, width, height)
+		return _start_view_mode(x, y, width, height, profile)
 
 	if options.has("x") or options.has("y") or options.has("width") or options.has("height"):
 		return _start_view_mode(
 			int(options.get("x", _view_x)),
 			int(options.get("y", _view_y)),
 			int(options.get("width", _view_w)),
-			int(options.get("height", _view_h))
+			int(options.get("height", _view_h)),
+			profile
 		)
 
+	if not profile.is_empty() and not _browser.set_profile(profile):
+		return false
 	_started = _browser.start()
 	_view_mode = false
 	_texture_mode = false
@@ -197,7 +270,7 @@ func _ensure_started() -> bool:
 	return _ensure_started_with_options({})
 
 
-func _start_texture_mode(texture_options: Dictionary = {}) -> bool:
+func _start_texture_mode(texture_options: Dictionary = {}, profile: Dictionary = {}) -> bool:
 	var width := int(texture_options.get("width", _texture_w))
 	var height := int(texture_options.get("height", _texture_h))
 	var fps := int(texture_options.get("fps", _texture_fps))
@@ -207,6 +280,8 @@ func _start_texture_mode(texture_options: Dictionary = {}) -> bool:
 	fps = max(1, fps)
 
 	_texture_last_error = ""
+	if not profile.is_empty() and not _texture_browser.set_profile(profile):
+		return false
 	var started_ok := _texture_browser.start_texture(width, height, fps)
 	if not started_ok:
 		return false
@@ -236,6 +311,47 @@ func _active_backend_eval(script: String, timeout_ms: int) -> int:
 	return _browser.eval(script, max(0, timeout_ms))
 
 
+func _active_backend_snapshot(timeout_ms: int) -> int:
+	var resolved_timeout: int = max(0, _timeout_value(timeout_ms))
+	if _using_texture_mode():
+		return _texture_browser.snapshot("", "yaml", resolved_timeout)
+	return _browser.snapshot("", "yaml", resolved_timeout)
+
+
+func _active_backend_select_option(selector: String, options: Array, timeout_ms: int) -> int:
+	var resolved_timeout: int = max(0, _timeout_value(timeout_ms))
+	if _using_texture_mode():
+		return _texture_browser.select_option(selector, options, resolved_timeout)
+	return _browser.select_option(selector, options, resolved_timeout)
+
+
+func _active_backend_set_checked(selector: String, checked: bool, timeout_ms: int) -> int:
+	var resolved_timeout: int = max(0, _timeout_value(timeout_ms))
+	if _using_texture_mode():
+		return _texture_browser.set_checked(selector, checked, resolved_timeout)
+	return _browser.set_checked(selector, checked, resolved_timeout)
+
+
+func _active_backend_cookies_get(timeout_ms: int) -> int:
+	var resolved_timeout: int = max(0, _timeout_value(timeout_ms))
+	if _using_texture_mode():
+		return _texture_browser.cookies_get(PackedStringArray(), resolved_timeout)
+	return _browser.cookies_get(PackedStringArray(), resolved_timeout)
+
+
+func _active_backend_cookies_set(cookies: Array) -> int:
+	if _using_texture_mode():
+		return _texture_browser.cookies_set(cookies)
+	return _browser.cookies_set(cookies)
+
+
+func _active_backend_cookies_clear(filter: Dictionary, timeout_ms: int) -> int:
+	var resolved_timeout: int = max(0, _timeout_value(timeout_ms))
+	if _using_texture_mode():
+		return _texture_browser.cookies_clear(filter, resolved_timeout)
+	return _browser.cookies_clear(filter, resolved_timeout)
+
+
 func _next_local_id() -> int:
 	var request_id := _next_local_request_id
 	_next_local_request_id -= 1
@@ -295,30 +411,6 @@ func _save_text_to_file(target_path: String, content_text: String, error_tag: St
 	return ""
 
 
-func _read_text_from_file(source_path: String, error_tag: String) -> Dictionary:
-	var output_path := _normalize_output_path(source_path)
-	if output_path == "":
-		return {"ok": false, "error": "%s_filename_empty" % error_tag}
-
-	if not FileAccess.file_exists(output_path):
-		return {"ok": false, "error": "%s_file_not_found:%s" % [error_tag, output_path]}
-
-	var file := FileAccess.open(output_path, FileAccess.READ)
-	if file == null:
-		return {"ok": false, "error": "%s_open_error:%s" % [error_tag, output_path]}
-
-	return {"ok": true, "path": output_path, "text": file.get_as_text()}
-
-
-func _parse_json_text(source_text: String, error_tag: String) -> Dictionary:
-	var parser := JSON.new()
-	var parse_result := parser.parse(source_text)
-	if parse_result != OK:
-		return {"ok": false, "error": "%s_invalid_json" % error_tag}
-
-	return {"ok": true, "value": parser.data}
-
-
 func _parse_result_json(result_json: String) -> Variant:
 	var parser := JSON.new()
 	if parser.parse(result_json) != OK:
@@ -326,67 +418,6 @@ func _parse_result_json(result_json: String) -> Variant:
 	return parser.data
 
 
-func _update_tab_url_for_index_from_result(tab_index: int, result_json: String) -> void:
-	_ensure_tab_state()
-	if tab_index < 0 or tab_index >= _tab_urls.size():
-		return
-
-	var parsed: Variant = _parse_result_json(result_json)
-	if parsed is String:
-		_tab_urls[tab_index] = String(parsed)
-		return
-
-	if parsed is Dictionary:
-		var data: Dictionary = parsed
-		if data.has("url"):
-			_tab_urls[tab_index] = String(data.get("url", ""))
-
-
-func _ensure_tab_state() -> void:
-	if _tab_urls.is_empty():
-		_tab_urls.append("")
-		_active_tab_index = 0
-
-	_active_tab_index = clampi(_active_tab_index, 0, _tab_urls.size() - 1)
-
-
-func _build_tab_result() -> Dictionary:
-	_ensure_tab_state()
-	var tabs: Array = []
-	for idx in range(_tab_urls.size()):
-		tabs.append({
-			"index": idx,
-			"url": _tab_urls[idx],
-			"active": idx == _active_tab_index,
-		})
-
-	return {
-		"active_index": _active_tab_index,
-		"tab_count": _tab_urls.size(),
-		"tabs": tabs,
-	}
-
-
-func _set_tab_url_for_active(url: String) -> void:
-	_ensure_tab_state()
-	_tab_urls[_active_tab_index] = url
-
-
-func _navigate_active_tab(timeout_ms: int) -> int:
-	_ensure_tab_state()
-	var url := _tab_urls[_active_tab_index]
-	if url == "":
-		return _local_success(_build_tab_result())
-
-	if not _ensure_started():
-		return _local_error("start_error")
-
-	var request_id := _active_backend_goto(url, _timeout_value(timeout_ms))
-	if request_id > 0:
-		_pending_tab_nav[request_id] = _active_tab_index
-	return request_id
-
-
 func _storage_area_js(area_name: String) -> String:
 	return "window.%s" % area_name
 
@@ -499,52 +530,6 @@ func _resolve_local_file_path(file_path: String) -> String:
 	return file_path
 
 
-func _mime_from_extension(file_path: String) -> String:
-	match file_path.get_extension().to_lower():
-		"txt":
-			return "text/plain"
-		"json":
-			return "application/json"
-		"html", "htm":
-			return "text/html"
-		"png":
-			return "image/png"
-		"jpg", "jpeg":
-			return "image/jpeg"
-		"webp":
-			return "image/webp"
-		"gif":
-			return "image/gif"
-		"svg":
-			return "image/svg+xml"
-		"pdf":
-			return "application/pdf"
-		"csv":
-			return "text/csv"
-		"zip":
-			return "application/zip"
-		_:
-			return "application/octet-stream"
-
-
-func _file_to_upload_entry(file_path: String) -> Dictionary:
-	var resolved := _resolve_local_file_path(file_path)
-	if not FileAccess.file_exists(resolved):
-		return {"ok": false, "error": "upload_file_not_found:%s" % file_path}
-
-	var file := FileAccess.open(resolved, FileAccess.READ)
-	if file == null:
-		return {"ok": false, "error": "upload_open_error:%s" % file_path}
-
-	var bytes := file.get_buffer(file.get_length())
-	return {
-		"ok": true,
-		"name": resolved.get_file(),
-		"mime": _mime_from_extension(resolved),
-		"base64": Marshalls.raw_to_base64(bytes),
-	}
-
-
 func _collect_upload_paths(file: Variant) -> Array[String]:
 	var paths: Array[String] = []
 
@@ -596,7 +581,7 @@ func _run_eval(script: String, timeout_ms: int = -1) -> int:
 
 func _eval_with_payload(js_body: String, payload: Dictionary, timeout_ms: int = -1) -> int:
 	var payload_json := JSON.stringify(payload)
-	var script := "(() => { const payload = %s; %s })()" % [payload_json, js_body]
+	var script := "(() => { const payload = %s; const query = %s; %s })()" % [payload_json, _QUERY_JS, js_body]
 	return _run_eval(script, timeout_ms)
 
 
@@ -607,14 +592,10 @@ func open(url: String, options: Dictionary = {}) -> int:
 			return _local_error(_texture_last_error)
 		return _local_error("start_error")
 
-	_set_tab_url_for_active(url)
 	var request_id := _active_backend_goto(url, timeout_ms)
 	if request_id > 0:
-		var tab_index := _active_tab_index
-		_pending_tab_nav[request_id] = tab_index
 		_open_retry_state[request_id] = {
 			"origin_id": request_id,
-			"tab_index": tab_index,
 			"url": url,
 			"timeout_ms": max(0, timeout_ms),
 			"attempt": 0,
@@ -627,7 +608,6 @@ func close() -> int:
 	_started = false
 	_view_mode = false
 	_texture_mode = false
-	_pending_tab_nav.clear()
 	_open_retry_state.clear()
 	if is_instance_valid(_texture_browser):
 		_texture_browser.stop()
@@ -669,7 +649,7 @@ func click(ref: String, button: String = "left", timeout_ms: int = -1) -> int:
 
 	return _eval_with_payload(
 		"""
-		const element = document.querySelector(payload.ref);
+		const element = query(payload.ref);
 		if (!element) {
 		  throw new Error("not_found");
 		}
@@ -688,7 +668,7 @@ func click(ref: String, button: String = "left", timeout_ms: int = -1) -> int:
 func dblclick(ref: String, button: String = "left", timeout_ms: int = -1) -> int:
 	return _eval_with_payload(
 		"""
-		const element = document.querySelector(payload.ref);
+		const element = query(payload.ref);
 		if (!element) {
 		  throw new Error("not_found");
 		}
@@ -713,8 +693,8 @@ func fill(ref: String, text: String, timeout_ms: int = -1) -> int:
 func drag(start_ref: String, end_ref: String, timeout_ms: int = -1) -> int:
 	return _eval_with_payload(
 		"""
-		const source = document.querySelector(payload.start_ref);
-		const target = document.querySelector(payload.end_ref);
+		const source = query(payload.start_ref);
+		const target = query(payload.end_ref);
 		if (!source || !target) {
 		  throw new Error("not_found");
 		}
@@ -732,7 +712,7 @@ func drag(start_ref: String, end_ref: String, timeout_ms: int = -1) -> int:
 func hover(ref: String, timeout_ms: int = -1) -> int:
 	return _eval_with_payload(
 		"""
-		const element = document.querySelector(payload.ref);
+		const element = query(payload.ref);
 		if (!element) {
 		  throw new Error("not_found");
 		}
@@ -747,139 +727,55 @@ func hover(ref: String, timeout_ms: int = -1) -> int:
 
 
 func select(ref: String, value: String, timeout_ms: int = -1) -> int:
-	return _eval_with_payload(
-		"""
-		const element = document.querySelector(payload.ref);
-		if (!element) {
-		  throw new Error("not_found");
-		}
-		element.value = String(payload.value ?? "");
-		element.dispatchEvent(new Event("input", { bubbles: true }));
-		element.dispatchEvent(new Event("change", { bubbles: true }));
-		return true;
-		""",
-		{"ref": ref, "value": value},
-		timeout_ms
-	)
+	if not _ensure_started():
+		return _local_error("start_error")
+	return _active_backend_select_option(ref, [value], timeout_ms)
 
 
+# Sets the files of the `ref` file input (the focused one when empty) through the native upload.
 func upload(file: Variant, timeout_ms: int = -1, ref: String = "") -> int:
 	var file_paths := _collect_upload_paths(file)
 	if file_paths.is_empty():
 		return _local_error("upload_empty")
 
-	var entries: Array = []
+	var paths := PackedStringArray()
+	var names: Array = []
 	for file_path in file_paths:
-		var entry := _file_to_upload_entry(file_path)
-		if not bool(entry.get("ok", false)):
-			return _local_error(String(entry.get("error", "upload_unknown_error")))
-		entry.erase("ok")
-		entries.append(entry)
+		var resolved := _resolve_local_file_path(file_path)
+		if not FileAccess.file_exists(resolved):
+			return _local_error("upload_file_not_found:%s" % file_path)
+		paths.append(resolved)
+		names.append(resolved.get_file())
 
-	return _eval_with_payload(
-		"""
-		const files = Array.isArray(payload.files) ? payload.files : [];
-		if (files.length === 0) {
-		  throw new Error("upload_empty");
-		}
-
-		let target = null;
-		if (payload.ref && String(payload.ref) !== "") {
-		  target = document.querySelector(String(payload.ref));
-		} else {
-		  target = document.activeElement;
-		}
-
-		if (!target || String(target.tagName || "").toLowerCase() !== "input" || String(target.type || "").toLowerCase() !== "file") {
-		  throw new Error("upload_target_not_file_input");
-		}
-
-		if (typeof DataTransfer !== "function") {
-		  throw new Error("upload_datatransfer_unavailable");
-		}
-
-		const transfer = new DataTransfer();
-		for (const spec of files) {
-		  const binary = atob(String(spec.base64 || ""));
-		  const bytes = new Uint8Array(binary.length);
-		  for (let i = 0; i < binary.length; i += 1) {
-		    bytes[i] = binary.charCodeAt(i);
-		  }
-		  const fileObj = new File(
-		    [bytes],
-		    String(spec.name || "upload.bin"),
-		    { type: String(spec.mime || "application/octet-stream") }
-		  );
-		  transfer.items.add(fileObj);
-		}
-
-		target.files = transfer.files;
-		target.dispatchEvent(new Event("input", { bubbles: true }));
-		target.dispatchEvent(new Event("change", { bubbles: true }));
-
-		return {
-		  count: transfer.files.length,
-		  names: Array.from(transfer.files).map((item) => item.name),
-		};
-		""",
-		{"ref": ref, "files": entries},
-		timeout_ms
-	)
+	if not _ensure_started():
+		return _local_error("start_error")
+	var selector := ref if ref != "" else ":focus"
+	var resolved_timeout: int = max(0, _timeout_value(timeout_ms))
+	var request_id: int
+	if _using_texture_mode():
+		request_id = _texture_browser.set_input_files(selector, paths, resolved_timeout)
+	else:
+		request_id = _browser.set_input_files(selector, paths, resolved_timeout)
+	_upload_requests[request_id] = names
+	return request_id
 
 
 func check(ref: String, timeout_ms: int = -1) -> int:
-	return _eval_with_payload(
-		"""
-		const element = document.querySelector(payload.ref);
-		if (!element) {
-		  throw new Error("not_found");
-		}
-		element.checked = true;
-		element.dispatchEvent(new Event("input", { bubbles: true }));
-		element.dispatchEvent(new Event("change", { bubbles: true }));
-		return true;
-		""",
-		{"ref": ref},
-		timeout_ms
-	)
+	if not _ensure_started():
+		return _local_error("start_error")
+	return _active_backend_set_checked(ref, true, timeout_ms)
 
 
 func uncheck(ref: String, timeout_ms: int = -1) -> int:
-	return _eval_with_payload(
-		"""
-		const element = document.querySelector(payload.ref);
-		if (!element) {
-		  throw new Error("not_found");
-		}
-		element.checked = false;
-		element.dispatchEvent(new Event("input", { bubbles: true }));
-		element.dispatchEvent(new Event("change", { bubbles: true }));
-		return true;
-		""",
-		{"ref": ref},
-		timeout_ms
-	)
-
-
-func _snapshot_eval_script() -> String:
-	return """
-	(() => {
-	  const candidates = Array.from(document.querySelectorAll("a,button,input,textarea,select,[role],h1,h2,h3,p"));
-	  return candidates.slice(0, 300).map((element, index) => {
-	    const ref = `gwry_ref_${index + 1}`;
-	    element.setAttribute("data-gwry-ref", ref);
-	    return {
-	      ref,
-	      tag: String(element.tagName || "").toLowerCase(),
-	      text: String((element.innerText || element.value || "")).slice(0, 120),
-	    };
-	  });
-	})()
-	"""
+	if not _ensure_started():
+		return _local_error("start_error")
+	return _active_backend_set_checked(ref, false, timeout_ms)
 
 
 func snapshot(filename: String = "", timeout_ms: int = -1) -> int:
-	var request_id := _run_eval(_snapshot_eval_script(), timeout_ms)
+	if not _ensure_started():
+		return _local_error("start_error")
+	var request_id := _active_backend_snapshot(timeout_ms)
 	if filename != "" and request_id > 0:
 		_snapshot_save_map[request_id] = filename
 	return request_id
@@ -891,7 +787,7 @@ func eval(func_or_expr: String, ref: String = "", timeout_ms: int = -1) -> int:
 
 	return _eval_with_payload(
 		"""
-		const element = document.querySelector(payload.ref);
+		const element = query(payload.ref);
 		if (!element) {
 		  throw new Error("not_found");
 		}
@@ -906,68 +802,18 @@ func eval(func_or_expr: String, ref: String = "", timeout_ms: int = -1) -> int:
 	)
 
 
-func _set_dialog_mode(mode: String, prompt_text: String, timeout_ms: int = -1) -> int:
-	return _eval_with_payload(
-		"""
-		const mode = String(payload.mode ?? "dismiss");
-		const promptText = String(payload.prompt_text ?? "");
-
-		if (!window.__gwry_dialog) {
-		  window.__gwry_dialog = {
-		    mode: "dismiss",
-		    promptText: "",
-		    last: null,
-		  };
-
-		  const original = {
-		    alert: typeof window.alert === "function" ? window.alert.bind(window) : null,
-		    confirm: typeof window.confirm === "function" ? window.confirm.bind(window) : null,
-		    prompt: typeof window.prompt === "function" ? window.prompt.bind(window) : null,
-		  };
-		  window.__gwry_dialog.original = original;
-
-		  window.alert = function(message) {
-		    window.__gwry_dialog.last = {
-		      type: "alert",
-		      message: String(message ?? ""),
-		    };
-		    return undefined;
-		  };
-
-		  window.confirm = function(message) {
-		    window.__gwry_dialog.last = {
-		      type: "confirm",
-		      message: String(message ?? ""),
-		    };
-		    return window.__gwry_dialog.mode === "accept";
-		  };
-
-		  window.prompt = function(message, defaultValue) {
-		    window.__gwry_dialog.last = {
-		      type: "prompt",
-		      message: String(message ?? ""),
-		      default_value: defaultValue == null ? null : String(defaultValue),
-		    };
-		    if (window.__gwry_dialog.mode === "accept") {
-		      if (window.__gwry_dialog.promptText !== "") {
-		        return window.__gwry_dialog.promptText;
-		      }
-		      return defaultValue == null ? "" : String(defaultValue);
-		    }
-		    return null;
-		  };
-		}
-
-		window.__gwry_dialog.mode = mode;
-		window.__gwry_dialog.promptText = promptText;
-		return {
-		  mode: window.__gwry_dialog.mode,
-		  promptText: window.__gwry_dialog.promptText,
-		};
-		""",
-		{"mode": mode, "prompt_text": prompt_text},
-		timeout_ms
-	)
+# Sets the native dialog policy, so every later alert/confirm/prompt in any tab is answered.
+func _set_dialog_mode(mode: String, prompt_text: String, _timeout_ms: int = -1) -> int:
+	if not _ensure_started():
+		return _local_error("start_error")
+	var applied: bool
+	if _using_texture_mode():
+		applied = _texture_browser.set_dialog_policy(mode, prompt_text)
+	else:
+		applied = _browser.set_dialog_policy(mode, prompt_text)
+	if not applied:
+		return _local_error("dialog_policy_invalid: %s" % mode)
+	return _local_success({"mode": mode, "promptText": prompt_text})
 
 
 func dialog_accept(prompt: String = "", timeout_ms: int = -1) -> int:
@@ -997,15 +843,30 @@ func resize(width: int, height: int, _timeout_ms: int = -1) -> int:
 
 
 func go_back(timeout_ms: int = -1) -> int:
-	return _run_eval("history.back(); true", timeout_ms)
+	if not _ensure_started():
+		return _local_error("start_error")
+	var resolved_timeout: int = max(0, _timeout_value(timeout_ms))
+	if _using_texture_mode():
+		return _texture_browser.go_back(resolved_timeout)
+	return _browser.go_back(resolved_timeout)
 
 
 func go_forward(timeout_ms: int = -1) -> int:
-	return _run_eval("history.forward(); true", timeout_ms)
+	if not _ensure_started():
+		return _local_error("start_error")
+	var resolved_timeout: int = max(0, _timeout_value(timeout_ms))
+	if _using_texture_mode():
+		return _texture_browser.go_forward(resolved_timeout)
+	return _browser.go_forward(resolved_timeout)
 
 
 func reload(timeout_ms: int = -1) -> int:
-	return _run_eval("location.reload(); true", timeout_ms)
+	if not _ensure_started():
+		return _local_error("start_error")
+	var resolved_timeout: int = max(0, _timeout_value(timeout_ms))
+	if _using_texture_mode():
+		return _texture_browser.reload(resolved_timeout)
+	return _browser.reload(resolved_timeout)
 
 
 func press(key: String, timeout_ms: int = -1) -> int:
@@ -1117,329 +978,129 @@ func mouse_wheel(delta_x: float, delta_y: float, timeout_ms: int = -1) -> int:
 	)
 
 
+# Captures the page (or the `ref` element) as PNG; writes it to `filename` when given.
 func screenshot(ref: String = "", filename: String = "") -> int:
-	var request_id := _eval_with_payload(
-		"""
-		const target = payload.ref ? document.querySelector(String(payload.ref)) : document.documentElement;
-		if (!target) {
-		  throw new Error("not_found");
-		}
-
-		const rect = target.getBoundingClientRect();
-		const width = Math.max(1, Math.ceil(rect.width || target.clientWidth || window.innerWidth || 1));
-		const height = Math.max(1, Math.ceil(rect.height || target.clientHeight || window.innerHeight || 1));
-		const xml = new XMLSerializer().serializeToString(target.cloneNode(true));
-		const svg = `<svg xmlns="http://www.w3.org/2000/svg" width="${width}" height="${height}"><foreignObject x="0" y="0" width="100%" height="100%">${xml}</foreignObject></svg>`;
-		const dataUrl = "data:image/svg+xml;base64," + btoa(unescape(encodeURIComponent(svg)));
-		return {
-		  format: "svg_data_url",
-		  data_url: dataUrl,
-		  width,
-		  height,
-		};
-		""",
-		{"ref": ref},
-		default_timeout_ms
-	)
-
-	if filename != "" and request_id > 0:
-		_snapshot_save_map[request_id] = {"path": filename, "tag": "screenshot"}
-	return request_id
+	if not _ensure_started():
+		return _local_error("start_error")
+	var options := {"timeout_ms": default_timeout_ms}
+	if ref != "":
+		options["selector"] = ref
+	if filename != "":
+		options["path"] = filename
+	if _using_texture_mode():
+		return _texture_browser.screenshot(options)
+	return _browser.screenshot(options)
 
 
+# Prints the page to a PDF file with the default paper settings.
 func pdf(filename: String = "page.pdf") -> int:
 	if filename.strip_edges() == "":
 		return _local_error("pdf_filename_empty")
-
-	var request_id := _run_eval(
-		"""
-		(() => {
-		  return {
-		    format: "html_json",
-		    url: location.href,
-		    title: document.title || "",
-		    html: document.documentElement ? document.documentElement.outerHTML : "",
-		  };
-		})()
-		""",
-		default_timeout_ms
-	)
-	if request_id > 0:
-		_snapshot_save_map[request_id] = {"path": filename, "tag": "pdf"}
-	return request_id
+	if not _ensure_started():
+		return _local_error("start_error")
+	if _using_texture_mode():
+		return _texture_browser.pdf(filename, {})
+	return _browser.pdf(filename, {})
 
 
 func tab_list() -> int:
-	return _local_success(_build_tab_result())
-
-
-func tab_new(url: String = "", timeout_ms: int = -1) -> int:
-	_ensure_tab_state()
-	_tab_urls.append(url)
-	_active_tab_index = _tab_urls.size() - 1
-
-	if url == "":
-		return _local_success(_build_tab_result())
-
-	return _navigate_active_tab(timeout_ms)
-
-
-func tab_close(index: int = -1, timeout_ms: int = -1) -> int:
-	_ensure_tab_state()
-	var close_index := _active_tab_index
-	if index >= 0:
-		close_index = index
-
-	if close_index < 0 or close_index >= _tab_urls.size():
-		return _local_error("tab_index_out_of_range")
+	if not _ensure_started():
+		return _local_error("start_error")
+	if _using_texture_mode():
+		return _texture_browser.tab_list()
+	return _browser.tab_list()
 
-	_tab_urls.remove_at(close_index)
-	if _tab_urls.is_empty():
-		_tab_urls.append("")
-		_active_tab_index = 0
-		return _local_success(_build_tab_result())
 
-	if _active_tab_index >= close_index:
-		_active_tab_index = max(0, _active_tab_index - 1)
+# Opens a background tab; `tab_select(id)` makes it the target of later commands.
+func tab_new(url: String = "") -> int:
+	if not _ensure_started():
+		return _local_error("start_error")
+	if _using_texture_mode():
+		return _texture_browser.tab_new(url)
+	return _browser.tab_new(url)
 
-	_active_tab_index = clampi(_active_tab_index, 0, _tab_urls.size() - 1)
-	return _navigate_active_tab(timeout_ms)
 
+func tab_close(tab_id: int = -1) -> int:
+	if not _ensure_started():
+		return _local_error("start_error")
+	if _using_texture_mode():
+		return _texture_browser.tab_close(tab_id)
+	return _browser.tab_close(tab_id)
 
-func tab_select(index: int, timeout_ms: int = -1) -> int:
-	_ensure_tab_state()
-	if index < 0 or index >= _tab_urls.size():
-		return _local_error("tab_index_out_of_range")
 
-	_active_tab_index = index
-	return _navigate_active_tab(timeout_ms)
+func tab_select(tab_id: int) -> int:
+	if not _ensure_started():
+		return _local_error("start_error")
+	if _using_texture_mode():
+		return _texture_browser.tab_select(tab_id)
+	return _browser.tab_select(tab_id)
 
 
+# Saves the profile's cookies and the current origin's `localStorage` as a storage-state file.
 func state_save(filename: String = "state.json") -> int:
 	if filename.strip_edges() == "":
 		return _local_error("state_save_filename_empty")
-
-	var request_id := _eval_with_payload(
-		"""
-		const readStorage = (store) => {
-		  const out = {};
-		  if (!store) {
-		    return out;
-		  }
-		  for (let i = 0; i < store.length; i += 1) {
-		    const key = store.key(i);
-		    if (key == null) {
-		      continue;
-		    }
-		    out[String(key)] = String(store.getItem(key) ?? "");
-		  }
-		  return out;
-		};
-
-		return {
-		  schema: "gwry-session-state-v1",
-		  saved_at_ms: Date.now(),
-		  url: location.href,
-		  title: document.title || "",
-		  cookies: String(document.cookie || ""),
-		  local_storage: readStorage(window.localStorage),
-		  session_storage: readStorage(window.sessionStorage),
-		  tabs: payload.tabs,
-		  active_index: Number(payload.active_index || 0),
-		};
-		""",
-		{"tabs": _tab_urls, "active_index": _active_tab_index},
-		default_timeout_ms
-	)
-
-	if request_id > 0:
-		_snapshot_save_map[request_id] = {"path": filename, "tag": "state_save"}
-	return request_id
+	if not _ensure_started():
+		return _local_error("start_error")
+	var timeout_ms: int = max(0, default_timeout_ms)
+	if _using_texture_mode():
+		return _texture_browser.storage_state_save(filename, PackedStringArray(), timeout_ms)
+	return _browser.storage_state_save(filename, PackedStringArray(), timeout_ms)
 
 
+# Restores a `state_save` file: cookies right away, `localStorage` when each origin next loads.
 func state_load(filename: String) -> int:
-	var file_result := _read_text_from_file(filename, "state_load")
-	if not bool(file_result.get("ok", false)):
-		return _local_error(String(file_result.get("error", "state_load_read_error")))
-
-	var parse_result := _parse_json_text(String(file_result.get("text", "")), "state_load")
-	if not bool(parse_result.get("ok", false)):
-		return _local_error(String(parse_result.get("error", "state_load_invalid_json")))
-
-	var state_value: Variant = parse_result.get("value", {})
-	if not (state_value is Dictionary):
-		return _local_error("state_load_invalid_payload")
-
-	var state: Dictionary = state_value
-	var restored_tabs: Array[String] = []
-	if state.get("tabs", null) is Array:
-		for entry in state.get("tabs", []):
-			restored_tabs.append(String(entry))
-
-	if restored_tabs.is_empty():
-		restored_tabs.append(String(state.get("url", "")))
-
-	_tab_urls = restored_tabs
-	_active_tab_index = clampi(int(state.get("active_index", 0)), 0, _tab_urls.size() - 1)
-
-	var apply_state: Dictionary = state.duplicate(true)
-	if not apply_state.has("url"):
-		apply_state["url"] = _tab_urls[_active_tab_index]
-
-	return _eval_with_payload(
-		"""
-		const state = (payload.state && typeof payload.state === "object") ? payload.state : {};
-
-		if (state.local_storage && typeof state.local_storage === "object" && window.localStorage) {
-		  window.localStorage.clear();
-		  for (const [key, value] of Object.entries(state.local_storage)) {
-		    window.localStorage.setItem(String(key), String(value));
-		  }
-		}
-
-		if (state.session_storage && typeof state.session_storage === "object" && window.sessionStorage) {
-		  window.sessionStorage.clear();
-		  for (const [key, value] of Object.entries(state.session_storage)) {
-		    window.sessionStorage.setItem(String(key), String(value));
-		  }
-		}
-
-		if (typeof state.cookies === "string" && state.cookies.trim() !== "") {
-		  const cookiePairs = state.cookies.split(";");
-		  for (const raw of cookiePairs) {
-		    const pair = String(raw || "").trim();
-		    if (pair !== "") {
-		      document.cookie = pair;
-		    }
-		  }
-		}
-
-		const result = {
-		  applied: true,
-		  navigated: false,
-		  url: location.href,
-		};
-
-		if (typeof state.url === "string" && state.url !== "" && state.url !== location.href) {
-		  result.navigated = true;
-		  result.url = state.url;
-		  location.href = state.url;
-		}
-
-		return result;
-		""",
-		{"state": apply_state},
-		default_timeout_ms
-	)
+	var path := _normalize_output_path(filename)
+	if path == "":
+		return _local_error("state_load_filename_empty")
+	if not FileAccess.file_exists(path):
+		return _local_error("state_load_file_not_found:%s" % path)
+	if not _ensure_started():
+		return _local_error("start_error")
+	if _using_texture_mode():
+		return _texture_browser.storage_state_load(path)
+	return _browser.storage_state_load(path)
 
 
+# Cookies come from the native cookie manager, so `HttpOnly` ones are included.
 func cookie_list(domain: String = "") -> int:
-	return _eval_with_payload(
-		"""
-		const all = String(document.cookie || "");
-		if (all === "") {
-		  return [];
-		}
-		return all.split(";").map((entry) => {
-		  const trimmed = String(entry || "").trim();
-		  const eqIndex = trimmed.indexOf("=");
-		  const name = eqIndex >= 0 ? trimmed.slice(0, eqIndex).trim() : trimmed;
-		  const value = eqIndex >= 0 ? trimmed.slice(eqIndex + 1) : "";
-		  return {
-		    name,
-		    value,
-		    domain: payload.domain || location.hostname || "",
-		  };
-		});
-		""",
-		{"domain": domain},
-		default_timeout_ms
-	)
+	if not _ensure_started():
+		return _local_error("start_error")
+	var request_id := _active_backend_cookies_get(default_timeout_ms)
+	_cookie_requests[request_id] = {"op": "list", "domain": domain.strip_edges()}
+	return request_id
 
 
 func cookie_get(name: String) -> int:
-	return _eval_with_payload(
-		"""
-		const lookup = String(payload.name || "");
-		const all = String(document.cookie || "");
-		for (const entry of all.split(";")) {
-		  const trimmed = String(entry || "").trim();
-		  const eqIndex = trimmed.indexOf("=");
-		  const key = eqIndex >= 0 ? trimmed.slice(0, eqIndex).trim() : trimmed;
-		  if (key === lookup) {
-		    return {
-		      name: key,
-		      value: eqIndex >= 0 ? trimmed.slice(eqIndex + 1) : "",
-		    };
-		  }
-		}
-		return null;
-		""",
-		{"name": name},
-		default_timeout_ms
-	)
+	if not _ensure_started():
+		return _local_error("start_error")
+	var request_id := _active_backend_cookies_get(default_timeout_ms)
+	_cookie_requests[request_id] = {"op": "get", "name": name}
+	return request_id
 
 
+# Sets a cookie for the current page URL.
 func cookie_set(name: String, value: String) -> int:
-	return _eval_with_payload(
-		"""
-		const name = String(payload.name || "");
-		if (name === "") {
-		  throw new Error("cookie_name_empty");
-		}
-		const value = String(payload.value || "");
-		document.cookie = `${name}=${value}; path=/`;
-		return {
-		  name,
-		  value,
-		};
-		""",
-		{"name": name, "value": value},
-		default_timeout_ms
-	)
+	if name == "":
+		return _local_error("cookie_name_empty")
+	var request_id := _run_eval("location.href", default_timeout_ms)
+	if request_id > 0:
+		_cookie_requests[request_id] = {"op": "set", "name": name, "value": value}
+	return request_id
 
 
 func cookie_delete(name: String) -> int:
-	return _eval_with_payload(
-		"""
-		const name = String(payload.name || "");
-		if (name === "") {
-		  throw new Error("cookie_name_empty");
-		}
-		document.cookie = `${name}=; Max-Age=0; path=/`;
-		return {
-		  name,
-		  deleted: true,
-		};
-		""",
-		{"name": name},
-		default_timeout_ms
-	)
+	if name == "":
+		return _local_error("cookie_name_empty")
+	if not _ensure_started():
+		return _local_error("start_error")
+	return _active_backend_cookies_clear({"name": name}, default_timeout_ms)
 
 
 func cookie_clear() -> int:
-	return _run_eval(
-		"""
-		(() => {
-		  const all = String(document.cookie || "");
-		  let count = 0;
-		  for (const entry of all.split(";")) {
-		    const trimmed = String(entry || "").trim();
-		    if (trimmed === "") {
-		      continue;
-		    }
-		    const eqIndex = trimmed.indexOf("=");
-		    const key = eqIndex >= 0 ? trimmed.slice(0, eqIndex).trim() : trimmed;
-		    if (key !== "") {
-		      document.cookie = `${key}=; Max-Age=0; path=/`;
-		      count += 1;
-		    }
-		  }
-		  return { cleared: true, count };
-		})()
-		""",
-		default_timeout_ms
-	)
+	if not _ensure_started():
+		return _local_error("start_error")
+	return _active_backend_cookies_clear({}, default_timeout_ms)
 
 
 func localstorage_list() -> int:
@@ -36,20 +36,18 @@ func _run_capture_storage_tabs_assertions(session: Node, pending: Dictionary, te
 	var output_base = "user://test_outputs/runtime"
 	DirAccess.make_dir_recursive_absolute(ProjectSettings.globalize_path(output_base))
 
-	var screenshot_path = "%s/snapshot.json" % output_base
+	var screenshot_path = "%s/screenshot.png" % output_base
 	var screenshot_id = session.screenshot("", screenshot_path)
 	var screenshot_resp = await T.wait_for_completed(self, pending, screenshot_id)
 	if not T.require_ok_response(self, screenshot_resp, "screenshot"):
 		return -1
-	var screenshot_file = T.read_text_file(screenshot_path)
-	if not T.require_true(self, bool(screenshot_file.ok), "screenshot file missing"):
+	var screenshot_json: Variant = T.parse_json_or_null(String(screenshot_resp.result_json))
+	if not T.require_true(self, screenshot_json is Dictionary, "screenshot result should be dictionary"):
 		return -1
-	var screenshot_json: Variant = T.parse_json_or_null(String(screenshot_file.text))
-	if not T.require_true(self, screenshot_json is Dictionary, "screenshot should be dictionary"):
+	if not T.require_eq(self, String((screenshot_json as Dictionary).get("format", "")), "png", "screenshot format mismatch"):
 		return -1
-	if not T.require_eq(self, String((screenshot_json as Dictionary).get("format", "")), "svg_data_url", "screenshot format mismatch"):
-		return -1
-	if not T.require_true(self, String((screenshot_json as Dictionary).get("data_url", "")).begins_with("data:image/svg+xml;base64,"), "screenshot data_url missing"):
+	var screenshot_bytes := FileAccess.get_file_as_bytes(screenshot_path)
+	if not T.require_true(self, screenshot_bytes.size() > 8 and screenshot_bytes[1] == 0x50, "screenshot file should be a PNG"):
 		return -1
 
 	var snapshot_path = "%s/snapshot_alias.json" % output_base
@@ -61,18 +59,18 @@ func _run_capture_storage_tabs_assertions(session: Node, pending: Dictionary, te
 	if not T.require_true(self, bool(snapshot_file.ok), "snapshot alias file missing"):
 		return -1
 
-	var pdf_path = "%s/page_dump.json" % output_base
+	var pdf_path = "%s/page.pdf" % output_base
 	var pdf_id = session.pdf(pdf_path)
 	var pdf_resp = await T.wait_for_completed(self, pending, pdf_id)
 	if not T.require_ok_response(self, pdf_resp, "pdf"):
 		return -1
-	var pdf_file = T.read_text_file(pdf_path)
-	if not T.require_true(self, bool(pdf_file.ok), "pdf output file missing"):
+	var pdf_json: Variant = T.parse_json_or_null(String(pdf_resp.result_json))
+	if not T.require_true(self, pdf_json is Dictionary, "pdf result should be dictionary"):
 		return -1
-	var pdf_json: Variant = T.parse_json_or_null(String(pdf_file.text))
-	if not T.require_true(self, pdf_json is Dictionary, "pdf output should be dictionary"):
+	if not T.require_eq(self, String((pdf_json as Dictionary).get("format", "")), "pdf", "pdf format mismatch"):
 		return -1
-	if not T.require_eq(self, String((pdf_json as Dictionary).get("format", "")), "html_json", "pdf format marker mismatch"):
+	var pdf_bytes := FileAccess.get_file_as_bytes(pdf_path)
+	if not T.require_true(self, pdf_bytes.size() > 4 and pdf_bytes.slice(0, 4).get_string_from_ascii() == "%PDF", "pdf output should be a PDF file"):
 		return -1
 
 	var set_cookie_id = session.cookie_set("session_cookie", "cookie_value")
@@ -89,6 +87,8 @@ func _run_capture_storage_tabs_assertions(session: Node, pending: Dictionary, te
 		return -1
 	if not T.require_eq(self, String((get_cookie_value as Dictionary).get("value", "")), "cookie_value", "cookie value mismatch"):
 		return -1
+	if not T.require_true(self, (get_cookie_value as Dictionary).has("httpOnly"), "cookie_get should return a native cookie record"):
+		return -1
 
 	var list_cookie_id = session.cookie_list("127.0.0.1")
 	var list_cookie_resp = await T.wait_for_completed(self, pending, list_cookie_id)
@@ -187,7 +187,9 @@ func _run_capture_storage_tabs_assertions(session: Node, pending: Dictionary, te
 	var state_value: Variant = T.parse_json_or_null(String(state_file.text))
 	if not T.require_true(self, state_value is Dictionary, "state_save payload should be dictionary"):
 		return -1
-	if not T.require_eq(self, String((state_value as Dictionary).get("schema", "")), "gwry-session-state-v1", "state schema mismatch"):
+	if not T.require_eq(self, int((state_value as Dictionary).get("version", 0)), 1, "state version mismatch"):
+		return -1
+	if not T.require_true(self, (state_value as Dictionary).get("origins", null) is Array, "state should list origins"):
 		return -1
 
 	var clear_ls_for_restore_id = session.localstorage_clear()
@@ -200,6 +202,11 @@ func _run_capture_storage_tabs_assertions(session: Node, pending: Dictionary, te
 	if not T.require_ok_response(self, state_load_resp, "state_load"):
 		return -1
 
+	var restore_reload_id = session.reload()
+	var restore_reload_resp = await T.wait_for_completed(self, pending, restore_reload_id)
+	if not T.require_ok_response(self, restore_reload_resp, "reload after state_load"):
+		return -1
+
 	var restored_ls_id = session.localstorage_get("ls_key")
 	var restored_ls_resp = await T.wait_for_completed(self, pending, restored_ls_id)
 	if not T.require_ok_response(self, restored_ls_resp, "localstorage_get after state_load"):
@@ -215,29 +222,39 @@ func _run_capture_storage_tabs_assertions(session: Node, pending: Dictionary, te
 	if not T.require_ok_response(self, tab_list_initial_resp, "tab_list initial"):
 		return -1
 	var tab_list_initial_value: Variant = T.parse_json_or_null(String(tab_list_initial_resp.result_json))
-	if not T.require_true(self, tab_list_initial_value is Dictionary, "tab_list should return dictionary"):
+	if not T.require_true(self, tab_list_initial_value is Array, "tab_list should return array"):
 		return -1
-	if not T.require_true(self, int((tab_list_initial_value as Dictionary).get("tab_count", 0)) >= 1, "initial tab count should be >=1"):
+	if not T.require_true(self, (tab_list_initial_value as Array).size() >= 1, "initial tab count should be >=1"):
 		return -1
+	var first_tab_id := int(((tab_list_initial_value as Array)[0] as Dictionary).get("id", -1))
 
 	var tab_new_id = session.tab_new(test_url)
 	var tab_new_resp = await T.wait_for_completed(self, pending, tab_new_id)
 	if not T.require_ok_response(self, tab_new_resp, "tab_new"):
 		return -1
+	var tab_new_value: Variant = T.parse_json_or_null(String(tab_new_resp.result_json))
+	if not T.require_true(self, tab_new_value is Dictionary, "tab_new should return dictionary"):
+		return -1
+	var new_tab_id := int((tab_new_value as Dictionary).get("id", -1))
 
 	var tab_list_after_new_id = session.tab_list()
 	var tab_list_after_new_resp = await T.wait_for_completed(self, pending, tab_list_after_new_id)
 	if not T.require_ok_response(self, tab_list_after_new_resp, "tab_list after new"):
 		return -1
 	var tab_after_new_value: Variant = T.parse_json_or_null(String(tab_list_after_new_resp.result_json))
-	if not T.require_true(self, tab_after_new_value is Dictionary, "tab_list after new should return dictionary"):
+	if not T.require_true(self, tab_after_new_value is Array, "tab_list after new should return array"):
+		return -1
+	if not T.require_true(self, (tab_after_new_value as Array).size() >= 2, "tab count should grow after tab_new"):
 		return -1
-	if not T.require_true(self, int((tab_after_new_value as Dictionary).get("tab_count", 0)) >= 2, "tab count should grow after tab_new"):
+
+	var tab_select_new_id = session.tab_select(new_tab_id)
+	var tab_select_new_resp = await T.wait_for_completed(self, pending, tab_select_new_id)
+	if not T.require_ok_response(self, tab_select_new_resp, "tab_select"):
 		return -1
 
-	var tab_select_first_id = session.tab_select(0)
-	var tab_select_first_resp = await T.wait_for_completed(self, pending, tab_select_first_id)
-	if not T.require_ok_response(self, tab_select_first_resp, "tab_select"):
+	var tab_select_missing_id = session.tab_select(9999)
+	var tab_select_missing_resp = await T.wait_for_completed(self, pending, tab_select_missing_id)
+	if not T.require_error_response(self, tab_select_missing_resp, "tab_select missing", "tab_not_found"):
 		return -1
 
 	var tab_close_id = session.tab_close(-1)
@@ -250,9 +267,15 @@ func _run_capture_storage_tabs_assertions(session: Node, pending: Dictionary, te
 	if not T.require_ok_response(self, tab_list_final_resp, "tab_list final"):
 		return -1
 	var tab_final_value: Variant = T.parse_json_or_null(String(tab_list_final_resp.result_json))
-	if not T.require_true(self, tab_final_value is Dictionary, "tab_list final should return dictionary"):
+	if not T.require_true(self, tab_final_value is Array, "tab_list final should return array"):
+		return -1
+	if not T.require_true(self, (tab_final_value as Array).size() >= 1, "final tab count should stay >=1"):
 		return -1
-	if not T.require_true(self, int((tab_final_value as Dictionary).get("tab_count", 0)) >= 1, "final tab count should stay >=1"):
+	var final_active_id := -1
+	for entry in (tab_final_value as Array):
+		if bool((entry as Dictionary).get("active", false)):
+			final_active_id = int((entry as Dictionary).get("id", -1))
+	if not T.require_eq(self, final_active_id, first_tab_id, "closing the selected tab should reactivate the first tab"):
 		return -1
 
 	var state_save_empty_id = session.state_save("")
@@ -81,15 +81,14 @@ def main() -> int:
         "class_name WryPwSession",
         "func _on_browser_completed(",
         "func _save_snapshot_to_file(",
-        "func _file_to_upload_entry(",
+        "func _collect_upload_paths(",
         "func _set_dialog_mode(",
         "func resize(",
         "_browser.set_view_rect",
         "_start_view_mode",
-        "window.__gwry_dialog",
-        "DataTransfer",
+        "set_dialog_policy(",
+        "set_input_files(",
         "_snapshot_save_map",
-        "Marshalls.raw_to_base64",
     ]
 
     for marker in required_markers:
@@ -112,7 +111,6 @@ def main() -> int:
         "resize_requires_view_mode",
         "upload_empty",
         "upload_target_not_file_input",
-        "upload_datatransfer_unavailable",
         "snapshot_filename_empty",
     ]
     for code in expected_errors:
@@ -24,8 +24,8 @@ def main() -> int:
     if "unsupported: dialog interception is not implemented yet" in text:
         return fail("dialog methods still marked unsupported")
 
-    if "__gwry_dialog" not in text:
-        return fail("dialog hook marker __gwry_dialog not found")
+    if "set_dialog_policy(" not in text:
+        return fail("dialog methods do not use the native set_dialog_policy")
 
     resize_match = re.search(r"func\s+resize\s*\([^\)]*\)\s*->\s*int:\n([\s\S]*?)(?:\nfunc\s+|\Z)", text)
     if not resize_match:
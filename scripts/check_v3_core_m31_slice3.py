@@ -25,8 +25,7 @@ def main() -> int:
 
     required_markers = [
         "func _save_snapshot_to_file",
-        "func _mime_from_extension",
-        "func _file_to_upload_entry",
+        "func _collect_upload_paths",
         "_snapshot_save_map",
     ]
 
@@ -34,8 +33,8 @@ def main() -> int:
         if marker not in text:
             return fail(f"missing marker: {marker}")
 
-    if "Marshalls.raw_to_base64" not in text:
-        return fail("upload path does not encode file bytes with Marshalls.raw_to_base64")
+    if "set_input_files(" not in text:
+        return fail("upload does not use the native set_input_files")
 
     print("PASS: M3.1 slice3 upload/snapshot semantics detected")
     return 0